@@ -1,18 +1,57 @@
 use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
-use std::io::{BufRead, BufReader, Write};
-use std::process::{Child, ChildStdin, ChildStdout, Command, Stdio};
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicU64, Ordering};
 use std::sync::{Arc, Mutex};
-use std::time::Duration;
-use tauri::{path::BaseDirectory, AppHandle, Emitter, Manager};
-use tokio::sync::oneshot;
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+use tauri::async_runtime::JoinHandle;
+use tauri::{path::BaseDirectory, AppHandle, Emitter, Manager, Runtime};
+use tokio::io::BufReader;
+use tokio::process::Child;
+use tokio::sync::{mpsc, oneshot, watch};
+use tracing::level_filters::LevelFilter;
+use tracing::{debug, error, info, instrument, trace, warn};
+
+use crate::commands::{MessageStreamEvent, ProviderEvent};
+use crate::bridge_script::{BridgeLocator, SCRIPT_PATH};
+use crate::config::{custom_config_dir, BridgeConfig};
+use crate::environment::{EnvPolicy, CONFIG_DIR_ENV, LOG_LEVEL_ENV};
+use crate::error::CommandError;
+use crate::framing::{read_frame, write_frame};
+use crate::node::NodeResolver;
+use crate::permissions::{PendingAction, PermissionBroker, PermissionMode};
+#[cfg(unix)]
+use crate::process;
+use crate::process::{PidFile, PIDFILE_NAME};
+use crate::redact::{redact_json, redact_text, redact_value};
+use crate::session::{Session, SessionClosedEvent, SessionManager, SessionState};
+#[cfg(unix)]
+use crate::transport::UnixSocketTransport;
+use crate::transport::{BridgeReader, BridgeTransport, BridgeWriter, StdioTransport, TcpTransport, TransportSpec};
 
 // ============================================================================
 // JSON-RPC Types
 // ============================================================================
 
+/// JSON-RPC version carried in every message
+const JSONRPC_VERSION: &str = "2.0";
+
+/// Version of the host <-> bridge protocol this app speaks. Bridges reporting
+/// a different major version in their `ready` notification are refused.
+pub const PROTOCOL_VERSION: &str = "2.0";
+
+/// Notification telling the bridge to abort the handler of a request nobody waits for anymore
+const CANCEL_REQUEST_METHOD: &str = "$/cancelRequest";
+
+/// Request answered by the bridge with its pid; used for heartbeats and latency
+const PING_METHOD: &str = "ping";
+
+/// Request asking the bridge to stop its provider CLIs, flush its state and exit
+const SHUTDOWN_METHOD: &str = "shutdown";
+
 #[derive(Debug, Clone, Serialize)]
 struct JsonRpcRequest {
+    jsonrpc: &'static str,
     id: u64,
     method: String,
     params: serde_json::Value,
@@ -20,394 +59,673 @@ struct JsonRpcRequest {
 
 #[derive(Debug, Clone, Deserialize)]
 struct JsonRpcResponse {
-    id: u64,
-    #[serde(skip_serializing_if = "Option::is_none")]
+    /// `null` when the bridge could not determine the request id (e.g. parse errors)
+    id: Option<u64>,
+    #[serde(default)]
     result: Option<serde_json::Value>,
-    #[serde(skip_serializing_if = "Option::is_none")]
-    error: Option<String>,
+    #[serde(default)]
+    error: Option<RpcError>,
+}
+
+/// Notification sent to the bridge; it gets no response
+#[derive(Debug, Clone, Serialize)]
+struct JsonRpcOutgoingNotification {
+    jsonrpc: &'static str,
+    method: String,
+    params: serde_json::Value,
 }
 
 #[derive(Debug, Clone, Deserialize)]
-struct JsonRpcEvent {
-    event: String,
-    data: serde_json::Value,
+struct JsonRpcNotification {
+    method: String,
+    #[serde(default)]
+    params: serde_json::Value,
 }
 
-// ============================================================================
-// Bridge Client
-// ============================================================================
+/// Error object of a JSON-RPC response
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct RpcError {
+    pub code: i64,
+    pub message: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub data: Option<serde_json::Value>,
+}
 
-type PendingRequest = oneshot::Sender<Result<serde_json::Value, String>>;
+/// Error codes used by the bridge (see `ErrorCode` in provider-bridge.ts)
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RpcErrorCode {
+    ParseError,
+    InvalidRequest,
+    MethodNotFound,
+    InvalidParams,
+    InternalError,
+    ProfileNotFound,
+    AuthRequired,
+    ProviderUnknown,
+    RequestCancelled,
+    Other(i64),
+}
 
-// Note: BridgeClient should be wrapped in Arc, not cloned directly
-pub struct BridgeClient {
-    child: Arc<Mutex<Option<Child>>>,
-    stdin: Arc<Mutex<Option<ChildStdin>>>,
-    next_id: Arc<Mutex<u64>>,
-    pending: Arc<Mutex<HashMap<u64, PendingRequest>>>,
-    app_handle: AppHandle,
-    ready: Arc<Mutex<bool>>,
+impl From<i64> for RpcErrorCode {
+    fn from(code: i64) -> Self {
+        match code {
+            -32700 => RpcErrorCode::ParseError,
+            -32600 => RpcErrorCode::InvalidRequest,
+            -32601 => RpcErrorCode::MethodNotFound,
+            -32602 => RpcErrorCode::InvalidParams,
+            -32603 => RpcErrorCode::InternalError,
+            -32001 => RpcErrorCode::ProfileNotFound,
+            -32002 => RpcErrorCode::AuthRequired,
+            -32003 => RpcErrorCode::ProviderUnknown,
+            -32800 => RpcErrorCode::RequestCancelled,
+            other => RpcErrorCode::Other(other),
+        }
+    }
 }
 
-impl BridgeClient {
-    /// Find the bridge service either from bundled resources or by searching up from current directory
-    fn find_bridge_service(app_handle: &AppHandle) -> Result<std::path::PathBuf, String> {
-        // First try the bundled resource path. This works for packaged builds.
-        if let Ok(resource_path) = app_handle
-            .path()
-            .resolve("dist/bridge/provider-bridge.js", BaseDirectory::Resource)
-        {
-            println!("Checking bundled bridge resource at: {:?}", resource_path);
-            if resource_path.exists() {
-                println!(
-                    "Found bridge service via resource resolver at: {:?}",
-                    resource_path
-                );
-                return Ok(resource_path);
+impl RpcError {
+    pub fn kind(&self) -> RpcErrorCode {
+        RpcErrorCode::from(self.code)
+    }
+}
+
+/// A message received from the bridge, classified by its shape
+#[derive(Debug)]
+enum IncomingMessage {
+    Response(JsonRpcResponse),
+    Notification(JsonRpcNotification),
+}
+
+impl IncomingMessage {
+    /// Parse one frame from the bridge, which is either a single message or a batch
+    fn parse_frame(frame: &str) -> Result<Vec<Self>, String> {
+        let value: serde_json::Value =
+            serde_json::from_str(frame).map_err(|e| format!("invalid JSON: {}", e))?;
+
+        match value {
+            serde_json::Value::Array(items) => items.into_iter().map(Self::from_value).collect(),
+            other => Ok(vec![Self::from_value(other)?]),
+        }
+    }
+
+    fn from_value(value: serde_json::Value) -> Result<Self, String> {
+        let object = value.as_object().ok_or("expected a JSON object")?;
+
+        if object.get("jsonrpc").and_then(|v| v.as_str()) != Some(JSONRPC_VERSION) {
+            return Err("missing or unsupported jsonrpc version".to_string());
+        }
+
+        if object.contains_key("method") {
+            if object.contains_key("id") {
+                return Err("requests from the bridge are not supported".to_string());
             }
+            return serde_json::from_value(value)
+                .map(IncomingMessage::Notification)
+                .map_err(|e| format!("invalid notification: {}", e));
         }
 
-        // Fallback to searching relative to current directory (useful for dev builds)
-        let mut current = std::env::current_dir()
-            .map_err(|e| format!("Failed to get current dir: {}", e))?;
+        if object.contains_key("id") && (object.contains_key("result") || object.contains_key("error")) {
+            return serde_json::from_value(value)
+                .map(IncomingMessage::Response)
+                .map_err(|e| format!("invalid response: {}", e));
+        }
 
-        println!("Starting bridge search from: {:?}", current);
+        Err("neither a response nor a notification".to_string())
+    }
+}
 
-        // Search up to 5 levels
-        for level in 0..5 {
-            let candidate = current.join("dist").join("bridge").join("provider-bridge.js");
+/// Whether a bridge speaking protocol `version` is compatible with this app
+pub fn protocol_compatible(version: &str) -> bool {
+    let major = |version: &str| version.split('.').next().unwrap_or_default().to_string();
+    major(version) == major(PROTOCOL_VERSION)
+}
 
-            println!("Level {}: Checking bridge path: {:?}", level, candidate);
+/// Check the protocol version announced in the bridge's `ready` notification
+fn check_protocol_version(params: &serde_json::Value) -> Result<(), BridgeError> {
+    let found = params.get("protocolVersion").and_then(|v| v.as_str());
 
-            if candidate.exists() {
-                println!("Found bridge service at: {:?}", candidate);
-                return Ok(candidate);
-            }
+    match found {
+        Some(version) if protocol_compatible(version) => Ok(()),
+        _ => Err(BridgeError::IncompatibleProtocol {
+            expected: PROTOCOL_VERSION.to_string(),
+            found: found.map(str::to_string),
+        }),
+    }
+}
 
-            // Go up one level
-            if let Some(parent) = current.parent() {
-                current = parent.to_path_buf();
-            } else {
-                break;
-            }
+// ============================================================================
+// Errors
+// ============================================================================
+
+/// Errors produced by requests to the bridge service
+#[derive(Debug, Clone)]
+pub enum BridgeError {
+    /// The bridge is not running or has not reported ready yet
+    Unavailable(String),
+    /// The bridge process went away before answering the request
+    Terminated,
+    /// No response arrived before the method's deadline
+    Timeout { method: String, after: Duration },
+    /// The request could not be serialized or written to the bridge
+    Io(String),
+    /// The bridge answered with a JSON-RPC error
+    Rpc(RpcError),
+    /// The bridge speaks a protocol version this app does not understand
+    IncompatibleProtocol { expected: String, found: Option<String> },
+}
+
+impl std::fmt::Display for BridgeError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            BridgeError::Unavailable(reason) => write!(f, "{}", reason),
+            BridgeError::Terminated => write!(
+                f,
+                "Bridge terminated before responding. It is being restarted automatically, please try again in a moment."
+            ),
+            BridgeError::Timeout { method, after } => write!(
+                f,
+                "Bridge request '{}' timed out after {} seconds",
+                method,
+                after.as_secs()
+            ),
+            BridgeError::Io(reason) => write!(f, "{}", reason),
+            BridgeError::Rpc(error) => write!(f, "{}", error.message),
+            BridgeError::IncompatibleProtocol { expected, found } => write!(
+                f,
+                "Bridge protocol version {} is not compatible with this app (expected {}). Please reinstall or rebuild the bridge.",
+                found.as_deref().unwrap_or("unknown"),
+                expected
+            ),
         }
+    }
+}
+
+impl std::error::Error for BridgeError {}
+
+// ============================================================================
+// Request Deadlines
+// ============================================================================
 
-        // If not found, provide helpful error message
-        let current_dir = std::env::current_dir()
-            .map(|p| p.display().to_string())
-            .unwrap_or_else(|_| "unknown".to_string());
+/// Per-method response deadlines for bridge requests
+#[derive(Debug, Clone)]
+pub struct RequestTimeouts {
+    default: Duration,
+    per_method: HashMap<String, Duration>,
+}
 
-        Err(format!(
-            "Could not find bridge service (dist/bridge/provider-bridge.js). \
-            Current directory: {}. \
-            Please ensure the project is built with 'npm run build' before running the Tauri app.",
-            current_dir
-        ))
+impl Default for RequestTimeouts {
+    fn default() -> Self {
+        let mut per_method = HashMap::new();
+        // Auth flows may touch the network or native CLIs
+        per_method.insert("checkAuth".to_string(), Duration::from_secs(60));
+        per_method.insert("getAuthOptions".to_string(), Duration::from_secs(60));
+        per_method.insert("loginWithApiKey".to_string(), Duration::from_secs(60));
+        per_method.insert("linkExistingCredential".to_string(), Duration::from_secs(60));
+        // Launching a provider CLI and generating a reply can take a while
+        per_method.insert("launch".to_string(), Duration::from_secs(120));
+        per_method.insert("sendMessage".to_string(), Duration::from_secs(600));
+        // A bridge that can't answer a heartbeat quickly is reported unhealthy
+        per_method.insert(PING_METHOD.to_string(), Duration::from_secs(5));
+        per_method.insert(SHUTDOWN_METHOD.to_string(), SHUTDOWN_GRACE);
+
+        Self {
+            default: Duration::from_secs(30),
+            per_method,
+        }
     }
+}
 
-    /// Create a new bridge client and start the Node.js bridge service
-    pub fn new(app_handle: AppHandle) -> Result<Self, String> {
-        // Find the bridge executable by searching up from current directory
-        let bridge_path = Self::find_bridge_service(&app_handle)?;
+impl RequestTimeouts {
+    /// Deadline applied to `method`
+    pub fn for_method(&self, method: &str) -> Duration {
+        self.per_method.get(method).copied().unwrap_or(self.default)
+    }
 
-        // Check if running in development or production
-        let node_cmd = if cfg!(target_os = "windows") {
-            "node.exe"
-        } else {
-            "node"
-        };
+    /// Override the deadline for a single method
+    pub fn set(&mut self, method: impl Into<String>, timeout: Duration) {
+        self.per_method.insert(method.into(), timeout);
+    }
 
-        println!("Starting bridge service at: {:?}", bridge_path);
+    /// Change the deadline used by methods without an override
+    pub fn set_default(&mut self, timeout: Duration) {
+        self.default = timeout;
+    }
+}
 
-        // Use user's home directory as working directory
-        // This ensures bridge can access native CLI tools and profile configurations
-        // regardless of where the app is installed
-        let working_dir = dirs::home_dir()
-            .ok_or("Failed to determine user home directory")?;
+// ============================================================================
+// Bridge Status
+// ============================================================================
 
-        println!("Setting bridge working directory to user home: {:?}", working_dir);
+/// Lifecycle states reported to the frontend through the `bridge-status` event
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum BridgeStatus {
+    Starting,
+    Ready,
+    Crashed,
+    Restarting,
+    /// The bridge's protocol version does not match; it will not be restarted
+    Incompatible,
+    /// The bridge is connected but stopped answering heartbeats
+    Unhealthy,
+}
 
-        // Spawn the Node.js bridge service with stderr piped for better error capture
-        let mut command = Command::new(node_cmd);
-        command
-            .arg(&bridge_path)
-            .current_dir(working_dir)
-            .stdin(Stdio::piped())
-            .stdout(Stdio::piped())
-            .stderr(Stdio::piped());
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct BridgeStatusEvent {
+    status: BridgeStatus,
+    /// Number of restarts since the last stable run (0 for the initial start)
+    attempt: u32,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    message: Option<String>,
+}
 
-        // On Windows, hide the console window
-        #[cfg(target_os = "windows")]
-        {
-            use std::os::windows::process::CommandExt;
-            const CREATE_NO_WINDOW: u32 = 0x08000000;
-            command.creation_flags(CREATE_NO_WINDOW);
-        }
-
-        let mut child = command
-            .spawn()
-            .map_err(|e| format!("Failed to spawn bridge service: {}", e))?;
-
-        let stdin = child.stdin.take().ok_or("Failed to get stdin")?;
-        let stdout = child.stdout.take().ok_or("Failed to get stdout")?;
-        let stderr = child.stderr.take().ok_or("Failed to get stderr")?;
-
-        let client = Self {
-            child: Arc::new(Mutex::new(Some(child))),
-            stdin: Arc::new(Mutex::new(Some(stdin))),
-            next_id: Arc::new(Mutex::new(1)),
-            pending: Arc::new(Mutex::new(HashMap::new())),
-            app_handle: app_handle.clone(),
-            ready: Arc::new(Mutex::new(false)),
-        };
+/// How long to wait for the `ready` event after spawning the bridge,
+/// and how long requests wait for a (re)starting bridge
+const READY_TIMEOUT: Duration = Duration::from_secs(10);
+/// First restart delay; doubled on every consecutive crash
+const RESTART_BACKOFF_BASE: Duration = Duration::from_millis(500);
+/// Upper bound for the restart delay
+const RESTART_BACKOFF_MAX: Duration = Duration::from_secs(30);
+/// A bridge that stayed up this long is considered stable and resets the backoff
+const STABLE_RUN: Duration = Duration::from_secs(60);
+/// How often a ready bridge is pinged
+const HEARTBEAT_INTERVAL: Duration = Duration::from_secs(15);
+/// How long the bridge may take to answer `shutdown`, i.e. to stop its provider CLIs and flush its state
+const SHUTDOWN_GRACE: Duration = Duration::from_secs(5);
+/// How long a stopping bridge gets to exit once its channel is closed, and again after SIGTERM
+const EXIT_GRACE: Duration = Duration::from_secs(2);
+
+fn restart_backoff(attempt: u32) -> Duration {
+    let factor = 1u32 << attempt.saturating_sub(1).min(16);
+    RESTART_BACKOFF_BASE
+        .saturating_mul(factor)
+        .min(RESTART_BACKOFF_MAX)
+}
 
-        // Start reading from stdout in a separate thread
-        client.start_reader(stdout);
+// ============================================================================
+// Bridge Health
+// ============================================================================
 
-        // Start reading from stderr in a separate thread
-        client.start_stderr_reader(stderr);
+/// Snapshot returned by the `bridge_health` command
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct BridgeHealth {
+    pub transport: String,
+    pub ready: bool,
+    /// Ready and answering heartbeats
+    pub healthy: bool,
+    /// Process id of the bridge; reported by the bridge itself for attached transports
+    pub pid: Option<u32>,
+    /// Time since the current connection was opened
+    pub uptime_ms: Option<u64>,
+    pub protocol_version: Option<String>,
+    pub pending_requests: usize,
+    /// Restarts since the app started
+    pub restart_count: u32,
+    /// Last lines the bridge wrote to stderr, oldest first
+    pub recent_stderr: Vec<String>,
+    pub last_error: Option<String>,
+    /// Round trip of the latest ping
+    pub latency_ms: Option<f64>,
+}
 
-        // Wait for ready event with timeout
-        let ready_clone = Arc::clone(&client.ready);
-        let start = std::time::Instant::now();
-        let timeout = Duration::from_secs(5);
+/// What the supervisor, the heartbeat and the handshake learned about the bridge
+#[derive(Debug, Default)]
+struct HealthState {
+    pid: Option<u32>,
+    connected_at: Option<Instant>,
+    protocol_version: Option<String>,
+    /// Restart attempt of the current run, as reported in `bridge-status`
+    attempt: u32,
+    restarts: u32,
+    last_error: Option<String>,
+    latency: Option<Duration>,
+    /// Consecutive heartbeats that timed out
+    missed_pings: u32,
+}
 
-        while start.elapsed() < timeout {
-            if *ready_clone.lock().unwrap() {
-                println!("Bridge service is ready!");
-                return Ok(client);
-            }
-            std::thread::sleep(Duration::from_millis(100));
+// ============================================================================
+// Bridge Client
+// ============================================================================
+
+type PendingRequest = oneshot::Sender<Result<serde_json::Value, BridgeError>>;
+
+/// Registration of a request that is still waiting for its response. Dropping
+/// it before the response arrived, e.g. because the command future was dropped
+/// or the request timed out, forgets the request and cancels it in the bridge.
+struct InFlight<'a> {
+    shared: &'a BridgeShared,
+    id: u64,
+}
+
+impl Drop for InFlight<'_> {
+    fn drop(&mut self) {
+        if self.shared.pending.lock().unwrap().remove(&self.id).is_none() {
+            return;
         }
 
-        // If we get here, bridge didn't send ready event
-        eprintln!("WARNING: Bridge service did not send ready event within 5 seconds");
-        eprintln!("The bridge may not be fully initialized. Some features may not work.");
+        debug!(id = self.id, "cancelling request");
+        if let Err(e) = self.shared.notify(CANCEL_REQUEST_METHOD, serde_json::json!({ "id": self.id })) {
+            warn!(id = self.id, "failed to cancel request: {}", e);
+        }
+    }
+}
 
-        Ok(client)
+/// Reject every outstanding request with `error`
+fn fail_pending(pending: &Mutex<HashMap<u64, PendingRequest>>, error: BridgeError) {
+    let drained: Vec<_> = pending.lock().unwrap().drain().collect();
+    if !drained.is_empty() {
+        warn!("failing {} in-flight request(s): {}", drained.len(), error);
+    }
+    for (_, sender) in drained {
+        let _ = sender.send(Err(error.clone()));
     }
+}
 
-    /// Start a background thread to read from bridge service stdout
-    fn start_reader(&self, stdout: ChildStdout) {
-        let pending = Arc::clone(&self.pending);
-        let app_handle = self.app_handle.clone();
-        let ready = Arc::clone(&self.ready);
-        let stdin_ref = Arc::clone(&self.stdin);
+/// Receives the events the bridge client sends to the frontend
+pub trait EventSink: Send + Sync {
+    fn emit_event(&self, event: &str, payload: serde_json::Value) -> Result<(), String>;
+}
 
-        std::thread::spawn(move || {
-            let reader = BufReader::new(stdout);
+impl<R: Runtime> EventSink for AppHandle<R> {
+    fn emit_event(&self, event: &str, payload: serde_json::Value) -> Result<(), String> {
+        self.emit(event, payload).map_err(|e| e.to_string())
+    }
+}
 
-            println!("[Rust Bridge] stdout reader thread started");
+/// State shared between the client and its supervisor task
+struct BridgeShared {
+    /// Feeds the writer task of the currently running bridge process
+    writer: Mutex<Option<mpsc::UnboundedSender<String>>>,
+    next_id: AtomicU64,
+    pending: Mutex<HashMap<u64, PendingRequest>>,
+    events: Arc<dyn EventSink>,
+    ready: watch::Sender<bool>,
+    transport: Arc<dyn BridgeTransport>,
+    shutdown: watch::Sender<bool>,
+    timeouts: Mutex<RequestTimeouts>,
+    /// Set when the handshake refused the bridge; returned by every later request
+    protocol_error: Mutex<Option<BridgeError>>,
+    sessions: Arc<SessionManager>,
+    permissions: Arc<PermissionBroker>,
+    health: Mutex<HealthState>,
+}
 
-            for line in reader.lines() {
-                match line {
-                    Ok(line) if !line.trim().is_empty() => {
-                        if let Err(e) = Self::handle_message(&line, &pending, &app_handle, &ready) {
-                            eprintln!("[Rust Bridge] Error handling message: {}", e);
-                        }
-                    }
-                    Ok(_) => {
-                        // Empty line, continue
-                    }
-                    Err(e) => {
-                        eprintln!("[Rust Bridge] ERROR: Failed to read line from bridge stdout: {}", e);
-                        eprintln!("[Rust Bridge] This usually means the bridge process stdout was closed");
-                        break;
-                    }
-                }
-            }
+// Note: BridgeClient should be wrapped in Arc, not cloned directly
+pub struct BridgeClient {
+    shared: Arc<BridgeShared>,
+    /// Completes once the supervisor stopped the bridge for good
+    supervisor: Mutex<Option<JoinHandle<()>>>,
+}
+
+impl BridgeClient {
+    /// Find a compatible bridge script: an explicit setting, else the bundled resource,
+    /// a development build above the working directory or the global npm package
+    fn find_bridge_service<R: Runtime>(app_handle: &AppHandle<R>, config: &BridgeConfig) -> Result<PathBuf, String> {
+        let bundled = app_handle.path().resolve(SCRIPT_PATH, BaseDirectory::Resource).ok();
+        BridgeLocator::new(config.path.as_ref().map(PathBuf::from), bundled)
+            .locate()
+            .map_err(|e| e.to_string())
+    }
 
-            println!("[Rust Bridge] Bridge stdout reader thread exiting - EOF reached");
+    /// Environment policy of a spawned bridge: the allowlist extended by `bridge.passEnv`,
+    /// plus the app's log level and custom config directory. The default directory is
+    /// left implicit; the bridge only migrates legacy config directories into it.
+    fn bridge_env(config: &BridgeConfig) -> EnvPolicy {
+        let mut policy = EnvPolicy::new(config.pass_env.clone())
+            .set(LOG_LEVEL_ENV, LevelFilter::current().to_string().to_lowercase());
+        if let Some(dir) = custom_config_dir() {
+            policy = policy.set(CONFIG_DIR_ENV, dir);
+        }
+        policy
+    }
 
-            // Mark stdin as closed so we know the process is dead
-            {
-                let mut stdin_guard = stdin_ref.lock().unwrap();
-                *stdin_guard = None;
-                println!("[Rust Bridge] Marked stdin as closed due to stdout EOF");
+    /// Build the transport selected by `MULTICODER_BRIDGE_TRANSPORT` or `config.json`
+    pub fn transport_from_config<R: Runtime>(
+        app_handle: &AppHandle<R>,
+        config: &BridgeConfig,
+    ) -> Result<Arc<dyn BridgeTransport>, String> {
+        let transport: Arc<dyn BridgeTransport> = match TransportSpec::select(config)? {
+            TransportSpec::Stdio => {
+                let bridge_path = Self::find_bridge_service(app_handle, config)?;
+                let resolver = NodeResolver::new(config.node.as_ref().map(PathBuf::from));
+                let pidfile = app_handle.path().app_data_dir().ok().map(|dir| PidFile::new(dir.join(PIDFILE_NAME)));
+                let env = Self::bridge_env(config).apply_current();
+                env.report();
+                Arc::new(StdioTransport::new(bridge_path, resolver, pidfile, env))
+            }
+            #[cfg(unix)]
+            TransportSpec::Unix(path) => Arc::new(UnixSocketTransport::new(path)),
+            #[cfg(not(unix))]
+            TransportSpec::Unix(_) => {
+                return Err("unix socket transport is not supported on this platform".to_string())
             }
+            TransportSpec::Tcp(addr) => Arc::new(TcpTransport::new(addr)),
+        };
+        Ok(transport)
+    }
+
+    /// Create a new bridge client and connect to the bridge through `transport`.
+    /// Returns immediately; use [`BridgeClient::wait_until_ready`] to await readiness.
+    pub fn new(
+        events: Arc<dyn EventSink>,
+        transport: Arc<dyn BridgeTransport>,
+        sessions: Arc<SessionManager>,
+        permissions: Arc<PermissionBroker>,
+    ) -> Result<Self, String> {
+        info!("starting bridge service via {}", transport.describe());
+
+        let shared = Arc::new(BridgeShared {
+            writer: Mutex::new(None),
+            next_id: AtomicU64::new(1),
+            pending: Mutex::new(HashMap::new()),
+            events,
+            ready: watch::channel(false).0,
+            transport,
+            shutdown: watch::channel(false).0,
+            timeouts: Mutex::new(RequestTimeouts::default()),
+            protocol_error: Mutex::new(None),
+            sessions,
+            permissions,
+            health: Mutex::new(HealthState::default()),
         });
+
+        // The supervisor owns the connection from here on: it connects (spawning
+        // the bridge for stdio), watches for exit and reconnects with backoff
+        let supervisor = tauri::async_runtime::spawn(Arc::clone(&shared).supervise());
+        tauri::async_runtime::spawn(Arc::clone(&shared).heartbeat());
+
+        Ok(Self {
+            shared,
+            supervisor: Mutex::new(Some(supervisor)),
+        })
     }
 
-    /// Start a background thread to read from bridge service stderr
-    fn start_stderr_reader(&self, stderr: std::process::ChildStderr) {
-        std::thread::spawn(move || {
-            let reader = BufReader::new(stderr);
+    /// Wait until the bridge has sent its `ready` event.
+    /// Returns `false` if it did not become ready within `timeout`.
+    pub async fn wait_until_ready(&self, timeout: Duration) -> bool {
+        let mut ready = self.shared.ready.subscribe();
+        tokio::time::timeout(timeout, BridgeShared::became_ready(&mut ready))
+            .await
+            .unwrap_or(false)
+    }
 
-            println!("[Rust Bridge] stderr reader thread started");
+    /// Handle a frame received from the bridge service
+    fn handle_message(shared: &BridgeShared, frame: &str) -> Result<(), String> {
+        trace!("received from bridge: {}", redact_json(frame));
 
-            for line in reader.lines() {
-                match line {
-                    Ok(line) if !line.trim().is_empty() => {
-                        eprintln!("[Bridge stderr] {}", line);
-                    }
-                    Ok(_) => {
-                        // Empty line, continue
-                    }
-                    Err(e) => {
-                        eprintln!("[Rust Bridge] ERROR: Failed to read line from bridge stderr: {}", e);
-                        eprintln!("[Rust Bridge] This usually means the bridge process stderr was closed");
-                        break;
-                    }
+        for message in IncomingMessage::parse_frame(frame)? {
+            match message {
+                IncomingMessage::Response(response) => Self::handle_response(shared, response),
+                IncomingMessage::Notification(notification) => {
+                    Self::handle_notification(shared, notification)
                 }
             }
+        }
 
-            println!("[Rust Bridge] Bridge stderr reader thread exiting - EOF reached");
-        });
+        Ok(())
     }
 
-    /// Handle a message from the bridge service
-    fn handle_message(
-        line: &str,
-        pending: &Arc<Mutex<HashMap<u64, PendingRequest>>>,
-        app_handle: &AppHandle,
-        ready: &Arc<Mutex<bool>>,
-    ) -> Result<(), String> {
-        println!("[Rust Bridge] Handling message from bridge: {}", if line.len() > 100 { &line[..100] } else { line });
-
-        // Try parsing as response first
-        if let Ok(response) = serde_json::from_str::<JsonRpcResponse>(line) {
-            println!("[Rust Bridge] Parsed as JSON-RPC response, id={}", response.id);
-            let mut pending_map = pending.lock().unwrap();
-            if let Some(sender) = pending_map.remove(&response.id) {
-                println!("[Rust Bridge] Found pending request for id={}", response.id);
-                let result = if let Some(error) = response.error {
-                    eprintln!("[Rust Bridge] Response contains error: {}", error);
-                    Err(error)
-                } else {
-                    println!("[Rust Bridge] Response contains result");
-                    Ok(response.result.unwrap_or(serde_json::Value::Null))
-                };
-                let _ = sender.send(result);
-                println!("[Rust Bridge] Sent result to waiting request");
+    /// Resolve the pending request a response belongs to
+    fn handle_response(shared: &BridgeShared, response: JsonRpcResponse) {
+        let Some(id) = response.id else {
+            // The bridge could not tell which request failed, e.g. a parse error
+            warn!("bridge reported an error without request id: {}", redact_text(&format!("{:?}", response.error)));
+            return;
+        };
+
+        let sender = shared.pending.lock().unwrap().remove(&id);
+        if let Some(sender) = sender {
+            let result = if let Some(error) = response.error {
+                debug!(id, "response contains error {}: {}", error.code, redact_text(&error.message));
+                Err(BridgeError::Rpc(error))
             } else {
-                eprintln!("[Rust Bridge] WARNING: No pending request found for id={}", response.id);
-            }
-            return Ok(());
+                Ok(response.result.unwrap_or(serde_json::Value::Null))
+            };
+            let _ = sender.send(result);
+        } else {
+            // Usually the answer to a request that timed out or was cancelled
+            debug!(id, "no pending request for response");
         }
+    }
 
-        // Try parsing as event
-        if let Ok(event) = serde_json::from_str::<JsonRpcEvent>(line) {
-            println!("[Rust Bridge] Parsed as event: {}", event.event);
-            match event.event.as_str() {
-                "ready" => {
-                    println!("Bridge service ready: {:?}", event.data);
-                    *ready.lock().unwrap() = true;
+    /// Act on a notification sent by the bridge
+    fn handle_notification(shared: &BridgeShared, notification: JsonRpcNotification) {
+        trace!(method = %notification.method, "notification from bridge");
+        match notification.method.as_str() {
+            "ready" => {
+                info!("bridge service ready: {}", redact_value(&notification.params));
+                shared.health.lock().unwrap().protocol_version = notification
+                    .params
+                    .get("protocolVersion")
+                    .and_then(|v| v.as_str())
+                    .map(str::to_string);
+                match check_protocol_version(&notification.params) {
+                    Ok(()) => {
+                        shared.ready.send_replace(true);
+                    }
+                    Err(e) => {
+                        error!("refusing bridge: {}", e);
+                        shared.health.lock().unwrap().last_error = Some(e.to_string());
+                        shared.emit_status(BridgeStatus::Incompatible, 0, Some(e.to_string()));
+                        *shared.protocol_error.lock().unwrap() = Some(e.clone());
+                        // Restarting the same script would not help; stop it for good
+                        shared.shutdown.send_replace(true);
+                        fail_pending(&shared.pending, e);
+                    }
                 }
-                "message" => {
-                    println!("[Rust Bridge] Forwarding message event to frontend");
-                    // Forward message event to frontend
-                    if let Err(e) = app_handle.emit("message-stream", event.data) {
-                        eprintln!("Failed to emit message-stream event: {}", e);
+            }
+            "message" => {
+                match serde_json::from_value::<MessageStreamEvent>(notification.params) {
+                    Ok(mut message) => {
+                        if matches!(message.event, ProviderEvent::Done) {
+                            shared.sessions.set_state(&message.session_id, SessionState::Idle);
+                        }
+                        if let ProviderEvent::Ask { .. } = message.event {
+                            match shared.broker_ask(&message) {
+                                Some(event) => message.event = event,
+                                None => return,
+                            }
+                        }
+                        // Forward message event to frontend
+                        shared.emit("message-stream", message);
+                    }
+                    Err(e) => {
+                        warn!("dropping malformed message event: {}", e);
                     }
                 }
-                _ => {
-                    println!("Unknown event: {}", event.event);
+            }
+            "sessionClosed" => {
+                let session_id = notification.params.get("sessionId").and_then(|v| v.as_str());
+                let reason = notification
+                    .params
+                    .get("reason")
+                    .and_then(|v| v.as_str())
+                    .unwrap_or("closed by bridge");
+                if let Some(session_id) = session_id {
+                    shared.forget_session(session_id, reason);
                 }
             }
-            return Ok(());
+            _ => {
+                warn!("unknown notification: {}", notification.method);
+            }
         }
+    }
 
-        // If we get here, it's an unknown message format
-        eprintln!("[Rust Bridge] Unknown message format: {}", line);
-        Ok(())
+    /// Record a session the bridge launched. The bridge keeps one session per
+    /// profile, so the profile's previous session is gone and reported closed.
+    pub fn track_session(&self, session: Session) {
+        for replaced in self.shared.sessions.upsert(session) {
+            self.shared.permissions.forget_session(&replaced.id);
+            self.shared.emit_session_closed(replaced, "replaced by a new session");
+        }
     }
 
-    /// Check if bridge process is still alive and ready
-    fn is_alive(&self) -> bool {
-        // Check if process is running
-        let process_alive = {
-            let child_guard = self.child.lock().unwrap();
-            if let Some(_child) = child_guard.as_ref() {
-                self.stdin.lock().unwrap().is_some()
-            } else {
-                false
-            }
-        };
+    /// Drop a session the bridge no longer runs, together with its permission
+    /// requests and grants, and report it closed unless that already happened
+    pub fn forget_session(&self, session_id: &str, reason: &str) {
+        self.shared.forget_session(session_id, reason);
+    }
 
-        // Check if bridge sent ready event
-        let is_ready = *self.ready.lock().unwrap();
+    /// Check if bridge process is still alive and ready
+    pub fn is_alive(&self) -> bool {
+        let process_alive = self.shared.writer.lock().unwrap().is_some();
+        let is_ready = *self.shared.ready.borrow();
 
         if process_alive && !is_ready {
-            eprintln!("Bridge process is running but not ready yet");
+            debug!("bridge process is running but not ready yet");
         }
 
         process_alive && is_ready
     }
 
-    /// Send a request to the bridge service
-    async fn send_request(
-        &self,
-        method: String,
-        params: serde_json::Value,
-    ) -> Result<serde_json::Value, String> {
-        println!("[Rust Bridge] Sending request: method={}", method);
-
-        // Check if bridge is alive before sending
-        if !self.is_alive() {
-            eprintln!("[Rust Bridge] ERROR: Bridge is not alive!");
-            return Err("Bridge process is not running. Please restart the application.".to_string());
-        }
-
-        let id = {
-            let mut next_id = self.next_id.lock().unwrap();
-            let id = *next_id;
-            *next_id += 1;
-            id
-        };
-
-        println!("[Rust Bridge] Request ID: {}", id);
-
-        let request = JsonRpcRequest { id, method: method.clone(), params };
+    /// Override the response deadline for a bridge method
+    pub fn set_request_timeout(&self, method: &str, timeout: Duration) {
+        self.shared.timeouts.lock().unwrap().set(method, timeout);
+    }
 
-        let (tx, rx) = oneshot::channel();
+    /// Change the response deadline of methods without one of their own
+    pub fn set_default_request_timeout(&self, timeout: Duration) {
+        self.shared.timeouts.lock().unwrap().set_default(timeout);
+    }
 
-        // Register the pending request
-        {
-            let mut pending = self.pending.lock().unwrap();
-            pending.insert(id, tx);
-            println!("[Rust Bridge] Registered pending request {}", id);
+    /// Fail fast when the bridge was refused, otherwise give a (re)starting bridge a moment to come up
+    async fn ensure_available(&self) -> Result<(), BridgeError> {
+        if let Some(error) = self.shared.protocol_error.lock().unwrap().clone() {
+            return Err(error);
         }
 
-        // Send the request
-        {
-            let mut stdin = self.stdin.lock().unwrap();
-            if let Some(stdin) = stdin.as_mut() {
-                let json = serde_json::to_string(&request).map_err(|e| e.to_string())?;
-                println!("[Rust Bridge] Writing to stdin: {}", json);
-                match writeln!(stdin, "{}", json) {
-                    Ok(_) => {
-                        println!("[Rust Bridge] Write successful");
-                    },
-                    Err(e) => {
-                        eprintln!("[Rust Bridge] ERROR: Write failed: {}", e);
-                        // Remove pending request on write error
-                        self.pending.lock().unwrap().remove(&id);
-                        return Err(format!("Bridge process closed unexpectedly: {}. Please check the bridge service logs and restart the application.", e));
-                    }
-                }
-                match stdin.flush() {
-                    Ok(_) => {
-                        println!("[Rust Bridge] Flush successful");
-                    },
-                    Err(e) => {
-                        eprintln!("[Rust Bridge] ERROR: Flush failed: {}", e);
-                        // Remove pending request on flush error
-                        self.pending.lock().unwrap().remove(&id);
-                        return Err(format!("Bridge process closed unexpectedly: {}. Please check the bridge service logs and restart the application.", e));
-                    }
-                }
-            } else {
-                eprintln!("[Rust Bridge] ERROR: stdin not available");
-                return Err("Bridge stdin not available. Please restart the application.".to_string());
+        if !self.is_alive() && !self.wait_until_ready(READY_TIMEOUT).await {
+            error!("bridge is not available");
+            if let Some(error) = self.shared.protocol_error.lock().unwrap().clone() {
+                return Err(error);
             }
+            return Err(BridgeError::Unavailable(
+                "Bridge process is not running. It is being restarted automatically, please try again in a moment.".to_string(),
+            ));
         }
 
-        println!("[Rust Bridge] Waiting for response to request {}...", id);
+        Ok(())
+    }
 
-        // Wait for response
-        let result = rx.await.map_err(|_| "Request cancelled".to_string())?;
-        println!("[Rust Bridge] Received response for request {}", id);
-        result
+    /// Send a request to the bridge service
+    async fn send_request(
+        &self,
+        method: String,
+        params: serde_json::Value,
+    ) -> Result<serde_json::Value, CommandError> {
+        Ok(self.call(method, params).await?)
+    }
+
+    /// Send a request and wait for its response, reporting transport level errors
+    async fn call(
+        &self,
+        method: String,
+        params: serde_json::Value,
+    ) -> Result<serde_json::Value, BridgeError> {
+        self.ensure_available().await?;
+        self.shared.call(method, params).await
     }
 
     /// Launch a provider session
@@ -416,7 +734,7 @@ impl BridgeClient {
         profile: String,
         provider: String,
         config: serde_json::Value,
-    ) -> Result<serde_json::Value, String> {
+    ) -> Result<serde_json::Value, CommandError> {
         self.send_request(
             "launch".to_string(),
             serde_json::json!({
@@ -433,7 +751,7 @@ impl BridgeClient {
         &self,
         profile: String,
         message: String,
-    ) -> Result<serde_json::Value, String> {
+    ) -> Result<serde_json::Value, CommandError> {
         self.send_request(
             "sendMessage".to_string(),
             serde_json::json!({
@@ -445,7 +763,7 @@ impl BridgeClient {
     }
 
     /// Stop a provider session
-    pub async fn stop(&self, profile: String) -> Result<serde_json::Value, String> {
+    pub async fn stop(&self, profile: String) -> Result<serde_json::Value, CommandError> {
         self.send_request(
             "stop".to_string(),
             serde_json::json!({
@@ -455,8 +773,56 @@ impl BridgeClient {
         .await
     }
 
+    /// Answer a permission request raised by a profile's session
+    pub async fn respond_permission(
+        &self,
+        profile: String,
+        action_id: String,
+        approved: bool,
+    ) -> Result<serde_json::Value, CommandError> {
+        // Session grants are kept by the `PermissionBroker` for the exact command or
+        // path; the bridge would allow the provider's whole tool
+        self.send_request(
+            "respondPermission".to_string(),
+            serde_json::json!({
+                "profile": profile,
+                "actionId": action_id,
+                "approved": approved,
+                "applyToSession": false,
+            }),
+        )
+        .await
+    }
+
+    /// Update and persist the permission mode of a profile
+    pub async fn set_permission_mode(
+        &self,
+        profile: String,
+        mode: PermissionMode,
+    ) -> Result<serde_json::Value, CommandError> {
+        self.send_request(
+            "setPermissionMode".to_string(),
+            serde_json::json!({
+                "profile": profile,
+                "mode": mode.as_str(),
+            }),
+        )
+        .await
+    }
+
+    /// Close a profile's provider session
+    pub async fn close_session(&self, profile: String) -> Result<serde_json::Value, CommandError> {
+        self.send_request(
+            "closeSession".to_string(),
+            serde_json::json!({
+                "profile": profile,
+            }),
+        )
+        .await
+    }
+
     /// List available providers
-    pub async fn list_providers(&self) -> Result<serde_json::Value, String> {
+    pub async fn list_providers(&self) -> Result<serde_json::Value, CommandError> {
         self.send_request("listProviders".to_string(), serde_json::json!({}))
             .await
     }
@@ -466,7 +832,7 @@ impl BridgeClient {
         &self,
         provider: String,
         profile_name: String,
-    ) -> Result<serde_json::Value, String> {
+    ) -> Result<serde_json::Value, CommandError> {
         self.send_request(
             "checkAuth".to_string(),
             serde_json::json!({
@@ -478,7 +844,7 @@ impl BridgeClient {
     }
 
     /// List all profiles
-    pub async fn list_profiles(&self) -> Result<serde_json::Value, String> {
+    pub async fn list_profiles(&self) -> Result<serde_json::Value, CommandError> {
         self.send_request("listProfiles".to_string(), serde_json::json!({}))
             .await
     }
@@ -488,7 +854,7 @@ impl BridgeClient {
         &self,
         name: String,
         provider: String,
-    ) -> Result<serde_json::Value, String> {
+    ) -> Result<serde_json::Value, CommandError> {
         self.send_request(
             "createProfile".to_string(),
             serde_json::json!({
@@ -500,7 +866,7 @@ impl BridgeClient {
     }
 
     /// Switch to a different profile
-    pub async fn switch_profile(&self, profile_id: String) -> Result<serde_json::Value, String> {
+    pub async fn switch_profile(&self, profile_id: String) -> Result<serde_json::Value, CommandError> {
         self.send_request(
             "switchProfile".to_string(),
             serde_json::json!({
@@ -511,7 +877,7 @@ impl BridgeClient {
     }
 
     /// Delete a profile
-    pub async fn delete_profile(&self, profile_id: String) -> Result<serde_json::Value, String> {
+    pub async fn delete_profile(&self, profile_id: String) -> Result<serde_json::Value, CommandError> {
         self.send_request(
             "deleteProfile".to_string(),
             serde_json::json!({
@@ -522,11 +888,17 @@ impl BridgeClient {
     }
 
     /// Get current profile
-    pub async fn get_current_profile(&self) -> Result<serde_json::Value, String> {
+    pub async fn get_current_profile(&self) -> Result<serde_json::Value, CommandError> {
         self.send_request("getCurrentProfile".to_string(), serde_json::json!({}))
             .await
     }
 
+    /// Re-read profiles.json after it was changed by another process
+    pub async fn reload_profiles(&self) -> Result<serde_json::Value, CommandError> {
+        self.send_request("reloadProfiles".to_string(), serde_json::json!({}))
+            .await
+    }
+
     /// Login with API key
     pub async fn login_with_api_key(
         &self,
@@ -534,7 +906,7 @@ impl BridgeClient {
         provider: String,
         api_key: String,
         metadata: Option<serde_json::Value>,
-    ) -> Result<serde_json::Value, String> {
+    ) -> Result<serde_json::Value, CommandError> {
         let mut params = serde_json::json!({
             "profileName": profile_name,
             "provider": provider,
@@ -554,7 +926,7 @@ impl BridgeClient {
         &self,
         profile_name: String,
         provider: String,
-    ) -> Result<serde_json::Value, String> {
+    ) -> Result<serde_json::Value, CommandError> {
         self.send_request(
             "getAuthOptions".to_string(),
             serde_json::json!({
@@ -570,7 +942,7 @@ impl BridgeClient {
         &self,
         profile_name: String,
         provider: String,
-    ) -> Result<serde_json::Value, String> {
+    ) -> Result<serde_json::Value, CommandError> {
         self.send_request(
             "linkExistingCredential".to_string(),
             serde_json::json!({
@@ -581,24 +953,558 @@ impl BridgeClient {
         .await
     }
 
-    /// Shutdown the bridge service
-    pub fn shutdown(&self) {
-        println!("[Rust Bridge] shutdown() called - killing bridge process");
-        if let Some(mut child) = self.child.lock().unwrap().take() {
-            println!("[Rust Bridge] Killing bridge process...");
-            let _ = child.kill();
-            let _ = child.wait();
-            println!("[Rust Bridge] Bridge process killed");
-        } else {
-            println!("[Rust Bridge] No child process to kill");
+    /// Ping the bridge if it is ready and report its state
+    pub async fn health(&self) -> BridgeHealth {
+        if *self.shared.ready.borrow() {
+            // A failed ping is recorded as the last error
+            let _ = self.shared.ping().await;
+        }
+
+        let health = self.shared.health.lock().unwrap();
+        let ready = *self.shared.ready.borrow();
+        BridgeHealth {
+            transport: self.shared.transport.describe(),
+            ready,
+            healthy: ready && health.missed_pings == 0,
+            pid: health.pid,
+            uptime_ms: health.connected_at.map(|at| at.elapsed().as_millis() as u64),
+            protocol_version: health.protocol_version.clone(),
+            pending_requests: self.shared.pending.lock().unwrap().len(),
+            restart_count: health.restarts,
+            recent_stderr: self.shared.transport.recent_stderr(),
+            last_error: health.last_error.clone(),
+            latency_ms: health.latency.map(|latency| latency.as_secs_f64() * 1000.0),
+        }
+    }
+
+    /// Stop the bridge for good. A ready bridge is asked to stop its provider CLIs
+    /// and flush its state first; a process that does not exit afterwards is sent
+    /// SIGTERM and finally killed. Returns once it is gone.
+    pub async fn shutdown(&self) {
+        info!("shutting down bridge");
+        let alive = self.is_alive();
+        // First, so the supervisor does not take the bridge closing the connection
+        // after its answer for a crash and restart it
+        self.request_stop();
+        if alive {
+            if let Err(e) = self.shared.call(SHUTDOWN_METHOD.to_string(), serde_json::json!({})).await {
+                warn!("bridge did not shut down cleanly: {}", e);
+            }
+        }
+
+        let supervisor = self.supervisor.lock().unwrap().take();
+        if let Some(supervisor) = supervisor {
+            let _ = supervisor.await;
+        }
+        info!("bridge stopped");
+    }
+
+    /// Tell the supervisor to stop the bridge without waiting for it. A ready
+    /// bridge gets `SHUTDOWN_GRACE` to answer `shutdown` and close the connection.
+    fn request_stop(&self) {
+        self.shared.shutdown.send_replace(true);
+    }
+}
+
+/// Why a single run of the bridge process ended
+enum RunOutcome {
+    Exited(String),
+    ChannelClosed,
+    Shutdown,
+}
+
+impl BridgeShared {
+    /// Keep the bridge process running until shutdown is requested
+    async fn supervise(self: Arc<Self>) {
+        debug!("supervisor task started");
+
+        let mut shutdown = self.shutdown.subscribe();
+        let mut attempt: u32 = 0;
+
+        while !*shutdown.borrow() {
+            self.health.lock().unwrap().attempt = attempt;
+            self.emit_status(
+                if attempt == 0 { BridgeStatus::Starting } else { BridgeStatus::Restarting },
+                attempt,
+                None,
+            );
+
+            let started_at = Instant::now();
+            let exit_reason = match self.connect().await {
+                Ok((child, reader)) => self.run(child, reader, attempt, &mut shutdown).await,
+                Err(e) => e,
+            };
+            self.transport.disconnected();
+
+            self.mark_stopped();
+
+            if *shutdown.borrow() {
+                break;
+            }
+
+            error!("bridge process stopped: {}", exit_reason);
+            self.health.lock().unwrap().last_error = Some(exit_reason.clone());
+            self.emit_status(BridgeStatus::Crashed, attempt, Some(exit_reason));
+
+            if started_at.elapsed() >= STABLE_RUN {
+                attempt = 0;
+            }
+            attempt += 1;
+            self.health.lock().unwrap().restarts += 1;
+
+            let delay = restart_backoff(attempt);
+            info!(attempt, "restarting bridge in {:?}", delay);
+            tokio::select! {
+                _ = tokio::time::sleep(delay) => {}
+                _ = shutdown.changed() => {}
+            }
+        }
+
+        debug!("supervisor task exiting");
+    }
+
+    /// Open a connection through the transport and attach its reader and writer tasks.
+    /// The returned reader handle completes when the channel reaches EOF.
+    async fn connect(self: &Arc<Self>) -> Result<(Option<Child>, JoinHandle<()>), String> {
+        info!("connecting to bridge via {}", self.transport.describe());
+        let connection = self.transport.connect().await?;
+
+        self.ready.send_replace(false);
+        {
+            let mut health = self.health.lock().unwrap();
+            health.pid = connection.child.as_ref().and_then(Child::id);
+            health.connected_at = Some(Instant::now());
+        }
+
+        let (writer_tx, writer_rx) = mpsc::unbounded_channel();
+        *self.writer.lock().unwrap() = Some(writer_tx);
+        tauri::async_runtime::spawn(Self::write_requests(connection.writer, writer_rx));
+
+        let shared = Arc::clone(self);
+        let reader = tauri::async_runtime::spawn(async move { shared.read_messages(connection.reader).await });
+
+        Ok((connection.child, reader))
+    }
+
+    /// Wait for the running bridge to report ready and then to stop
+    async fn run(
+        &self,
+        mut child: Option<Child>,
+        mut reader: JoinHandle<()>,
+        attempt: u32,
+        shutdown: &mut watch::Receiver<bool>,
+    ) -> String {
+        let mut ready = self.ready.subscribe();
+        let ready_deadline = tokio::time::sleep(READY_TIMEOUT);
+        tokio::pin!(ready_deadline);
+        let mut waiting_for_ready = true;
+
+        let outcome = loop {
+            tokio::select! {
+                reason = Self::wait_for_exit(&mut child) => break RunOutcome::Exited(reason),
+                _ = &mut reader => break RunOutcome::ChannelClosed,
+                _ = shutdown.changed() => break RunOutcome::Shutdown,
+                is_ready = Self::became_ready(&mut ready), if waiting_for_ready => {
+                    waiting_for_ready = false;
+                    if is_ready {
+                        info!("bridge service is ready");
+                        self.emit_status(BridgeStatus::Ready, attempt, None);
+                    }
+                }
+                _ = &mut ready_deadline, if waiting_for_ready => {
+                    waiting_for_ready = false;
+                    warn!(
+                        "bridge service did not send ready event within {} seconds",
+                        READY_TIMEOUT.as_secs()
+                    );
+                }
+            }
+        };
+
+        if matches!(outcome, RunOutcome::Shutdown) && *self.ready.borrow() {
+            // A ready bridge is sent `shutdown`, which it answers before closing the connection
+            let _ = tokio::time::timeout(SHUTDOWN_GRACE, &mut reader).await;
+        }
+
+        match (outcome, child) {
+            (RunOutcome::Exited(reason), _) => reason,
+            (RunOutcome::ChannelClosed, Some(mut child)) => {
+                // Give the process a moment to exit so we can report its status
+                match tokio::time::timeout(Duration::from_secs(1), child.wait()).await {
+                    Ok(Ok(status)) => format!("channel closed, process exited with {}", status),
+                    _ => {
+                        let _ = child.kill().await;
+                        "channel closed".to_string()
+                    }
+                }
+            }
+            (RunOutcome::ChannelClosed, None) => "connection closed by the bridge".to_string(),
+            (RunOutcome::Shutdown, Some(mut child)) => {
+                // Closing the channel makes the bridge exit if `shutdown` did not
+                self.writer.lock().unwrap().take();
+                Self::stop_process(&mut child).await;
+                "shutdown requested".to_string()
+            }
+            // An attached bridge keeps running; dropping the connection detaches from it
+            (RunOutcome::Shutdown, None) => "shutdown requested".to_string(),
+        }
+    }
+
+    /// Wait for the bridge process to exit; never completes for attached bridges
+    async fn wait_for_exit(child: &mut Option<Child>) -> String {
+        match child {
+            Some(child) => match child.wait().await {
+                Ok(status) => format!("process exited with {}", status),
+                Err(e) => format!("failed to query process status: {}", e),
+            },
+            None => std::future::pending().await,
+        }
+    }
+
+    /// Give a stopping bridge `EXIT_GRACE` to exit, then send SIGTERM and finally
+    /// SIGKILL to its process group, which includes the provider CLIs it started
+    async fn stop_process(child: &mut Child) {
+        if let Ok(Ok(status)) = tokio::time::timeout(EXIT_GRACE, child.wait()).await {
+            info!("bridge process exited with {}", status);
+            return;
+        }
+
+        #[cfg(unix)]
+        if let Some(pid) = child.id() {
+            warn!("bridge did not exit within {:?}, sending SIGTERM", EXIT_GRACE);
+            process::signal_group(pid, libc::SIGTERM);
+            if let Ok(Ok(status)) = tokio::time::timeout(EXIT_GRACE, child.wait()).await {
+                info!("bridge process exited with {}", status);
+                return;
+            }
+            process::signal_group(pid, libc::SIGKILL);
+        }
+
+        warn!("killing bridge process");
+        let _ = child.kill().await;
+    }
+
+    async fn became_ready(ready: &mut watch::Receiver<bool>) -> bool {
+        ready.wait_for(|ready| *ready).await.is_ok()
+    }
+
+    /// Write queued requests to the bridge, one JSON document per frame
+    async fn write_requests(mut writer: BridgeWriter, mut requests: mpsc::UnboundedReceiver<String>) {
+        while let Some(json) = requests.recv().await {
+            trace!("sending to bridge: {}", redact_json(&json));
+            if let Err(e) = write_frame(&mut writer, json.as_bytes()).await {
+                error!("write to bridge failed, it probably closed the channel: {}", e);
+                break;
+            }
+        }
+
+        debug!("writer task exiting");
+    }
+
+    /// Ping a ready bridge every `HEARTBEAT_INTERVAL` until shutdown and report it
+    /// unhealthy while pings time out
+    async fn heartbeat(self: Arc<Self>) {
+        let mut shutdown = self.shutdown.subscribe();
+        let mut interval = tokio::time::interval(HEARTBEAT_INTERVAL);
+        interval.set_missed_tick_behavior(tokio::time::MissedTickBehavior::Delay);
+
+        loop {
+            tokio::select! {
+                _ = interval.tick() => {}
+                _ = shutdown.changed() => {}
+            }
+            if *shutdown.borrow() {
+                break;
+            }
+            if !*self.ready.borrow() {
+                continue;
+            }
+
+            match self.ping().await {
+                Ok(_) => {
+                    let (missed, attempt) = {
+                        let mut health = self.health.lock().unwrap();
+                        (std::mem::take(&mut health.missed_pings), health.attempt)
+                    };
+                    if missed > 0 {
+                        info!("bridge answers heartbeats again after {} missed", missed);
+                        self.emit_status(BridgeStatus::Ready, attempt, None);
+                    }
+                }
+                Err(e @ BridgeError::Timeout { .. }) => {
+                    let (missed, attempt) = {
+                        let mut health = self.health.lock().unwrap();
+                        health.missed_pings += 1;
+                        (health.missed_pings, health.attempt)
+                    };
+                    warn!(missed, "bridge did not answer heartbeat");
+                    if missed == 1 {
+                        self.emit_status(BridgeStatus::Unhealthy, attempt, Some(e.to_string()));
+                    }
+                }
+                // Lost connections are handled by the supervisor
+                Err(e) => debug!("heartbeat failed: {}", e),
+            }
+        }
+
+        debug!("heartbeat task exiting");
+    }
+
+    /// Send a `ping` and record its round trip
+    async fn ping(&self) -> Result<Duration, BridgeError> {
+        let started = Instant::now();
+        let result = self.call(PING_METHOD.to_string(), serde_json::json!({})).await;
+
+        let mut health = self.health.lock().unwrap();
+        match result {
+            Ok(pong) => {
+                let latency = started.elapsed();
+                health.latency = Some(latency);
+                if health.pid.is_none() {
+                    health.pid = pong.get("pid").and_then(|pid| pid.as_u64()).map(|pid| pid as u32);
+                }
+                Ok(latency)
+            }
+            Err(e) => {
+                health.last_error = Some(format!("ping failed: {}", e));
+                Err(e)
+            }
+        }
+    }
+
+    /// Read responses and events from the bridge until EOF
+    async fn read_messages(&self, reader: BridgeReader) {
+        let mut reader = BufReader::new(reader);
+
+        debug!("reader task started");
+
+        loop {
+            match read_frame(&mut reader).await {
+                Ok(Some(frame)) => match std::str::from_utf8(&frame) {
+                    Ok(frame) => {
+                        if let Err(e) = BridgeClient::handle_message(self, frame) {
+                            warn!("error handling message: {}", e);
+                        }
+                    }
+                    Err(e) => warn!("dropping frame that is not UTF-8: {}", e),
+                },
+                Ok(None) => break,
+                Err(e) => {
+                    // Framing is lost after a bad frame; the supervisor reconnects
+                    error!("failed to read frame from bridge: {}", e);
+                    break;
+                }
+            }
+        }
+
+        debug!("reader task exiting, channel closed");
+
+        // Nobody is left to answer the outstanding requests. Clear `ready` right
+        // away so new requests wait for the reconnect instead of failing
+        self.ready.send_replace(false);
+        self.writer.lock().unwrap().take();
+        fail_pending(&self.pending, BridgeError::Terminated);
+    }
+
+    /// Reset the connection state after the bridge process stopped
+    fn mark_stopped(&self) {
+        self.ready.send_replace(false);
+        self.writer.lock().unwrap().take();
+        fail_pending(&self.pending, BridgeError::Terminated);
+        {
+            let mut health = self.health.lock().unwrap();
+            health.pid = None;
+            health.connected_at = None;
+            health.protocol_version = None;
+            health.latency = None;
+            health.missed_pings = 0;
+        }
+
+        // Sessions live inside the bridge process and died with it
+        self.permissions.clear();
+        for session in self.sessions.clear() {
+            self.emit_session_closed(session, "bridge stopped");
+        }
+    }
+
+    /// Hand a serialized message to the writer task; the lock only guards the sender clone
+    fn write_line(&self, json: String) -> Result<(), BridgeError> {
+        let writer = self.writer.lock().unwrap().clone();
+        match writer {
+            Some(writer) if writer.send(json).is_ok() => Ok(()),
+            _ => {
+                error!("bridge writer not available");
+                Err(BridgeError::Unavailable(
+                    "Bridge stdin not available. The bridge is being restarted, please try again in a moment.".to_string(),
+                ))
+            }
+        }
+    }
+
+    /// Wait for the response to request `id`, bounded by the method's deadline
+    #[instrument(name = "request", skip_all, fields(id = request.id, method = %method))]
+    async fn await_response(
+        &self,
+        request: InFlight<'_>,
+        method: String,
+        rx: oneshot::Receiver<Result<serde_json::Value, BridgeError>>,
+    ) -> Result<serde_json::Value, BridgeError> {
+        let id = request.id;
+
+        let deadline = self.timeouts.lock().unwrap().for_method(&method);
+        let result = match tokio::time::timeout(deadline, rx).await {
+            Ok(Ok(result)) => result,
+            // The sender was dropped without an answer
+            Ok(Err(_)) => Err(BridgeError::Terminated),
+            Err(_) => {
+                // Dropping `request` below cancels it in the bridge
+                error!("request {} ({}) timed out after {:?}", id, method, deadline);
+                Err(BridgeError::Timeout { method, after: deadline })
+            }
+        };
+        debug!(ok = result.is_ok(), "request finished");
+        result
+    }
+
+    /// Send a request to the running bridge and wait for its response
+    async fn call(&self, method: String, params: serde_json::Value) -> Result<serde_json::Value, BridgeError> {
+        let id = self.next_id.fetch_add(1, Ordering::SeqCst);
+        let (tx, rx) = oneshot::channel();
+        self.pending.lock().unwrap().insert(id, tx);
+        let in_flight = InFlight { shared: self, id };
+        debug!(id, method = %method, "sending request");
+
+        let request = JsonRpcRequest {
+            jsonrpc: JSONRPC_VERSION,
+            id,
+            method: method.clone(),
+            params,
+        };
+        let written = serde_json::to_string(&request)
+            .map_err(|e| BridgeError::Io(format!("Failed to serialize request: {}", e)))
+            .and_then(|json| self.write_line(json));
+        if let Err(e) = written {
+            // Never reached the bridge, so there is nothing to cancel
+            self.pending.lock().unwrap().remove(&id);
+            return Err(e);
+        }
+
+        self.await_response(in_flight, method, rx).await
+    }
+
+    /// Send a notification to the running bridge
+    fn notify(&self, method: &str, params: serde_json::Value) -> Result<(), BridgeError> {
+        let notification = JsonRpcOutgoingNotification {
+            jsonrpc: JSONRPC_VERSION,
+            method: method.to_string(),
+            params,
+        };
+        let json = serde_json::to_string(&notification)
+            .map_err(|e| BridgeError::Io(format!("Failed to serialize notification: {}", e)))?;
+        let writer = self.writer.lock().unwrap().clone();
+        match writer {
+            Some(writer) if writer.send(json).is_ok() => Ok(()),
+            _ => Err(BridgeError::Unavailable("Bridge stdin not available".to_string())),
+        }
+    }
+
+    /// Route a permission request through the broker. Returns the event to
+    /// forward to the frontend, or `None` if nothing should be shown.
+    fn broker_ask(&self, message: &MessageStreamEvent) -> Option<ProviderEvent> {
+        let ProviderEvent::Ask { id, reason, action, command, path } = &message.event else {
+            return Some(message.event.clone());
+        };
+
+        let session = self.sessions.get(&message.session_id);
+        let mode = session
+            .as_ref()
+            .and_then(|session| session.permission_mode.parse().ok())
+            .unwrap_or(PermissionMode::Ask);
+        let workspace = session.as_ref().map(|session| Path::new(&session.working_dir));
+        let pending = PendingAction {
+            id: id.clone(),
+            session_id: message.session_id.clone(),
+            profile: message.profile.clone(),
+            provider: message.provider.clone(),
+            action: action.clone(),
+            reason: reason.clone(),
+            command: command.clone(),
+            path: path.clone(),
+            requested_at: SystemTime::now()
+                .duration_since(UNIX_EPOCH)
+                .map(|elapsed| elapsed.as_millis() as u64)
+                .unwrap_or(0),
+        };
+
+        let Some(decision) = self.permissions.decide(&pending, mode, workspace) else {
+            self.permissions.add_pending(pending);
+            return Some(message.event.clone());
+        };
+        let approved = decision.approved;
+
+        info!(
+            "automatically {} {}: {}",
+            if approved { "approved" } else { "rejected" },
+            id,
+            decision.reason
+        );
+        let params = serde_json::json!({
+            "profile": message.profile,
+            "actionId": id,
+            "approved": approved,
+            "applyToSession": false,
+        });
+        if let Err(e) = self.notify("respondPermission", params) {
+            error!("failed to answer permission request {}: {}", id, e);
+            return None;
+        }
+        if approved {
+            self.sessions.set_state(&message.session_id, SessionState::Running);
+        }
+
+        Some(ProviderEvent::Progress {
+            message: format!("{} ({})", reason, decision.reason),
+        })
+    }
+
+    fn forget_session(&self, session_id: &str, reason: &str) {
+        self.permissions.forget_session(session_id);
+        if let Some(session) = self.sessions.remove(session_id) {
+            self.emit_session_closed(session, reason);
+        }
+    }
+
+    fn emit_session_closed(&self, session: Session, reason: &str) {
+        info!(session = %session.id, "session closed: {}", reason);
+        let payload = SessionClosedEvent {
+            session,
+            reason: reason.to_string(),
+        };
+        self.emit("session-closed", payload);
+    }
+
+    fn emit_status(&self, status: BridgeStatus, attempt: u32, message: Option<String>) {
+        self.emit("bridge-status", BridgeStatusEvent { status, attempt, message });
+    }
+
+    fn emit(&self, event: &str, payload: impl Serialize) {
+        let emitted = serde_json::to_value(payload)
+            .map_err(|e| e.to_string())
+            .and_then(|payload| self.events.emit_event(event, payload));
+        if let Err(e) = emitted {
+            error!("failed to emit {} event: {}", event, e);
         }
     }
 }
 
 impl Drop for BridgeClient {
     fn drop(&mut self) {
-        println!("[Rust Bridge] Drop called on BridgeClient - shutting down bridge process");
-        println!("[Rust Bridge] Note: This should only happen once when the application exits");
-        self.shutdown();
+        // Normally `shutdown` ran on exit already; otherwise the supervisor
+        // still stops the process if the runtime outlives us
+        debug!("bridge client dropped");
+        self.request_stop();
+        // No `shutdown` request follows, so close the channel right away
+        self.shared.writer.lock().unwrap().take();
+        fail_pending(&self.shared.pending, BridgeError::Terminated);
     }
 }
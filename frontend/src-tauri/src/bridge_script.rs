@@ -0,0 +1,245 @@
+use regex::Regex;
+use std::fmt;
+use std::path::{Path, PathBuf};
+use std::sync::LazyLock;
+use tracing::{debug, info};
+
+use crate::bridge::{protocol_compatible, PROTOCOL_VERSION};
+
+/// Environment variable naming the bridge script; overrides `config.json`
+pub const BRIDGE_PATH_ENV: &str = "MULTICODER_BRIDGE_PATH";
+
+/// The bridge script relative to the root of the multicoder package
+pub const SCRIPT_PATH: &str = "dist/bridge/provider-bridge.js";
+
+/// Name of the npm package shipping the bridge
+const NPM_PACKAGE: &str = "multicoder";
+
+/// Command installed by the npm package, see `bin` in package.json
+const NPM_BIN: &str = "coders";
+
+/// Parent directories of the working directory searched for a development build
+const SEARCH_DEPTH: usize = 5;
+
+/// `export const PROTOCOL_VERSION = '2.0';` in the compiled bridge
+static PROTOCOL_DECLARATION: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r#"\bPROTOCOL_VERSION\s*=\s*["']([^"']+)["']"#).unwrap());
+
+/// Where a candidate script came from
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ScriptSource {
+    /// `MULTICODER_BRIDGE_PATH` or `bridge.path` in `config.json`
+    Explicit,
+    /// Resources bundled with the app
+    Bundled,
+    /// `dist/` of a checkout containing the working directory
+    WorkingDir,
+    /// The globally installed npm package
+    NpmGlobal,
+}
+
+impl fmt::Display for ScriptSource {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            ScriptSource::Explicit => "explicit setting",
+            ScriptSource::Bundled => "bundled resource",
+            ScriptSource::WorkingDir => "working directory",
+            ScriptSource::NpmGlobal => "global npm package",
+        })
+    }
+}
+
+/// Every candidate that was checked, with the reason it was rejected
+#[derive(Debug, Clone, Default)]
+pub struct BridgeNotFound {
+    pub attempts: Vec<(ScriptSource, PathBuf, String)>,
+}
+
+impl fmt::Display for BridgeNotFound {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "Could not find a bridge service for protocol version {}. Build it with 'npm run build', \
+            install the {} npm package globally or set {} to {}.",
+            PROTOCOL_VERSION, NPM_PACKAGE, BRIDGE_PATH_ENV, SCRIPT_PATH
+        )?;
+        if self.attempts.is_empty() {
+            return write!(f, " No candidates were found.");
+        }
+        write!(f, " Checked:")?;
+        for (source, path, reason) in &self.attempts {
+            write!(f, "\n  - {} ({}): {}", path.display(), source, reason)?;
+        }
+        Ok(())
+    }
+}
+
+impl std::error::Error for BridgeNotFound {}
+
+/// Protocol version declared by the bridge script `source`
+pub fn declared_protocol_version(source: &str) -> Option<&str> {
+    PROTOCOL_DECLARATION
+        .captures(source)
+        .and_then(|captures| captures.get(1))
+        .map(|version| version.as_str())
+}
+
+/// Finds a bridge script speaking this app's protocol version
+#[derive(Debug, Clone, Default)]
+pub struct BridgeLocator {
+    /// `bridge.path` from `config.json`; `MULTICODER_BRIDGE_PATH` takes precedence
+    configured: Option<PathBuf>,
+    /// The script in the app's resources, when it could be resolved
+    bundled: Option<PathBuf>,
+}
+
+impl BridgeLocator {
+    pub fn new(configured: Option<PathBuf>, bundled: Option<PathBuf>) -> Self {
+        Self { configured, bundled }
+    }
+
+    /// Check the candidates in order and return the first compatible one. An explicit
+    /// setting is the only candidate when present, so a bad setting is reported
+    /// instead of silently replaced.
+    pub fn locate(&self) -> Result<PathBuf, BridgeNotFound> {
+        let mut not_found = BridgeNotFound::default();
+
+        let candidates = match self.explicit() {
+            Some(path) => vec![(ScriptSource::Explicit, path)],
+            None => self.discover(),
+        };
+
+        for (source, path) in candidates {
+            match Self::check(&path) {
+                Ok(version) => {
+                    info!("using bridge {} with protocol {} ({})", path.display(), version, source);
+                    return Ok(path);
+                }
+                Err(reason) => {
+                    debug!("skipping bridge {} ({}): {}", path.display(), source, reason);
+                    not_found.attempts.push((source, path, reason));
+                }
+            }
+        }
+
+        Err(not_found)
+    }
+
+    fn explicit(&self) -> Option<PathBuf> {
+        match std::env::var_os(BRIDGE_PATH_ENV) {
+            Some(path) if !path.is_empty() => Some(PathBuf::from(path)),
+            _ => self.configured.clone(),
+        }
+    }
+
+    /// Candidates when nothing was configured, without duplicates
+    fn discover(&self) -> Vec<(ScriptSource, PathBuf)> {
+        let mut candidates: Vec<(ScriptSource, PathBuf)> = Vec::new();
+        let mut push = |source: ScriptSource, path: PathBuf| {
+            if !candidates.iter().any(|(_, existing)| *existing == path) {
+                candidates.push((source, path));
+            }
+        };
+
+        if let Some(path) = &self.bundled {
+            push(ScriptSource::Bundled, path.clone());
+        }
+        if let Ok(cwd) = std::env::current_dir() {
+            for dir in cwd.ancestors().take(SEARCH_DEPTH) {
+                push(ScriptSource::WorkingDir, dir.join(SCRIPT_PATH));
+            }
+        }
+        for package in Self::npm_global_packages() {
+            push(ScriptSource::NpmGlobal, package.join(SCRIPT_PATH));
+        }
+
+        candidates
+    }
+
+    /// Possible install locations of the global npm package: next to the `coders`
+    /// command on PATH, then the usual global prefixes
+    fn npm_global_packages() -> Vec<PathBuf> {
+        let mut packages = Vec::new();
+
+        // `coders` links to <package>/dist/cli/index.js
+        if let Some(path) = std::env::var_os("PATH") {
+            let bin = if cfg!(target_os = "windows") { format!("{}.cmd", NPM_BIN) } else { NPM_BIN.to_string() };
+            for dir in std::env::split_paths(&path) {
+                let Ok(target) = std::fs::canonicalize(dir.join(&bin)) else {
+                    continue;
+                };
+                if target.ends_with("dist/cli/index.js") {
+                    packages.extend(target.ancestors().nth(3).map(Path::to_path_buf));
+                }
+                // npm installs the command next to lib/node_modules, or node_modules on Windows
+                if let Some(prefix) = dir.parent() {
+                    packages.push(prefix.join("lib").join("node_modules").join(NPM_PACKAGE));
+                }
+                packages.push(dir.join("node_modules").join(NPM_PACKAGE));
+            }
+        }
+
+        let env_dir = |name: &str| std::env::var_os(name).filter(|dir| !dir.is_empty()).map(PathBuf::from);
+        let mut prefixes: Vec<PathBuf> = env_dir("NPM_CONFIG_PREFIX").into_iter().collect();
+        if cfg!(target_os = "windows") {
+            prefixes.extend(env_dir("APPDATA").map(|dir| dir.join("npm")));
+            packages.extend(prefixes.iter().map(|prefix| prefix.join("node_modules").join(NPM_PACKAGE)));
+        } else {
+            prefixes.extend(dirs::home_dir().map(|home| home.join(".npm-global")));
+            prefixes.extend(["/usr/local", "/opt/homebrew", "/usr"].map(PathBuf::from));
+            packages.extend(
+                prefixes
+                    .iter()
+                    .map(|prefix| prefix.join("lib").join("node_modules").join(NPM_PACKAGE)),
+            );
+        }
+
+        packages
+    }
+
+    /// Read the script's protocol version and check it against the app's
+    fn check(path: &Path) -> Result<String, String> {
+        if !path.is_file() {
+            return Err("not found".to_string());
+        }
+        let source = std::fs::read_to_string(path).map_err(|e| format!("failed to read: {}", e))?;
+        match declared_protocol_version(&source) {
+            Some(version) if protocol_compatible(version) => Ok(version.to_string()),
+            Some(version) => Err(format!("protocol version {}, expected {}", version, PROTOCOL_VERSION)),
+            None => Err("does not declare a protocol version; it is older than this app, rebuild it".to_string()),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const COMPILED: &str = "import * as net from 'node:net';\nexport const PROTOCOL_VERSION = '2.0';\n";
+
+    #[test]
+    fn reads_protocol_version_of_compiled_bridge() {
+        assert_eq!(declared_protocol_version(COMPILED), Some("2.0"));
+        assert_eq!(declared_protocol_version(r#"exports.PROTOCOL_VERSION = "1.3";"#), Some("1.3"));
+        assert_eq!(declared_protocol_version("const JSONRPC_VERSION = '2.0';"), None);
+    }
+
+    #[test]
+    fn explicit_setting_is_the_only_candidate() {
+        let dir = std::env::temp_dir().join(format!("multicoder-bridge-script-{}", uuid::Uuid::new_v4()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let outdated = dir.join("provider-bridge.js");
+        std::fs::write(&outdated, "export const PROTOCOL_VERSION = '1.0';").unwrap();
+        let bundled = dir.join("bundled.js");
+        std::fs::write(&bundled, COMPILED).unwrap();
+
+        let not_found = BridgeLocator::new(Some(outdated.clone()), Some(bundled.clone())).locate().unwrap_err();
+        assert_eq!(
+            not_found.attempts,
+            [(ScriptSource::Explicit, outdated, format!("protocol version 1.0, expected {}", PROTOCOL_VERSION))]
+        );
+        assert_eq!(BridgeLocator::new(None, Some(bundled.clone())).locate().unwrap(), bundled);
+
+        std::fs::remove_dir_all(dir).unwrap();
+    }
+}
@@ -0,0 +1,517 @@
+//! Runs the commands through Tauri's mock runtime against a [`FakeBridge`],
+//! exercising the whole path from IPC call to JSON-RPC and back.
+
+use serde_json::{json, Value};
+use std::io::Write;
+use std::sync::{Arc, Mutex, Once};
+use std::time::{Duration, Instant};
+use tauri::ipc::{CallbackFn, InvokeBody};
+use tauri::test::{get_ipc_response, mock_builder, mock_context, noop_assets, MockRuntime, INVOKE_KEY};
+use tauri::webview::InvokeRequest;
+use tauri::{App, Listener, Manager, WebviewWindow, WebviewWindowBuilder};
+
+use crate::bridge::BridgeClient;
+use crate::commands;
+use crate::fake_bridge::{FakeBridge, Reply};
+use crate::permissions::{PendingAction, PermissionBroker};
+use crate::policy::PolicyEngine;
+use crate::profile_store::{ProfileStore, PROFILES_FILE};
+use crate::session::SessionManager;
+use crate::state::AppState;
+
+struct TestApp {
+    app: App<MockRuntime>,
+    webview: WebviewWindow<MockRuntime>,
+    client: Arc<BridgeClient>,
+}
+
+impl TestApp {
+    /// Build an app wired to `bridge` and wait for its ready handshake
+    fn start(bridge: &FakeBridge) -> Self {
+        let app = mock_builder()
+            .manage(Mutex::new(AppState::new()))
+            .invoke_handler(tauri::generate_handler![
+                commands::send_message,
+                commands::list_sessions,
+                commands::close_session,
+                commands::switch_profile,
+                commands::list_profiles,
+                commands::get_current_profile,
+                commands::login_with_api_key,
+                commands::check_provider_auth,
+            ])
+            .build(mock_context(noop_assets()))
+            .expect("failed to build mock app");
+
+        let sessions = Arc::new(SessionManager::new());
+        app.manage(Arc::clone(&sessions));
+        let permissions = Arc::new(PermissionBroker::with_policy(PolicyEngine::empty()));
+        app.manage(Arc::clone(&permissions));
+
+        let client = BridgeClient::new(Arc::new(app.handle().clone()), Arc::new(bridge.clone()), sessions, permissions)
+            .expect("failed to start bridge client");
+        let client = Arc::new(client);
+        app.manage(Arc::clone(&client));
+
+        assert!(
+            tauri::async_runtime::block_on(client.wait_until_ready(Duration::from_secs(5))),
+            "fake bridge did not become ready"
+        );
+
+        let webview = WebviewWindowBuilder::new(&app, "main", Default::default())
+            .build()
+            .expect("failed to create webview");
+
+        Self { app, webview, client }
+    }
+
+    /// Invoke a command the way the frontend does and return its JSON result or error
+    fn invoke(&self, cmd: &str, args: Value) -> Result<Value, Value> {
+        get_ipc_response(
+            &self.webview,
+            InvokeRequest {
+                cmd: cmd.to_string(),
+                callback: CallbackFn(0),
+                error: CallbackFn(1),
+                url: "http://tauri.localhost".parse().unwrap(),
+                body: InvokeBody::Json(args),
+                headers: Default::default(),
+                invoke_key: INVOKE_KEY.to_string(),
+            },
+        )
+        .map(|body| body.deserialize::<Value>().unwrap())
+    }
+
+    /// Collect the payloads of every `event` emitted from now on
+    fn record(&self, event: &str) -> Arc<Mutex<Vec<Value>>> {
+        let payloads = Arc::new(Mutex::new(Vec::new()));
+        let sink = Arc::clone(&payloads);
+        self.app.listen_any(event, move |event| {
+            sink.lock().unwrap().push(serde_json::from_str(event.payload()).unwrap());
+        });
+        payloads
+    }
+}
+
+/// Poll `condition` until it holds or a few seconds have passed
+fn wait_for(mut condition: impl FnMut() -> bool) -> bool {
+    let deadline = Instant::now() + Duration::from_secs(5);
+    while Instant::now() < deadline {
+        if condition() {
+            return true;
+        }
+        std::thread::sleep(Duration::from_millis(20));
+    }
+    false
+}
+
+static LOGS: Mutex<Vec<u8>> = Mutex::new(Vec::new());
+
+/// Appends formatted log lines to `LOGS`
+struct LogCapture;
+
+impl Write for LogCapture {
+    fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+        LOGS.lock().unwrap().extend_from_slice(buf);
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> std::io::Result<()> {
+        Ok(())
+    }
+}
+
+/// Record everything logged from now on, by every test, at every level
+fn capture_logs() {
+    static INIT: Once = Once::new();
+    INIT.call_once(|| {
+        tracing_subscriber::fmt()
+            .with_max_level(tracing::Level::TRACE)
+            .with_ansi(false)
+            .with_writer(|| LogCapture)
+            .init();
+    });
+}
+
+fn captured_logs() -> String {
+    String::from_utf8_lossy(&LOGS.lock().unwrap()).into_owned()
+}
+
+fn profile(name: &str, provider: &str) -> Value {
+    json!({ "name": name, "provider": provider, "createdAt": "2025-01-01T00:00:00.000Z" })
+}
+
+#[test]
+fn list_profiles_returns_the_bridge_result() {
+    let profiles = json!({ "profiles": [profile("work", "claude"), profile("home", "gemini")] });
+    let bridge = FakeBridge::new().respond("listProfiles", profiles.clone());
+    let app = TestApp::start(&bridge);
+
+    assert_eq!(app.invoke("list_profiles", json!({})), Ok(profiles));
+    assert_eq!(bridge.requests_for("listProfiles"), vec![json!({})]);
+}
+
+#[test]
+fn profile_reads_fall_back_to_profiles_json_while_the_bridge_is_down() {
+    let bridge = FakeBridge::new()
+        .on("listProfiles", Reply::drop_connection())
+        .on("getCurrentProfile", Reply::drop_connection());
+    let app = TestApp::start(&bridge);
+
+    let dir = std::env::temp_dir().join(format!("multicoder-fallback-{}", uuid::Uuid::new_v4()));
+    std::fs::create_dir_all(&dir).unwrap();
+    std::fs::write(
+        dir.join(PROFILES_FILE),
+        r#"{ "current": "work", "profiles": [{ "name": "work", "providers": {}, "permissionMode": "ask", "createdAt": 1, "updatedAt": 2 }] }"#,
+    )
+    .unwrap();
+    app.app.manage(Arc::new(ProfileStore::new(dir.clone(), Vec::new())));
+    let changes = app.record("profile-changed");
+
+    let stored = json!({ "name": "work", "providers": {}, "permissionMode": "ask", "createdAt": 1, "updatedAt": 2 });
+    assert_eq!(app.invoke("list_profiles", json!({})), Ok(json!({ "profiles": [stored.clone()] })));
+    assert_eq!(app.invoke("get_current_profile", json!({})), Ok(json!({ "profile": stored })));
+    assert_eq!(*changes.lock().unwrap(), vec![json!({ "profileId": "work" })]);
+
+    std::fs::remove_dir_all(dir).unwrap();
+}
+
+#[test]
+fn switch_profile_updates_state_and_emits_profile_changed() {
+    let bridge = FakeBridge::new().respond(
+        "switchProfile",
+        json!({ "success": true, "profile": profile("work", "claude") }),
+    );
+    let app = TestApp::start(&bridge);
+    let changes = app.record("profile-changed");
+
+    let result = app.invoke("switch_profile", json!({ "profileId": "work" })).unwrap();
+    assert_eq!(result["profile"]["name"], "work");
+    assert_eq!(bridge.requests_for("switchProfile"), vec![json!({ "profileId": "work" })]);
+
+    let state = app.app.state::<Mutex<AppState>>();
+    assert_eq!(state.lock().unwrap().current_profile_id.as_deref(), Some("work"));
+    assert_eq!(*changes.lock().unwrap(), vec![json!({ "profileId": "work" })]);
+
+    // Switching to the profile that is already current is not a change
+    app.invoke("switch_profile", json!({ "profileId": "work" })).unwrap();
+    assert_eq!(changes.lock().unwrap().len(), 1);
+}
+
+#[test]
+fn switch_profile_maps_unknown_profiles_to_profile_not_found() {
+    let bridge = FakeBridge::new().on(
+        "switchProfile",
+        Reply::error(-32001, "Profile not found: ghost", Some(json!({ "profile": "ghost" }))),
+    );
+    let app = TestApp::start(&bridge);
+    let changes = app.record("profile-changed");
+
+    let error = app.invoke("switch_profile", json!({ "profileId": "ghost" })).unwrap_err();
+    assert_eq!(error["code"], "PROFILE_NOT_FOUND");
+    assert_eq!(error["details"], json!({ "profile": "ghost" }));
+
+    let state = app.app.state::<Mutex<AppState>>();
+    assert_eq!(state.lock().unwrap().current_profile_id, None);
+    assert!(changes.lock().unwrap().is_empty());
+}
+
+#[test]
+fn login_with_api_key_forwards_the_key_and_metadata() {
+    let bridge = FakeBridge::new().respond("loginWithApiKey", json!({ "success": true }));
+    let app = TestApp::start(&bridge);
+
+    let result = app.invoke(
+        "login_with_api_key",
+        json!({
+            "profileName": "work",
+            "provider": "claude",
+            "apiKey": "sk-test",
+            "metadata": { "baseUrl": "https://example.invalid" },
+        }),
+    );
+    assert_eq!(result, Ok(json!({ "success": true })));
+    assert_eq!(
+        bridge.requests_for("loginWithApiKey"),
+        vec![json!({
+            "profileName": "work",
+            "provider": "claude",
+            "apiKey": "sk-test",
+            "metadata": { "baseUrl": "https://example.invalid" },
+        })]
+    );
+}
+
+#[test]
+fn secrets_in_bridge_traffic_never_reach_the_logs() {
+    capture_logs();
+    const API_KEY: &str = "plain-api-key-4f9c2e";
+    const ACCESS_TOKEN: &str = "oauth-access-7d1b8a";
+    const LEAKED_KEY: &str = "sk-proj-leaked0123456789";
+
+    let bridge = FakeBridge::new().respond(
+        "loginWithApiKey",
+        json!({
+            "success": true,
+            "credential": { "access_token": ACCESS_TOKEN },
+            "warning": format!("ignored {} from the environment", LEAKED_KEY),
+        }),
+    );
+    let app = TestApp::start(&bridge);
+
+    let args = json!({ "profileName": "work", "provider": "claude", "apiKey": API_KEY, "metadata": {} });
+    let result = app.invoke("login_with_api_key", args).unwrap();
+
+    // Redaction only applies to logging; the bridge and the frontend see the real values
+    assert_eq!(bridge.requests_for("loginWithApiKey")[0]["apiKey"], API_KEY);
+    assert_eq!(result["credential"]["access_token"], ACCESS_TOKEN);
+
+    let logs = captured_logs();
+    assert!(
+        logs.contains("sending to bridge") && logs.contains("loginWithApiKey"),
+        "the request was not logged"
+    );
+    for secret in [API_KEY, ACCESS_TOKEN, LEAKED_KEY] {
+        assert!(!logs.contains(secret), "{} reached the logs", secret);
+    }
+}
+
+#[test]
+fn check_provider_auth_reports_validity() {
+    let bridge = FakeBridge::new()
+        .respond("checkAuth", json!({ "valid": true }))
+        .respond("checkAuth", json!({ "valid": false, "reason": "expired" }))
+        .on("checkAuth", Reply::error(-32002, "Run claude login", Some(json!({ "provider": "claude" }))))
+        .on("checkAuth", Reply::error(-32603, "keychain locked", None));
+    let app = TestApp::start(&bridge);
+    let args = json!({ "provider": "claude", "profileName": "work" });
+
+    assert_eq!(app.invoke("check_provider_auth", args.clone()), Ok(json!(true)));
+    assert_eq!(app.invoke("check_provider_auth", args.clone()), Ok(json!(false)));
+    assert_eq!(app.invoke("check_provider_auth", args.clone()), Ok(json!(false)));
+    // Other bridge errors are not an answer
+    let error = app.invoke("check_provider_auth", args).unwrap_err();
+    assert_eq!((&error["code"], &error["message"]), (&json!("BRIDGE_ERROR"), &json!("keychain locked")));
+    assert_eq!(
+        bridge.requests_for("checkAuth")[0],
+        json!({ "provider": "claude", "profileName": "work" })
+    );
+}
+
+#[test]
+fn send_message_streams_provider_events() {
+    let stream_event = |event: Value| {
+        json!({ "profile": "work", "provider": "claude", "sessionId": "session-1", "event": event })
+    };
+    let bridge = FakeBridge::new()
+        .respond(
+            "launch",
+            json!({
+                "success": true,
+                "session": {
+                    "sessionId": "session-1",
+                    "profile": "work",
+                    "provider": "claude",
+                    "workingDir": "/tmp",
+                    "permissionMode": "ask",
+                    "startedAt": 1,
+                },
+            }),
+        )
+        .on(
+            "sendMessage",
+            Reply::result(json!({ "success": true }))
+                .notify("message", stream_event(json!({ "type": "text", "content": "Hello" })))
+                .notify("message", stream_event(json!({ "type": "text", "content": " world" })))
+                .notify("message", stream_event(json!({ "type": "done" }))),
+        );
+    let app = TestApp::start(&bridge);
+    let events = app.record("message-stream");
+    let args = json!({ "profile": "work", "provider": "claude", "message": "Hi", "context": [] });
+
+    assert_eq!(app.invoke("send_message", args.clone()), Ok(Value::Null));
+    assert_eq!(
+        *events.lock().unwrap(),
+        vec![
+            stream_event(json!({ "type": "text", "content": "Hello" })),
+            stream_event(json!({ "type": "text", "content": " world" })),
+            stream_event(json!({ "type": "done" })),
+        ]
+    );
+
+    let sessions = app.invoke("list_sessions", json!({})).unwrap();
+    assert_eq!(sessions[0]["id"], "session-1");
+    assert_eq!(sessions[0]["state"], "idle");
+
+    // The second message reuses the session instead of launching another
+    app.invoke("send_message", args).unwrap();
+    assert_eq!(bridge.requests_for("launch").len(), 1);
+    assert_eq!(
+        bridge.requests_for("sendMessage"),
+        vec![json!({ "profile": "work", "message": "Hi" }); 2]
+    );
+}
+
+#[test]
+fn replaced_and_closed_sessions_are_reported_and_forgotten() {
+    let launched = |id: &str, provider: &str| {
+        json!({
+            "success": true,
+            "session": {
+                "sessionId": id,
+                "profile": "work",
+                "provider": provider,
+                "workingDir": "/tmp",
+                "permissionMode": "ask",
+                "startedAt": 1,
+            },
+        })
+    };
+    let bridge = FakeBridge::new()
+        .respond("launch", launched("session-1", "claude"))
+        .respond("launch", launched("session-2", "gemini"))
+        .respond("sendMessage", json!({ "success": true }))
+        .respond("sendMessage", json!({ "success": true }))
+        .respond("closeSession", json!({ "success": true }));
+    let app = TestApp::start(&bridge);
+    let closed = app.record("session-closed");
+    let permissions = app.app.state::<Arc<PermissionBroker>>().inner().clone();
+    let message = |provider: &str| json!({ "profile": "work", "provider": provider, "message": "Hi", "context": [] });
+
+    let ls = |session: &str| PendingAction {
+        id: "action-1".to_string(),
+        session_id: session.to_string(),
+        profile: "work".to_string(),
+        provider: "claude".to_string(),
+        action: "shell_exec".to_string(),
+        reason: "List files".to_string(),
+        command: Some("ls".to_string()),
+        path: None,
+        requested_at: 1,
+    };
+
+    app.invoke("send_message", message("claude")).unwrap();
+    permissions.grant(&ls("session-1"));
+
+    // The profile's new session replaces the first one in the bridge
+    app.invoke("send_message", message("gemini")).unwrap();
+    permissions.grant(&ls("session-2"));
+    assert!(!permissions.is_granted(&ls("session-1")));
+
+    app.invoke("close_session", json!({ "sessionId": "session-2" })).unwrap();
+    assert!(!permissions.is_granted(&ls("session-2")));
+    assert_eq!(app.invoke("list_sessions", json!({})), Ok(json!([])));
+
+    let closed: Vec<(Value, Value)> = closed
+        .lock()
+        .unwrap()
+        .iter()
+        .map(|event| (event["session"]["id"].clone(), event["reason"].clone()))
+        .collect();
+    assert_eq!(
+        closed,
+        [
+            (json!("session-1"), json!("replaced by a new session")),
+            (json!("session-2"), json!("closed")),
+        ]
+    );
+}
+
+#[test]
+fn shutdown_is_not_taken_for_a_crash() {
+    let bridge = FakeBridge::new().on("shutdown", Reply::result(json!({ "success": true })).then_close());
+    let app = TestApp::start(&bridge);
+    let statuses = app.record("bridge-status");
+
+    tauri::async_runtime::block_on(app.client.shutdown());
+    assert_eq!(bridge.requests_for("shutdown").len(), 1);
+
+    // Longer than the first restart backoff
+    std::thread::sleep(Duration::from_secs(1));
+    assert_eq!(bridge.connections(), 1);
+    let statuses = statuses.lock().unwrap();
+    assert!(
+        statuses.iter().all(|event| event["status"] != "crashed"),
+        "unexpected bridge-status events: {:?}",
+        statuses
+    );
+}
+
+#[test]
+fn bridge_crash_fails_the_request_and_reconnects() {
+    let bridge = FakeBridge::new()
+        .on("listProfiles", Reply::drop_connection())
+        .respond("listProfiles", json!({ "profiles": [] }));
+    let app = TestApp::start(&bridge);
+    let statuses = app.record("bridge-status");
+
+    let error = app.invoke("list_profiles", json!({})).unwrap_err();
+    assert_eq!(error["code"], "BRIDGE_UNAVAILABLE");
+
+    // The supervisor reports the crash and connects again after its backoff
+    assert!(wait_for(|| bridge.connections() == 2), "bridge was not reconnected");
+    assert_eq!(app.invoke("list_profiles", json!({})), Ok(json!({ "profiles": [] })));
+
+    // The initial `ready` may land after the listener was added; look from the crash on
+    let after_crash = || -> Vec<Value> {
+        let statuses = statuses.lock().unwrap();
+        let statuses = statuses.iter().map(|event| event["status"].clone());
+        statuses.skip_while(|status| status != "crashed").collect()
+    };
+    assert!(
+        wait_for(|| after_crash() == vec![json!("crashed"), json!("restarting"), json!("ready")]),
+        "unexpected bridge-status events: {:?}",
+        after_crash()
+    );
+}
+
+#[test]
+fn slow_responses_time_out() {
+    let bridge = FakeBridge::new().on(
+        "listProfiles",
+        Reply::result(json!({ "profiles": [] })).delay(Duration::from_secs(2)),
+    );
+    let app = TestApp::start(&bridge);
+    app.client.set_request_timeout("listProfiles", Duration::from_millis(100));
+
+    let error = app.invoke("list_profiles", json!({})).unwrap_err();
+    assert_eq!(error["code"], "BRIDGE_TIMEOUT");
+    assert_eq!(error["details"], json!({ "method": "listProfiles", "timeoutMs": 100 }));
+
+    // The bridge is told to stop working on the abandoned request
+    assert!(
+        wait_for(|| bridge.requests_for("$/cancelRequest").len() == 1),
+        "request was not cancelled"
+    );
+    assert!(bridge.requests_for("$/cancelRequest")[0]["id"].is_u64());
+}
+
+#[test]
+fn dropping_a_request_cancels_it_in_the_bridge() {
+    let bridge = FakeBridge::new().on(
+        "listProfiles",
+        Reply::result(json!({ "profiles": [] })).delay(Duration::from_secs(5)),
+    );
+    let app = TestApp::start(&bridge);
+
+    // The caller goes away, e.g. the window that invoked the command was closed
+    tauri::async_runtime::block_on(async {
+        tokio::select! {
+            _ = app.client.list_profiles() => panic!("the delayed request finished"),
+            _ = async {
+                while bridge.request_ids_for("listProfiles").is_empty() {
+                    tokio::time::sleep(Duration::from_millis(10)).await;
+                }
+            } => {}
+        }
+    });
+
+    assert!(
+        wait_for(|| bridge.requests_for("$/cancelRequest").len() == 1),
+        "request was not cancelled"
+    );
+    assert_eq!(
+        bridge.requests_for("$/cancelRequest")[0],
+        json!({ "id": bridge.request_ids_for("listProfiles")[0] })
+    );
+}
@@ -1,16 +1,76 @@
-use tauri::AppHandle;
 use serde::{Serialize, Deserialize};
+use std::path::Path;
 use std::sync::{Arc, Mutex};
-use crate::state::AppState;
-use crate::bridge::BridgeClient;
+use tauri::{AppHandle, Emitter, Manager, Runtime};
+use crate::state::{AppState, ProfileChangedEvent};
+use crate::bridge::{BridgeClient, BridgeHealth};
+use crate::error::CommandError;
+use crate::logging::{LogBuffer, LogEntry};
+use crate::permissions::{PendingAction, PermissionBroker, PermissionMode};
+use crate::profile_bundle::{self, ExportSummary, ImportSummary, OnConflict};
+use crate::profile_store::ProfileStore;
+use crate::session::{Session, SessionManager, SessionState};
+use tracing::{debug, error, info, warn};
 #[cfg(target_os = "windows")]
 use std::os::windows::process::CommandExt;
 
+// Helper to read the profile name out of a bridge result's `profile` field
+pub fn profile_name(result: &serde_json::Value) -> Option<String> {
+    result
+        .get("profile")
+        .and_then(|profile| profile.get("name"))
+        .and_then(|name| name.as_str())
+        .map(str::to_string)
+}
+
+/// Record the bridge's current profile in `AppState` and tell every window
+/// when it changed
+pub fn sync_current_profile<R: Runtime>(app: &AppHandle<R>, app_state: &Mutex<AppState>, profile_id: Option<String>) {
+    let changed = app_state.lock().unwrap().set_current_profile(profile_id.clone());
+    if changed {
+        if let Err(e) = app.emit("profile-changed", ProfileChangedEvent { profile_id }) {
+            error!("failed to emit profile-changed event: {}", e);
+        }
+    }
+}
+
 // Helper to get bridge client or return error
-fn get_bridge(bridge_state: &tauri::State<Arc<BridgeClient>>) -> Result<Arc<BridgeClient>, String> {
+fn get_bridge(bridge_state: &tauri::State<Arc<BridgeClient>>) -> Result<Arc<BridgeClient>, CommandError> {
     Ok(bridge_state.inner().clone())
 }
 
+/// Run a profile command through the bridge, or against `profiles.json` when the bridge
+/// is not running, still starting or stops answering. `fallback` produces the bridge's
+/// result from the store, on a blocking thread since it takes the store's lock.
+async fn profile_command<R, F>(
+    app: &AppHandle<R>,
+    method: &str,
+    from_bridge: impl AsyncFnOnce(Arc<BridgeClient>) -> Result<serde_json::Value, CommandError>,
+    fallback: F,
+) -> Result<serde_json::Value, CommandError>
+where
+    R: Runtime,
+    F: FnOnce(&ProfileStore) -> Result<serde_json::Value, CommandError> + Send + 'static,
+{
+    let bridge = app.try_state::<Arc<BridgeClient>>().map(|state| state.inner().clone());
+    let error = match bridge {
+        Some(bridge) if bridge.is_alive() => match from_bridge(bridge).await {
+            Err(e @ (CommandError::BridgeUnavailable(_) | CommandError::BridgeTimeout { .. })) => e,
+            result => return result,
+        },
+        _ => CommandError::BridgeUnavailable("Bridge is not running".to_string()),
+    };
+
+    let Some(store) = app.try_state::<Arc<ProfileStore>>() else {
+        return Err(error);
+    };
+    warn!("{} falls back to {}: {}", method, store.path().display(), error);
+    let store = store.inner().clone();
+    tauri::async_runtime::spawn_blocking(move || fallback(&store))
+        .await
+        .map_err(|e| CommandError::Internal(e.to_string()))?
+}
+
 // ============================================================================
 // Types
 // ============================================================================
@@ -35,7 +95,15 @@ pub enum ProviderEvent {
     #[serde(rename = "shell")]
     Shell { command: String },
     #[serde(rename = "ask")]
-    Ask { reason: String, action: String },
+    Ask {
+        id: String,
+        reason: String,
+        action: String,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        command: Option<String>,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        path: Option<String>,
+    },
     #[serde(rename = "progress")]
     Progress { message: String },
     #[serde(rename = "error")]
@@ -44,47 +112,67 @@ pub enum ProviderEvent {
     Done,
 }
 
+/// Payload of the `message-stream` event: one provider event of a chat session
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct MessageStreamEvent {
+    pub profile: String,
+    pub provider: String,
+    pub session_id: String,
+    pub event: ProviderEvent,
+}
+
 // ============================================================================
 // Chat Commands
 // ============================================================================
 
 #[tauri::command]
 pub async fn send_message(
-    _app: AppHandle,
-    _app_state: tauri::State<'_, Mutex<AppState>>,
     bridge_state: tauri::State<'_, Arc<BridgeClient>>,
+    sessions: tauri::State<'_, Arc<SessionManager>>,
+    permissions: tauri::State<'_, Arc<PermissionBroker>>,
     profile: String,
     provider: String,
     message: String,
     _context: Vec<String>,
-) -> Result<(), String> {
-    println!("send_message called: profile={}, provider={}, message_len={}", profile, provider, message.len());
+) -> Result<(), CommandError> {
+    debug!(%profile, %provider, message_len = message.len(), "send_message");
 
     // Get Arc clone (just increments reference count, doesn't trigger Drop)
     let bridge_clone = get_bridge(&bridge_state)?;
 
-    // Launch provider session if not already started
-    // The bridge will handle session management internally
-    let launch_result = bridge_clone.launch(
-        profile.clone(),
-        provider.clone(),
-        serde_json::json!({
-            "profileName": profile,
-            "workingDir": std::env::current_dir().unwrap().to_string_lossy(),
-            "permissionMode": "ask",
-        })
-    ).await;
-
-    match launch_result {
-        Ok(_) => println!("Provider session launched/reused successfully"),
-        Err(e) => {
-            // Session might already exist, that's ok
-            println!("Launch note: {}", e);
+    // Reuse the profile's session, launching one only when none is running
+    let session = match sessions.find(&profile, &provider) {
+        Some(session) => session,
+        None => {
+            let working_dir = std::env::current_dir()
+                .map_err(|e| CommandError::Io { path: None, message: format!("Failed to get current dir: {}", e) })?;
+
+            let launch_result = bridge_clone.launch(
+                profile.clone(),
+                provider.clone(),
+                serde_json::json!({
+                    "profileName": profile,
+                    // The bridge applies the profile's persisted permission mode
+                    "workingDir": working_dir.to_string_lossy(),
+                })
+            ).await?;
+
+            let session = Session::from_launch_result(&launch_result).map_err(CommandError::Internal)?;
+            info!(session = %session.id, "provider session launched");
+            bridge_clone.track_session(session.clone());
+            session
         }
-    }
+    };
 
-    // Send the message
-    bridge_clone.send_message(profile, message).await?;
+    // Send the message; provider events stream in as `message-stream` events
+    // and the call returns once the turn has finished or was stopped
+    // Requests of the previous turn can no longer be answered
+    permissions.drop_pending(&session.id);
+    sessions.set_state(&session.id, SessionState::Running);
+    let result = bridge_clone.send_message(profile, message).await;
+    sessions.set_state(&session.id, SessionState::Idle);
+    result?;
 
     Ok(())
 }
@@ -93,84 +181,160 @@ pub async fn send_message(
 pub async fn stop_message_stream(
     app_state: tauri::State<'_, Mutex<AppState>>,
     bridge_state: tauri::State<'_, Arc<BridgeClient>>,
-) -> Result<(), String> {
-    let profile_id = {
-        let state = app_state.lock().unwrap();
-        state.current_profile_id.clone()
-            .ok_or("No profile selected")?
+    sessions: tauri::State<'_, Arc<SessionManager>>,
+    profile: Option<String>,
+) -> Result<(), CommandError> {
+    // Stop the given profile's session, defaulting to the current profile
+    let profile_id = match profile {
+        Some(profile) => profile,
+        None => {
+            let state = app_state.lock().unwrap();
+            state.current_profile_id.clone()
+                .ok_or_else(|| CommandError::Validation("No profile selected".to_string()))?
+        }
     };
 
     let bridge_clone = get_bridge(&bridge_state)?;
 
+    if let Some(session) = sessions.find_by_profile(&profile_id) {
+        sessions.set_state(&session.id, SessionState::Stopping);
+    }
+
     // Stop the provider session
     bridge_clone.stop(profile_id).await?;
 
     Ok(())
 }
 
+// ============================================================================
+// Session Commands
+// ============================================================================
+
+#[tauri::command]
+pub async fn list_sessions(
+    sessions: tauri::State<'_, Arc<SessionManager>>,
+) -> Result<Vec<Session>, CommandError> {
+    Ok(sessions.list())
+}
+
+#[tauri::command]
+pub async fn get_session(
+    sessions: tauri::State<'_, Arc<SessionManager>>,
+    session_id: String,
+) -> Result<Session, CommandError> {
+    sessions
+        .get(&session_id)
+        .ok_or(CommandError::SessionNotFound(session_id))
+}
+
+#[tauri::command]
+pub async fn close_session(
+    bridge_state: tauri::State<'_, Arc<BridgeClient>>,
+    sessions: tauri::State<'_, Arc<SessionManager>>,
+    session_id: String,
+) -> Result<(), CommandError> {
+    let session = sessions
+        .get(&session_id)
+        .ok_or_else(|| CommandError::SessionNotFound(session_id.clone()))?;
+
+    let bridge_clone = get_bridge(&bridge_state)?;
+    bridge_clone.close_session(session.profile).await?;
+    // Usually the bridge's sessionClosed notification got here first
+    bridge_clone.forget_session(&session_id, "closed");
+
+    Ok(())
+}
+
 // ============================================================================
 // Profile Commands
 // ============================================================================
 
 #[tauri::command]
-pub async fn create_profile(
-    _state: tauri::State<'_, Mutex<AppState>>,
+pub async fn create_profile<R: Runtime>(
+    app: AppHandle<R>,
+    state: tauri::State<'_, Mutex<AppState>>,
     bridge_state: tauri::State<'_, Arc<BridgeClient>>,
     name: String,
     provider: String,
-) -> Result<serde_json::Value, String> {
-    println!("create_profile: name={}, provider={}", name, provider);
+) -> Result<serde_json::Value, CommandError> {
+    debug!(%name, %provider, "create_profile");
 
     let bridge_clone = get_bridge(&bridge_state)?;
     let result = bridge_clone.create_profile(name, provider).await?;
-    
+
+    // The bridge selects the profile it just created
+    if let Some(profile_id) = profile_name(&result) {
+        sync_current_profile(&app, &state, Some(profile_id));
+    }
+
     Ok(result)
 }
 
 #[tauri::command]
-pub async fn switch_profile(
-    _state: tauri::State<'_, Mutex<AppState>>,
+pub async fn switch_profile<R: Runtime>(
+    app: AppHandle<R>,
+    state: tauri::State<'_, Mutex<AppState>>,
     bridge_state: tauri::State<'_, Arc<BridgeClient>>,
     profile_id: String,
-) -> Result<serde_json::Value, String> {
-    println!("switch_profile: profile_id={}", profile_id);
+) -> Result<serde_json::Value, CommandError> {
+    debug!(%profile_id, "switch_profile");
 
     let bridge_clone = get_bridge(&bridge_state)?;
-    let result = bridge_clone.switch_profile(profile_id).await?;
-    
+    let result = bridge_clone.switch_profile(profile_id.clone()).await?;
+
+    sync_current_profile(&app, &state, Some(profile_name(&result).unwrap_or(profile_id)));
+
     Ok(result)
 }
 
 #[tauri::command]
-pub async fn list_profiles(
+pub async fn list_profiles<R: Runtime>(
+    app: AppHandle<R>,
     _state: tauri::State<'_, Mutex<AppState>>,
-    bridge_state: tauri::State<'_, Arc<BridgeClient>>,
-) -> Result<serde_json::Value, String> {
-    let bridge_clone = get_bridge(&bridge_state)?;
-    let result = bridge_clone.list_profiles().await?;
-    
-    Ok(result)
+) -> Result<serde_json::Value, CommandError> {
+    profile_command(
+        &app,
+        "list_profiles",
+        async |bridge| bridge.list_profiles().await,
+        |store| Ok(serde_json::json!({ "profiles": store.load()?.list() })),
+    )
+    .await
 }
 
 #[tauri::command]
-pub async fn delete_profile(
-    _state: tauri::State<'_, Mutex<AppState>>,
+pub async fn delete_profile<R: Runtime>(
+    app: AppHandle<R>,
+    state: tauri::State<'_, Mutex<AppState>>,
     bridge_state: tauri::State<'_, Arc<BridgeClient>>,
     profile_id: String,
-) -> Result<serde_json::Value, String> {
+) -> Result<serde_json::Value, CommandError> {
     let bridge_clone = get_bridge(&bridge_state)?;
-    let result = bridge_clone.delete_profile(profile_id).await?;
-    
+    let result = bridge_clone.delete_profile(profile_id.clone()).await?;
+
+    // Deleting the current profile leaves the bridge without one
+    let is_current = state.lock().unwrap().current_profile_id.as_deref() == Some(profile_id.as_str());
+    if is_current && result.get("success").and_then(|v| v.as_bool()).unwrap_or(false) {
+        sync_current_profile(&app, &state, None);
+    }
+
     Ok(result)
 }
 
 #[tauri::command]
-pub async fn get_current_profile(
-    bridge_state: tauri::State<'_, Arc<BridgeClient>>,
-) -> Result<serde_json::Value, String> {
-    let bridge_clone = get_bridge(&bridge_state)?;
-    let result = bridge_clone.get_current_profile().await?;
-    
+pub async fn get_current_profile<R: Runtime>(
+    app: AppHandle<R>,
+    state: tauri::State<'_, Mutex<AppState>>,
+) -> Result<serde_json::Value, CommandError> {
+    let result = profile_command(
+        &app,
+        "get_current_profile",
+        async |bridge| bridge.get_current_profile().await,
+        |store| Ok(serde_json::json!({ "profile": store.load()?.current() })),
+    )
+    .await?;
+
+    sync_current_profile(&app, &state, profile_name(&result));
+
     Ok(result)
 }
 
@@ -181,13 +345,61 @@ pub async fn login_with_api_key(
     provider: String,
     api_key: String,
     metadata: Option<serde_json::Value>,
-) -> Result<serde_json::Value, String> {
+) -> Result<serde_json::Value, CommandError> {
     let bridge_clone = get_bridge(&bridge_state)?;
     let result = bridge_clone.login_with_api_key(profile_name, provider, api_key, metadata).await?;
 
     Ok(result)
 }
 
+// Helper to get the profile store, which is missing when there is no home directory
+fn get_profile_store<R: Runtime>(app: &AppHandle<R>) -> Result<Arc<ProfileStore>, CommandError> {
+    app.try_state::<Arc<ProfileStore>>()
+        .map(|state| state.inner().clone())
+        .ok_or_else(|| CommandError::Internal("No configuration directory".to_string()))
+}
+
+/// Write profiles and their managed credentials to an encrypted bundle; all profiles
+/// when `names` is empty
+#[tauri::command]
+pub async fn export_profiles<R: Runtime>(
+    app: AppHandle<R>,
+    names: Vec<String>,
+    passphrase: String,
+    path: String,
+) -> Result<ExportSummary, CommandError> {
+    let store = get_profile_store(&app)?;
+    // Key derivation is deliberately slow
+    tauri::async_runtime::spawn_blocking(move || {
+        profile_bundle::export_profiles(&store, &names, &passphrase, Path::new(&path))
+    })
+    .await
+    .map_err(|e| CommandError::Internal(e.to_string()))?
+}
+
+/// Add the profiles of an encrypted bundle; `on_conflict` is skip, overwrite or rename
+#[tauri::command]
+pub async fn import_profiles<R: Runtime>(
+    app: AppHandle<R>,
+    path: String,
+    passphrase: String,
+    on_conflict: String,
+) -> Result<ImportSummary, CommandError> {
+    let on_conflict: OnConflict = on_conflict.parse().map_err(CommandError::Validation)?;
+    let store = get_profile_store(&app)?;
+    let summary = tauri::async_runtime::spawn_blocking(move || {
+        profile_bundle::import_profiles(&store, Path::new(&path), &passphrase, on_conflict)
+    })
+    .await
+    .map_err(|e| CommandError::Internal(e.to_string()))??;
+
+    info!(
+        "imported profiles {:?} from bundle, skipped {:?}",
+        summary.imported, summary.skipped
+    );
+    Ok(summary)
+}
+
 // ============================================================================
 // Context Commands
 // ============================================================================
@@ -195,12 +407,12 @@ pub async fn login_with_api_key(
 #[tauri::command]
 pub async fn add_context_paths(
     paths: Vec<String>,
-) -> Result<Vec<ContextItem>, String> {
+) -> Result<Vec<ContextItem>, CommandError> {
     let mut items = Vec::new();
 
     for path in paths {
         let metadata = std::fs::metadata(&path)
-            .map_err(|e| format!("Failed to read {}: {}", path, e))?;
+            .map_err(|e| CommandError::io("read", &path, e))?;
 
         items.push(ContextItem {
             id: uuid::Uuid::new_v4().to_string(),
@@ -214,9 +426,9 @@ pub async fn add_context_paths(
 }
 
 #[tauri::command]
-pub async fn read_file(path: String) -> Result<String, String> {
+pub async fn read_file(path: String) -> Result<String, CommandError> {
     std::fs::read_to_string(&path)
-        .map_err(|e| format!("Failed to read {}: {}", path, e))
+        .map_err(|e| CommandError::io("read", &path, e))
 }
 
 // ============================================================================
@@ -229,26 +441,21 @@ pub async fn check_provider_auth(
     bridge_state: tauri::State<'_, Arc<BridgeClient>>,
     provider: String,
     profile_name: String,
-) -> Result<bool, String> {
-    println!("check_provider_auth: provider={}, profile_name={}", provider, profile_name);
+) -> Result<bool, CommandError> {
+    debug!(%provider, %profile_name, "check_provider_auth");
 
     // Get Arc clone (just increments reference count)
     let bridge_clone = get_bridge(&bridge_state)?;
 
-    // Call bridge to check auth
+    // Call bridge to check auth; only a provider that needs a login counts as not
+    // authenticated, a bridge that can't answer is reported as such
     match bridge_clone.check_auth(provider, profile_name).await {
-        Ok(result) => {
-            // Parse the result to get 'valid' field
-            if let Some(valid) = result.get("valid").and_then(|v| v.as_bool()) {
-                Ok(valid)
-            } else {
-                Ok(false)
-            }
-        }
-        Err(e) => {
-            println!("Error checking auth: {}", e);
+        Ok(result) => Ok(result.get("valid").and_then(|v| v.as_bool()).unwrap_or(false)),
+        Err(CommandError::AuthRequired { message, .. }) => {
+            debug!("not authenticated: {}", message);
             Ok(false)
         }
+        Err(e) => Err(e),
     }
 }
 
@@ -257,11 +464,13 @@ pub async fn get_auth_options(
     bridge_state: tauri::State<'_, Arc<BridgeClient>>,
     profile_name: String,
     provider: String,
-) -> Result<serde_json::Value, String> {
+) -> Result<serde_json::Value, CommandError> {
     let bridge_clone = get_bridge(&bridge_state)?;
-    bridge_clone
+    let result = bridge_clone
         .get_auth_options(profile_name, provider)
-        .await
+        .await?;
+
+    Ok(result)
 }
 
 #[tauri::command]
@@ -269,16 +478,18 @@ pub async fn link_existing_credential(
     bridge_state: tauri::State<'_, Arc<BridgeClient>>,
     profile_name: String,
     provider: String,
-) -> Result<serde_json::Value, String> {
+) -> Result<serde_json::Value, CommandError> {
     let bridge_clone = get_bridge(&bridge_state)?;
-    bridge_clone
+    let result = bridge_clone
         .link_existing_credential(profile_name, provider)
-        .await
+        .await?;
+
+    Ok(result)
 }
 
 #[tauri::command]
-pub async fn trigger_provider_login(provider: String) -> Result<String, String> {
-    println!("trigger_provider_login: provider={}", provider);
+pub async fn trigger_provider_login(provider: String) -> Result<String, CommandError> {
+    debug!(%provider, "trigger_provider_login");
 
     use std::process::Command;
 
@@ -295,7 +506,7 @@ pub async fn trigger_provider_login(provider: String) -> Result<String, String>
             let output = cmd.output();
             match output {
                 Ok(_) => Ok("Login initiated. Please complete in browser.".to_string()),
-                Err(e) => Err(format!("Failed to start codex login: {}", e)),
+                Err(e) => Err(CommandError::Io { path: None, message: format!("Failed to start codex login: {}", e) }),
             }
         },
         "claude" => {
@@ -307,7 +518,7 @@ pub async fn trigger_provider_login(provider: String) -> Result<String, String>
                     .spawn();
                 match result {
                     Ok(_) => Ok("Opening terminal window for Claude authentication. Please follow the instructions in the terminal.".to_string()),
-                    Err(e) => Err(format!("Failed to open terminal for claude auth: {}", e)),
+                    Err(e) => Err(CommandError::Io { path: None, message: format!("Failed to open terminal for claude auth: {}", e) }),
                 }
             } else if cfg!(target_os = "macos") {
                 // macOS: use AppleScript to open Terminal
@@ -319,7 +530,7 @@ pub async fn trigger_provider_login(provider: String) -> Result<String, String>
                     .spawn();
                 match result {
                     Ok(_) => Ok("Opening terminal window for Claude authentication. Please follow the instructions in the terminal.".to_string()),
-                    Err(e) => Err(format!("Failed to open terminal for claude auth: {}", e)),
+                    Err(e) => Err(CommandError::Io { path: None, message: format!("Failed to open terminal for claude auth: {}", e) }),
                 }
             } else {
                 // Linux: try common terminal emulators
@@ -340,7 +551,10 @@ pub async fn trigger_provider_login(provider: String) -> Result<String, String>
                 if success {
                     Ok("Opening terminal window for Claude authentication. Please follow the instructions in the terminal.".to_string())
                 } else {
-                    Err("Failed to open terminal. Please run 'claude setup-token' manually in your terminal.".to_string())
+                    Err(CommandError::Io {
+                        path: None,
+                        message: "Failed to open terminal. Please run 'claude setup-token' manually in your terminal.".to_string(),
+                    })
                 }
             }
         },
@@ -354,17 +568,17 @@ pub async fn trigger_provider_login(provider: String) -> Result<String, String>
                     .spawn();
                 match result {
                     Ok(_) => Ok("Login initiated. Gemini CLI will open browser for authentication.".to_string()),
-                    Err(e) => Err(format!("Failed to start gemini login: {}", e)),
+                    Err(e) => Err(CommandError::Io { path: None, message: format!("Failed to start gemini login: {}", e) }),
                 }
             } else {
                 let output = Command::new("gemini").arg("hello").output();
                 match output {
                     Ok(_) => Ok("Login initiated. Gemini CLI will open browser for authentication.".to_string()),
-                    Err(e) => Err(format!("Failed to start gemini login: {}", e)),
+                    Err(e) => Err(CommandError::Io { path: None, message: format!("Failed to start gemini login: {}", e) }),
                 }
             }
         },
-        _ => Err(format!("Unknown provider: {}", provider)),
+        _ => Err(CommandError::ProviderUnknown(provider)),
     };
 
     command
@@ -376,27 +590,134 @@ pub async fn trigger_provider_login(provider: String) -> Result<String, String>
 
 #[tauri::command]
 pub async fn approve_action(
+    bridge_state: tauri::State<'_, Arc<BridgeClient>>,
+    sessions: tauri::State<'_, Arc<SessionManager>>,
+    permissions: tauri::State<'_, Arc<PermissionBroker>>,
     action_id: String,
     apply_to_session: bool,
-) -> Result<(), String> {
-    println!("approve_action: id={}, apply_to_session={}", action_id, apply_to_session);
-    // TODO: Execute the approved action
+) -> Result<(), CommandError> {
+    debug!(%action_id, apply_to_session, "approve_action");
+
+    let action = take_pending_action(&permissions, &action_id)?;
+    if apply_to_session {
+        permissions.grant(&action);
+    }
+
+    let bridge_clone = get_bridge(&bridge_state)?;
+    let result = bridge_clone.respond_permission(action.profile, action_id, true).await?;
+
+    // The bridge resumes the turn once every request of the turn was answered
+    if result.get("resumed").and_then(|v| v.as_bool()).unwrap_or(false) {
+        sessions.set_state(&action.session_id, SessionState::Running);
+    }
+
     Ok(())
 }
 
 #[tauri::command]
 pub async fn reject_action(
+    bridge_state: tauri::State<'_, Arc<BridgeClient>>,
+    sessions: tauri::State<'_, Arc<SessionManager>>,
+    permissions: tauri::State<'_, Arc<PermissionBroker>>,
     action_id: String,
-) -> Result<(), String> {
-    println!("reject_action: id={}", action_id);
+) -> Result<(), CommandError> {
+    debug!(%action_id, "reject_action");
+
+    let action = take_pending_action(&permissions, &action_id)?;
+
+    let bridge_clone = get_bridge(&bridge_state)?;
+    let result = bridge_clone.respond_permission(action.profile, action_id, false).await?;
+
+    // Other requests of the same turn may still have been approved
+    if result.get("resumed").and_then(|v| v.as_bool()).unwrap_or(false) {
+        sessions.set_state(&action.session_id, SessionState::Running);
+    }
+
     Ok(())
 }
 
 #[tauri::command]
-pub async fn set_permission_mode(
-    _state: tauri::State<'_, Mutex<AppState>>,
-    _mode: String,
-) -> Result<(), String> {
-    // TODO: Update current profile's permission mode
+pub async fn list_pending_actions(
+    permissions: tauri::State<'_, Arc<PermissionBroker>>,
+) -> Result<Vec<PendingAction>, CommandError> {
+    Ok(permissions.pending())
+}
+
+#[tauri::command]
+pub async fn set_permission_mode<R: Runtime>(
+    app: AppHandle<R>,
+    state: tauri::State<'_, Mutex<AppState>>,
+    sessions: tauri::State<'_, Arc<SessionManager>>,
+    mode: String,
+) -> Result<(), CommandError> {
+    let mode: PermissionMode = mode.parse().map_err(CommandError::Validation)?;
+    let profile_id = state
+        .lock()
+        .unwrap()
+        .current_profile_id
+        .clone()
+        .ok_or_else(|| CommandError::Validation("No profile selected".to_string()))?;
+
+    let fallback_id = profile_id.clone();
+    profile_command(
+        &app,
+        "set_permission_mode",
+        async |bridge| bridge.set_permission_mode(profile_id.clone(), mode).await,
+        move |store| {
+            store.update(|data| {
+                let profile = data
+                    .profiles
+                    .get_mut(&fallback_id)
+                    .ok_or_else(|| CommandError::ProfileNotFound(fallback_id.clone()))?;
+                profile.permission_mode = Some(mode);
+                profile.touch();
+                Ok(serde_json::json!({ "success": true }))
+            })
+        },
+    )
+    .await?;
+    sessions.set_permission_mode(&profile_id, mode.as_str());
+
     Ok(())
 }
+
+// ============================================================================
+// Diagnostics Commands
+// ============================================================================
+
+/// State of the bridge connection; pings the bridge to measure latency when it is ready
+#[tauri::command]
+pub async fn bridge_health(
+    bridge_state: tauri::State<'_, Arc<BridgeClient>>,
+) -> Result<BridgeHealth, CommandError> {
+    let bridge_clone = get_bridge(&bridge_state)?;
+    Ok(bridge_clone.health().await)
+}
+
+/// Recent log entries, oldest first. `level` defaults to `info` and also
+/// includes more severe entries; `since` is in ms since the epoch.
+#[tauri::command]
+pub async fn get_logs(
+    logs: tauri::State<'_, Arc<LogBuffer>>,
+    level: Option<String>,
+    since: Option<u64>,
+    limit: Option<usize>,
+) -> Result<Vec<LogEntry>, CommandError> {
+    let level = match level {
+        Some(level) => level
+            .parse::<tracing::Level>()
+            .map_err(|_| CommandError::Validation(format!("Unknown log level: {}", level)))?,
+        None => tracing::Level::INFO,
+    };
+    Ok(logs.query(level, since, limit))
+}
+
+// Helper to claim a pending permission request
+fn take_pending_action(
+    permissions: &tauri::State<'_, Arc<PermissionBroker>>,
+    action_id: &str,
+) -> Result<PendingAction, CommandError> {
+    permissions
+        .take(action_id)
+        .ok_or_else(|| CommandError::Validation(format!("No pending action with id {}", action_id)))
+}
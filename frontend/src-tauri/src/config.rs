@@ -0,0 +1,68 @@
+use serde::Deserialize;
+use std::collections::HashMap;
+use std::path::PathBuf;
+use tracing::warn;
+
+/// Settings of the desktop app, read from `config.json` in the multicoder
+/// config directory. Keys used by other tools are ignored.
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+pub struct AppConfig {
+    pub bridge: BridgeConfig,
+}
+
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+pub struct BridgeConfig {
+    /// How to reach the bridge: `stdio` (default), `unix:<path>` or `tcp:<host>:<port>`
+    pub transport: Option<String>,
+    /// Bridge script (`dist/bridge/provider-bridge.js`) run by the stdio transport;
+    /// found automatically when unset
+    pub path: Option<String>,
+    /// Node executable running the bridge; found automatically when unset
+    pub node: Option<String>,
+    /// Inherited environment variables passed to a spawned bridge in addition to
+    /// the built-in allowlist; `PREFIX*` matches every variable starting with `PREFIX`
+    pub pass_env: Vec<String>,
+    /// Response deadline in ms of bridge requests without a deadline of their own
+    pub timeout_ms: Option<u64>,
+    /// Response deadlines in ms of single bridge methods, e.g. `sendMessage`
+    pub method_timeouts_ms: HashMap<String, u64>,
+}
+
+/// Config directory set through `MULTICODER_CONFIG_DIR`, or the legacy `UNYCODING_CONFIG_DIR`
+pub fn custom_config_dir() -> Option<PathBuf> {
+    ["MULTICODER_CONFIG_DIR", "UNYCODING_CONFIG_DIR"]
+        .into_iter()
+        .filter_map(std::env::var_os)
+        .find(|dir| !dir.is_empty())
+        .map(PathBuf::from)
+}
+
+/// The multicoder config directory: a custom one, or `~/.multicoder`
+pub fn config_dir() -> Option<PathBuf> {
+    custom_config_dir().or_else(|| dirs::home_dir().map(|home| home.join(".multicoder")))
+}
+
+impl AppConfig {
+    /// Load `config.json`; a missing or invalid file yields the defaults
+    pub fn load() -> Self {
+        let Some(path) = config_dir().map(|dir| dir.join("config.json")) else {
+            return Self::default();
+        };
+
+        let source = match std::fs::read_to_string(&path) {
+            Ok(source) => source,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Self::default(),
+            Err(e) => {
+                warn!("failed to read {}: {}", path.display(), e);
+                return Self::default();
+            }
+        };
+
+        serde_json::from_str(&source).unwrap_or_else(|e| {
+            warn!("ignoring invalid {}: {}", path.display(), e);
+            Self::default()
+        })
+    }
+}
@@ -0,0 +1,269 @@
+//! Environment of the spawned bridge. The app's own environment may carry
+//! provider API keys that would silently override the credentials of the
+//! selected profile, so the bridge only inherits an allowlist of variables.
+//! Names are matched ignoring case, as on Windows.
+//!
+//! A provider key the user names exactly in `bridge.passEnv` is passed on;
+//! profiles whose credentials come from the environment rely on that.
+
+use std::ffi::OsString;
+use tokio::process::Command;
+use tracing::{info, warn};
+
+/// Environment variable the bridge reads its config directory from
+pub const CONFIG_DIR_ENV: &str = "MULTICODER_CONFIG_DIR";
+
+/// Environment variable the bridge reads its log level from
+pub const LOG_LEVEL_ENV: &str = "MULTICODER_LOG_LEVEL";
+
+/// Inherited variables the bridge and the provider CLIs need to run
+const PASS_THROUGH: &[&str] = &[
+    // Executables and the user's home and config locations
+    "PATH",
+    "HOME",
+    "USER",
+    "LOGNAME",
+    "SHELL",
+    "TMPDIR",
+    "TERM",
+    "LANG",
+    "LANGUAGE",
+    "TZ",
+    // Desktop session, for browser logins and the system keyring
+    "DISPLAY",
+    "WAYLAND_DISPLAY",
+    "XAUTHORITY",
+    "DBUS_SESSION_BUS_ADDRESS",
+    "BROWSER",
+    // Git over SSH
+    "SSH_AUTH_SOCK",
+    // Network settings
+    "HTTP_PROXY",
+    "HTTPS_PROXY",
+    "ALL_PROXY",
+    "NO_PROXY",
+    "SSL_CERT_FILE",
+    "SSL_CERT_DIR",
+    "NODE_EXTRA_CA_CERTS",
+    // Where the provider CLIs keep their state
+    "CLAUDE_CONFIG_DIR",
+    "CODEX_HOME",
+    "GEMINI_HOME_DIR",
+    // Windows
+    "SYSTEMROOT",
+    "SYSTEMDRIVE",
+    "WINDIR",
+    "COMSPEC",
+    "PATHEXT",
+    "USERPROFILE",
+    "USERNAME",
+    "APPDATA",
+    "LOCALAPPDATA",
+    "PROGRAMDATA",
+    "PROGRAMFILES",
+    "PROGRAMFILES(X86)",
+    "TEMP",
+    "TMP",
+];
+
+/// Inherited variables passed through by prefix
+const PASS_THROUGH_PREFIXES: &[&str] = &["LC_", "XDG_", "MULTICODER_", "UNYCODING_"];
+
+/// Provider credentials; only inherited when `bridge.passEnv` names them exactly,
+/// not through a `PREFIX*` pattern. Any other `*_API_KEY` variable is treated the same.
+const PROVIDER_SECRETS: &[&str] = &[
+    "ANTHROPIC_API_KEY",
+    "ANTHROPIC_AUTH_TOKEN",
+    "GOOGLE_API_KEY",
+    "GEMINI_API_KEY",
+    "OPENAI_API_KEY",
+    "AZURE_OPENAI_API_KEY",
+    "AWS_ACCESS_KEY_ID",
+    "AWS_SECRET_ACCESS_KEY",
+    "AWS_SESSION_TOKEN",
+];
+
+fn is_provider_secret(name: &str) -> bool {
+    PROVIDER_SECRETS.iter().any(|secret| secret.eq_ignore_ascii_case(name)) || name.ends_with("_API_KEY")
+}
+
+/// Whether `pattern` matches `name`; a trailing `*` matches any suffix
+fn matches(pattern: &str, name: &str) -> bool {
+    match pattern.strip_suffix('*') {
+        Some(prefix) => name.get(..prefix.len()).is_some_and(|start| start.eq_ignore_ascii_case(prefix)),
+        None => pattern.eq_ignore_ascii_case(name),
+    }
+}
+
+/// Which inherited variables reach the bridge and what is set on top of them
+#[derive(Debug, Clone, Default)]
+pub struct EnvPolicy {
+    /// Names or `PREFIX*` patterns allowed in addition to the built-in ones
+    pass: Vec<String>,
+    /// Variables set by the app, overriding inherited ones
+    set: Vec<(String, OsString)>,
+}
+
+impl EnvPolicy {
+    pub fn new(pass: Vec<String>) -> Self {
+        Self { pass, set: Vec::new() }
+    }
+
+    /// Set `name` for the bridge, whatever the app inherited
+    pub fn set(mut self, name: &str, value: impl Into<OsString>) -> Self {
+        self.set.push((name.to_string(), value.into()));
+        self
+    }
+
+    /// Whether `bridge.passEnv` names `name` itself rather than through a pattern
+    fn names_exactly(&self, name: &str) -> bool {
+        self.pass.iter().any(|pattern| !pattern.ends_with('*') && pattern.eq_ignore_ascii_case(name))
+    }
+
+    fn allows(&self, name: &str) -> bool {
+        PASS_THROUGH.iter().any(|allowed| allowed.eq_ignore_ascii_case(name))
+            || PASS_THROUGH_PREFIXES.iter().any(|prefix| matches(&format!("{}*", prefix), name))
+            || self.pass.iter().any(|pattern| matches(pattern, name))
+    }
+
+    /// Filter `inherited` down to the bridge's environment
+    pub fn apply(&self, inherited: impl IntoIterator<Item = (OsString, OsString)>) -> BridgeEnv {
+        let mut env = BridgeEnv::default();
+        for (name, value) in inherited {
+            let Some(key) = name.to_str() else {
+                env.dropped.push(name.to_string_lossy().into_owned());
+                continue;
+            };
+            let key = key.to_string();
+            if is_provider_secret(&key.to_ascii_uppercase()) && !self.names_exactly(&key) {
+                env.removed_secrets.push(key);
+            } else if self.set.iter().any(|(set, _)| set.eq_ignore_ascii_case(&key)) {
+                // Replaced below
+            } else if self.allows(&key) {
+                env.vars.push((name, value));
+            } else {
+                env.dropped.push(key);
+            }
+        }
+        env.vars
+            .extend(self.set.iter().map(|(name, value)| (OsString::from(name), value.clone())));
+        env.dropped.sort();
+        env.removed_secrets.sort();
+        env
+    }
+
+    /// Filter the app's own environment down to the bridge's environment
+    pub fn apply_current(&self) -> BridgeEnv {
+        self.apply(std::env::vars_os())
+    }
+}
+
+/// The bridge's environment and what was left out of it
+#[derive(Debug, Default)]
+pub struct BridgeEnv {
+    vars: Vec<(OsString, OsString)>,
+    /// Inherited variables not on the allowlist
+    pub dropped: Vec<String>,
+    /// Inherited provider credentials
+    pub removed_secrets: Vec<String>,
+}
+
+impl BridgeEnv {
+    /// Replace the environment of `command` with this one
+    pub fn configure(&self, command: &mut Command) {
+        command.env_clear().envs(self.vars.iter().map(|(name, value)| (name, value)));
+    }
+
+    /// Log which inherited variables the bridge does not get
+    pub fn report(&self) {
+        if !self.removed_secrets.is_empty() {
+            warn!(
+                "not passing provider credentials {} to the bridge; profiles supply credentials (name a variable in bridge.passEnv to pass it)",
+                self.removed_secrets.join(", ")
+            );
+        }
+        if !self.dropped.is_empty() {
+            info!(
+                "not passing {} inherited variables to the bridge (allow more with bridge.passEnv): {}",
+                self.dropped.len(),
+                self.dropped.join(", ")
+            );
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn inherited(vars: &[(&str, &str)]) -> Vec<(OsString, OsString)> {
+        vars.iter().map(|(name, value)| (OsString::from(name), OsString::from(value))).collect()
+    }
+
+    fn names(env: &BridgeEnv) -> Vec<String> {
+        env.vars.iter().map(|(name, _)| name.to_string_lossy().into_owned()).collect()
+    }
+
+    #[test]
+    fn passes_only_allowed_variables() {
+        let env = EnvPolicy::default().apply(inherited(&[
+            ("PATH", "/usr/bin"),
+            ("HOME", "/home/me"),
+            ("LC_ALL", "C.UTF-8"),
+            ("Path", "C:\\Windows"),
+            ("npm_config_prefix", "/opt/npm"),
+            ("DISPLAY", ":0"),
+            ("XAUTHORITY", "/run/user/1000/xauth"),
+            ("BROWSER", "firefox"),
+            ("SSH_AUTH_SOCK", "/run/user/1000/ssh-agent"),
+            ("GITHUB_TOKEN", "ghp_1"),
+        ]));
+
+        assert_eq!(
+            names(&env),
+            ["PATH", "HOME", "LC_ALL", "Path", "DISPLAY", "XAUTHORITY", "BROWSER", "SSH_AUTH_SOCK"]
+        );
+        assert_eq!(env.dropped, ["GITHUB_TOKEN", "npm_config_prefix"]);
+        assert!(env.removed_secrets.is_empty());
+    }
+
+    #[test]
+    fn removes_provider_keys_unless_named_exactly() {
+        let policy = EnvPolicy::new(vec!["ANTHROPIC_*".to_string(), "MISTRAL_API_KEY".to_string()]);
+        let env = policy.apply(inherited(&[
+            ("ANTHROPIC_API_KEY", "sk-ant-1"),
+            ("ANTHROPIC_BASE_URL", "https://proxy"),
+            ("OPENAI_API_KEY", "sk-2"),
+            ("gemini_api_key", "AIza"),
+            ("MISTRAL_API_KEY", "m"),
+        ]));
+
+        assert_eq!(names(&env), ["ANTHROPIC_BASE_URL", "MISTRAL_API_KEY"]);
+        assert_eq!(env.removed_secrets, ["ANTHROPIC_API_KEY", "OPENAI_API_KEY", "gemini_api_key"]);
+        assert!(env.dropped.is_empty());
+    }
+
+    #[test]
+    fn passes_the_key_of_an_env_credential_profile() {
+        let policy = EnvPolicy::new(vec!["anthropic_api_key".to_string()]);
+        let env = policy.apply(inherited(&[("ANTHROPIC_API_KEY", "sk-ant-1"), ("OPENAI_API_KEY", "sk-2")]));
+
+        assert_eq!(names(&env), ["ANTHROPIC_API_KEY"]);
+        assert_eq!(env.removed_secrets, ["OPENAI_API_KEY"]);
+    }
+
+    #[test]
+    fn injected_settings_override_inherited_ones() {
+        let policy = EnvPolicy::default().set(CONFIG_DIR_ENV, "/app/config").set(LOG_LEVEL_ENV, "debug");
+        let env = policy.apply(inherited(&[("MULTICODER_CONFIG_DIR", "/elsewhere"), ("MULTICODER_GEMINI_HOME", "/g")]));
+
+        assert_eq!(
+            env.vars,
+            inherited(&[
+                ("MULTICODER_GEMINI_HOME", "/g"),
+                ("MULTICODER_CONFIG_DIR", "/app/config"),
+                ("MULTICODER_LOG_LEVEL", "debug"),
+            ])
+        );
+    }
+}
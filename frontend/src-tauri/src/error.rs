@@ -0,0 +1,172 @@
+use serde::ser::{Serialize, SerializeStruct, Serializer};
+
+use crate::bridge::{BridgeError, RpcErrorCode};
+
+/// Error returned by every Tauri command.
+///
+/// Serialized as `{ code, message, details? }` where `code` is a stable
+/// SCREAMING_SNAKE_CASE identifier the frontend can match on.
+#[derive(Debug, Clone, PartialEq)]
+pub enum CommandError {
+    /// The bridge is not running, restarting, or died while handling the request
+    BridgeUnavailable(String),
+    /// The bridge did not answer within the method's deadline
+    BridgeTimeout { method: String, timeout_ms: u64 },
+    /// The bridge speaks an incompatible protocol version
+    BridgeIncompatible { expected: String, found: Option<String> },
+    /// Any other error reported by the bridge
+    Bridge { code: i64, message: String, data: Option<serde_json::Value> },
+    ProfileNotFound(String),
+    SessionNotFound(String),
+    AuthRequired { provider: Option<String>, message: String },
+    ProviderUnknown(String),
+    Io { path: Option<String>, message: String },
+    Validation(String),
+    Internal(String),
+}
+
+impl CommandError {
+    /// Stable identifier of the error kind
+    pub fn code(&self) -> &'static str {
+        match self {
+            CommandError::BridgeUnavailable(_) => "BRIDGE_UNAVAILABLE",
+            CommandError::BridgeTimeout { .. } => "BRIDGE_TIMEOUT",
+            CommandError::BridgeIncompatible { .. } => "BRIDGE_INCOMPATIBLE",
+            CommandError::Bridge { .. } => "BRIDGE_ERROR",
+            CommandError::ProfileNotFound(_) => "PROFILE_NOT_FOUND",
+            CommandError::SessionNotFound(_) => "SESSION_NOT_FOUND",
+            CommandError::AuthRequired { .. } => "AUTH_REQUIRED",
+            CommandError::ProviderUnknown(_) => "PROVIDER_UNKNOWN",
+            CommandError::Io { .. } => "IO",
+            CommandError::Validation(_) => "VALIDATION",
+            CommandError::Internal(_) => "INTERNAL",
+        }
+    }
+
+    /// Machine readable context for the error, if any
+    pub fn details(&self) -> Option<serde_json::Value> {
+        match self {
+            CommandError::BridgeTimeout { method, timeout_ms } => Some(serde_json::json!({
+                "method": method,
+                "timeoutMs": timeout_ms,
+            })),
+            CommandError::BridgeIncompatible { expected, found } => Some(serde_json::json!({
+                "expected": expected,
+                "found": found,
+            })),
+            CommandError::Bridge { code, data, .. } => Some(serde_json::json!({
+                "rpcCode": code,
+                "data": data,
+            })),
+            CommandError::ProfileNotFound(profile) => Some(serde_json::json!({ "profile": profile })),
+            CommandError::SessionNotFound(session_id) => {
+                Some(serde_json::json!({ "sessionId": session_id }))
+            }
+            CommandError::AuthRequired { provider: Some(provider), .. } => {
+                Some(serde_json::json!({ "provider": provider }))
+            }
+            CommandError::ProviderUnknown(provider) => Some(serde_json::json!({ "provider": provider })),
+            CommandError::Io { path: Some(path), .. } => Some(serde_json::json!({ "path": path })),
+            _ => None,
+        }
+    }
+
+    /// Shorthand for an I/O error while trying to `operation` (read, write, ...) `path`
+    pub fn io(operation: &str, path: impl Into<String>, error: impl std::fmt::Display) -> Self {
+        let path = path.into();
+        CommandError::Io {
+            message: format!("Failed to {} {}: {}", operation, path, error),
+            path: Some(path),
+        }
+    }
+}
+
+impl std::fmt::Display for CommandError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            CommandError::BridgeUnavailable(message)
+            | CommandError::Validation(message)
+            | CommandError::Internal(message) => write!(f, "{}", message),
+            CommandError::BridgeTimeout { method, timeout_ms } => write!(
+                f,
+                "Bridge request '{}' timed out after {} seconds",
+                method,
+                timeout_ms / 1000
+            ),
+            CommandError::BridgeIncompatible { expected, found } => write!(
+                f,
+                "Bridge protocol version {} is not compatible with this app (expected {}). Please reinstall or rebuild the bridge.",
+                found.as_deref().unwrap_or("unknown"),
+                expected
+            ),
+            CommandError::Bridge { message, .. }
+            | CommandError::AuthRequired { message, .. }
+            | CommandError::Io { message, .. } => write!(f, "{}", message),
+            CommandError::ProfileNotFound(profile) => write!(f, "Profile {} not found", profile),
+            CommandError::SessionNotFound(session_id) => write!(f, "Session {} not found", session_id),
+            CommandError::ProviderUnknown(provider) => write!(f, "Unknown provider: {}", provider),
+        }
+    }
+}
+
+impl std::error::Error for CommandError {}
+
+impl Serialize for CommandError {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let details = self.details();
+        let mut state =
+            serializer.serialize_struct("CommandError", if details.is_some() { 3 } else { 2 })?;
+        state.serialize_field("code", self.code())?;
+        state.serialize_field("message", &self.to_string())?;
+        if let Some(details) = details {
+            state.serialize_field("details", &details)?;
+        }
+        state.end()
+    }
+}
+
+impl From<BridgeError> for CommandError {
+    fn from(error: BridgeError) -> Self {
+        match error {
+            BridgeError::Unavailable(_) | BridgeError::Terminated | BridgeError::Io(_) => {
+                CommandError::BridgeUnavailable(error.to_string())
+            }
+            BridgeError::Timeout { method, after } => CommandError::BridgeTimeout {
+                method,
+                timeout_ms: after.as_millis() as u64,
+            },
+            BridgeError::IncompatibleProtocol { expected, found } => {
+                CommandError::BridgeIncompatible { expected, found }
+            }
+            BridgeError::Rpc(error) => {
+                let data_field = |key: &str| {
+                    error
+                        .data
+                        .as_ref()
+                        .and_then(|data| data.get(key))
+                        .and_then(|value| value.as_str())
+                        .map(str::to_string)
+                };
+
+                match error.kind() {
+                    RpcErrorCode::ProfileNotFound => CommandError::ProfileNotFound(
+                        data_field("profile").unwrap_or(error.message),
+                    ),
+                    RpcErrorCode::AuthRequired => CommandError::AuthRequired {
+                        provider: data_field("provider"),
+                        message: error.message,
+                    },
+                    RpcErrorCode::ProviderUnknown => CommandError::ProviderUnknown(
+                        data_field("provider").unwrap_or(error.message),
+                    ),
+                    RpcErrorCode::InvalidParams => CommandError::Validation(error.message),
+                    _ => CommandError::Bridge {
+                        code: error.code,
+                        message: error.message,
+                        data: error.data,
+                    },
+                }
+            }
+        }
+    }
+}
@@ -0,0 +1,282 @@
+//! Scriptable in-process bridge for tests.
+//!
+//! `FakeBridge` implements [`BridgeTransport`], so a real [`BridgeClient`]
+//! talks JSON-RPC to it over an in-memory pipe. Replies are scripted per
+//! method: a result or error, notifications sent before it, a delay, or
+//! dropping the connection to simulate a crash.
+//!
+//! [`BridgeClient`]: crate::bridge::BridgeClient
+
+use serde_json::{json, Value};
+use std::collections::{HashMap, VecDeque};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use tokio::sync::{mpsc, watch};
+
+use crate::bridge::PROTOCOL_VERSION;
+use crate::framing::{read_frame, write_frame};
+use crate::transport::{BoxFuture, BridgeConnection, BridgeTransport};
+
+/// Scripted reply to one request
+#[derive(Debug, Clone)]
+pub struct Reply {
+    outcome: Outcome,
+    notifications: Vec<(String, Value)>,
+    delay: Duration,
+    close: bool,
+}
+
+#[derive(Debug, Clone)]
+enum Outcome {
+    Result(Value),
+    Error { code: i64, message: String, data: Option<Value> },
+    /// Close the connection instead of answering
+    Drop,
+}
+
+impl Reply {
+    pub fn result(result: Value) -> Self {
+        Self::with(Outcome::Result(result))
+    }
+
+    pub fn error(code: i64, message: &str, data: Option<Value>) -> Self {
+        Self::with(Outcome::Error {
+            code,
+            message: message.to_string(),
+            data,
+        })
+    }
+
+    /// Close the connection once the request arrived, as if the bridge crashed
+    pub fn drop_connection() -> Self {
+        Self::with(Outcome::Drop)
+    }
+
+    fn with(outcome: Outcome) -> Self {
+        Self {
+            outcome,
+            notifications: Vec::new(),
+            delay: Duration::ZERO,
+            close: false,
+        }
+    }
+
+    /// Send a notification before the reply
+    pub fn notify(mut self, method: &str, params: Value) -> Self {
+        self.notifications.push((method.to_string(), params));
+        self
+    }
+
+    /// Wait before replying
+    pub fn delay(mut self, delay: Duration) -> Self {
+        self.delay = delay;
+        self
+    }
+
+    /// Close the connection after replying, like the bridge does after `shutdown`
+    pub fn then_close(mut self) -> Self {
+        self.close = true;
+        self
+    }
+}
+
+#[derive(Debug, Default)]
+struct Script {
+    /// Replies per method, used in order; the last one keeps answering
+    replies: HashMap<String, VecDeque<Reply>>,
+    /// Every request received, as (method, params)
+    requests: Vec<(String, Value)>,
+    /// Ids of the requests received that expect a response, as (method, id)
+    ids: Vec<(String, Value)>,
+}
+
+impl Script {
+    fn next_reply(&mut self, method: &str) -> Option<Reply> {
+        let replies = self.replies.get_mut(method)?;
+        if replies.len() > 1 {
+            replies.pop_front()
+        } else {
+            replies.front().cloned()
+        }
+    }
+}
+
+/// In-process bridge answering with scripted replies. Clones share the script.
+#[derive(Debug, Clone, Default)]
+pub struct FakeBridge {
+    script: Arc<Mutex<Script>>,
+    connections: Arc<AtomicUsize>,
+}
+
+impl FakeBridge {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Queue a reply for `method`
+    pub fn on(self, method: &str, reply: Reply) -> Self {
+        self.script
+            .lock()
+            .unwrap()
+            .replies
+            .entry(method.to_string())
+            .or_default()
+            .push_back(reply);
+        self
+    }
+
+    /// Queue a successful result for `method`
+    pub fn respond(self, method: &str, result: Value) -> Self {
+        self.on(method, Reply::result(result))
+    }
+
+    /// Requests received so far, as (method, params)
+    pub fn requests(&self) -> Vec<(String, Value)> {
+        self.script.lock().unwrap().requests.clone()
+    }
+
+    /// Params of the requests received for `method`
+    pub fn requests_for(&self, method: &str) -> Vec<Value> {
+        self.requests()
+            .into_iter()
+            .filter(|(name, _)| name == method)
+            .map(|(_, params)| params)
+            .collect()
+    }
+
+    /// Ids of the requests received for `method`
+    pub fn request_ids_for(&self, method: &str) -> Vec<Value> {
+        self.script
+            .lock()
+            .unwrap()
+            .ids
+            .iter()
+            .filter(|(name, _)| name == method)
+            .map(|(_, id)| id.clone())
+            .collect()
+    }
+
+    /// Number of times a client connected
+    pub fn connections(&self) -> usize {
+        self.connections.load(Ordering::SeqCst)
+    }
+
+    /// Serve one connection until the client goes away or a reply drops it
+    async fn serve(self, stream: tokio::io::DuplexStream) {
+        let (mut reader, mut writer) = tokio::io::split(stream);
+        let (out_tx, mut out_rx) = mpsc::unbounded_channel::<String>();
+        let (closed_tx, mut closed) = watch::channel(false);
+
+        let writer_closed = closed_tx.clone();
+        let writer_task = tokio::spawn(async move {
+            while let Some(message) = out_rx.recv().await {
+                // Empty: close once everything queued before it was written
+                if message.is_empty() {
+                    writer_closed.send_replace(true);
+                    break;
+                }
+                if write_frame(&mut writer, message.as_bytes()).await.is_err() {
+                    break;
+                }
+            }
+            writer
+        });
+
+        let ready = json!({
+            "jsonrpc": "2.0",
+            "method": "ready",
+            "params": { "status": "initialized", "protocolVersion": PROTOCOL_VERSION },
+        });
+        let _ = out_tx.send(ready.to_string());
+
+        loop {
+            let frame = tokio::select! {
+                frame = read_frame(&mut reader) => match frame {
+                    Ok(Some(frame)) => frame,
+                    _ => break,
+                },
+                _ = closed.changed() => break,
+            };
+
+            let Ok(request) = serde_json::from_slice::<Value>(&frame) else {
+                continue;
+            };
+            let method = request["method"].as_str().unwrap_or_default().to_string();
+            let params = request.get("params").cloned().unwrap_or(Value::Null);
+            let id = request.get("id").cloned();
+
+            let reply = {
+                let mut script = self.script.lock().unwrap();
+                script.requests.push((method.clone(), params));
+                if let Some(id) = &id {
+                    script.ids.push((method.clone(), id.clone()));
+                }
+                script.next_reply(&method)
+            };
+
+            // Notifications from the client get no reply
+            let Some(id) = id else {
+                continue;
+            };
+
+            let out_tx = out_tx.clone();
+            let closed_tx = closed_tx.clone();
+            tokio::spawn(async move {
+                let reply = reply.unwrap_or_else(|| {
+                    Reply::error(-32601, &format!("Unknown method: {}", method), None)
+                });
+                tokio::time::sleep(reply.delay).await;
+
+                for (method, params) in reply.notifications {
+                    let notification = json!({ "jsonrpc": "2.0", "method": method, "params": params });
+                    let _ = out_tx.send(notification.to_string());
+                }
+
+                let response = match reply.outcome {
+                    Outcome::Result(result) => json!({ "jsonrpc": "2.0", "id": id, "result": result }),
+                    Outcome::Error { code, message, data } => json!({
+                        "jsonrpc": "2.0",
+                        "id": id,
+                        "error": { "code": code, "message": message, "data": data },
+                    }),
+                    Outcome::Drop => {
+                        closed_tx.send_replace(true);
+                        return;
+                    }
+                };
+                let _ = out_tx.send(response.to_string());
+                if reply.close {
+                    let _ = out_tx.send(String::new());
+                }
+            });
+        }
+
+        // Dropping both halves closes the pipe, which the client sees as EOF
+        drop(out_tx);
+        writer_task.abort();
+        let _ = writer_task.await;
+    }
+}
+
+impl BridgeTransport for FakeBridge {
+    fn describe(&self) -> String {
+        "fake bridge".to_string()
+    }
+
+    fn connect(&self) -> BoxFuture<'_, Result<BridgeConnection, String>> {
+        Box::pin(async move {
+            self.connections.fetch_add(1, Ordering::SeqCst);
+
+            let (client, server) = tokio::io::duplex(64 * 1024);
+            tokio::spawn(self.clone().serve(server));
+
+            let (reader, writer) = tokio::io::split(client);
+            Ok(BridgeConnection {
+                reader: Box::new(reader),
+                writer: Box::new(writer),
+                child: None,
+            })
+        })
+    }
+}
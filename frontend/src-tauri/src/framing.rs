@@ -0,0 +1,114 @@
+//! Length-prefixed framing of the bridge channel: every message is a 4-byte
+//! big-endian payload length followed by the payload (UTF-8 JSON-RPC).
+//! See `framing.ts` for the bridge side.
+
+use std::io;
+use tokio::io::{AsyncRead, AsyncReadExt, AsyncWrite, AsyncWriteExt};
+
+/// Largest payload either side accepts; guards against reading garbage as a length
+pub const MAX_FRAME_LEN: usize = 64 * 1024 * 1024;
+
+/// Read one frame. Returns `None` on a clean end of stream between frames.
+pub async fn read_frame<R: AsyncRead + Unpin>(reader: &mut R) -> io::Result<Option<Vec<u8>>> {
+    let mut header = [0u8; 4];
+    let mut filled = 0;
+    while filled < header.len() {
+        match reader.read(&mut header[filled..]).await? {
+            0 if filled == 0 => return Ok(None),
+            0 => {
+                return Err(io::Error::new(
+                    io::ErrorKind::UnexpectedEof,
+                    format!("stream ended after {} of 4 header bytes", filled),
+                ))
+            }
+            n => filled += n,
+        }
+    }
+
+    let len = u32::from_be_bytes(header) as usize;
+    if len > MAX_FRAME_LEN {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidData,
+            format!("frame of {} bytes exceeds the limit of {} bytes", len, MAX_FRAME_LEN),
+        ));
+    }
+
+    let mut payload = vec![0u8; len];
+    reader.read_exact(&mut payload).await?;
+    Ok(Some(payload))
+}
+
+/// Write one frame and flush it
+pub async fn write_frame<W: AsyncWrite + Unpin>(writer: &mut W, payload: &[u8]) -> io::Result<()> {
+    if payload.len() > MAX_FRAME_LEN {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidInput,
+            format!("frame of {} bytes exceeds the limit of {} bytes", payload.len(), MAX_FRAME_LEN),
+        ));
+    }
+
+    writer.write_all(&(payload.len() as u32).to_be_bytes()).await?;
+    writer.write_all(payload).await?;
+    writer.flush().await
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn round_trips_frames() {
+        let mut buffer = Vec::new();
+        write_frame(&mut buffer, br#"{"jsonrpc":"2.0"}"#).await.unwrap();
+        write_frame(&mut buffer, b"").await.unwrap();
+        assert_eq!(&buffer[..4], &[0, 0, 0, 17]);
+
+        let mut reader = buffer.as_slice();
+        assert_eq!(read_frame(&mut reader).await.unwrap(), Some(br#"{"jsonrpc":"2.0"}"#.to_vec()));
+        assert_eq!(read_frame(&mut reader).await.unwrap(), Some(Vec::new()));
+        assert_eq!(read_frame(&mut reader).await.unwrap(), None);
+    }
+
+    #[tokio::test]
+    async fn reassembles_frames_split_across_reads() {
+        let mut frame = Vec::new();
+        write_frame(&mut frame, b"hello").await.unwrap();
+        let mut reader = chunked_reader(&[&frame[..1], &frame[1..3], &frame[3..6], &frame[6..]]);
+        assert_eq!(read_frame(&mut reader).await.unwrap(), Some(b"hello".to_vec()));
+        assert_eq!(read_frame(&mut reader).await.unwrap(), None);
+    }
+
+    #[tokio::test]
+    async fn rejects_oversized_frames() {
+        let header = ((MAX_FRAME_LEN + 1) as u32).to_be_bytes();
+        let error = read_frame(&mut header.as_slice()).await.unwrap_err();
+        assert_eq!(error.kind(), io::ErrorKind::InvalidData);
+
+        let error = write_frame(&mut Vec::new(), &vec![0; MAX_FRAME_LEN + 1]).await.unwrap_err();
+        assert_eq!(error.kind(), io::ErrorKind::InvalidInput);
+    }
+
+    #[tokio::test]
+    async fn truncated_frames_are_errors() {
+        let mut frame = Vec::new();
+        write_frame(&mut frame, b"hello").await.unwrap();
+
+        for cut in [1, 3, 4, 8] {
+            let error = read_frame(&mut &frame[..cut]).await.unwrap_err();
+            assert_eq!(error.kind(), io::ErrorKind::UnexpectedEof, "cut after {} bytes", cut);
+        }
+    }
+
+    /// A reader returning `chunks` one read at a time
+    fn chunked_reader(chunks: &[&[u8]]) -> impl AsyncRead + Unpin {
+        let (mut writer, reader) = tokio::io::duplex(64);
+        let chunks: Vec<Vec<u8>> = chunks.iter().map(|chunk| chunk.to_vec()).collect();
+        tokio::spawn(async move {
+            for chunk in chunks {
+                writer.write_all(&chunk).await.unwrap();
+                tokio::task::yield_now().await;
+            }
+        });
+        reader
+    }
+}
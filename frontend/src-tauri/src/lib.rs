@@ -1,28 +1,127 @@
 mod commands;
 mod state;
 mod bridge;
+mod error;
+mod session;
+mod permissions;
+mod policy;
+mod config;
+mod transport;
+mod node;
+mod framing;
+mod logging;
+mod redact;
+mod process;
+mod environment;
+mod bridge_script;
+mod profile_store;
+mod profile_watcher;
+mod profile_bundle;
+#[cfg(test)]
+mod fake_bridge;
+#[cfg(test)]
+mod command_tests;
 
+use std::sync::atomic::{AtomicBool, Ordering};
 use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use config::AppConfig;
+use permissions::PermissionBroker;
+use policy::PolicyEngine;
+use session::SessionManager;
 use state::AppState;
-use tauri::Manager;
+use tauri::{Manager, RunEvent};
+use tracing::{error, info, warn};
+
+/// Load the auto-approval policies from `policies.toml` (or `.json`) in the config directory
+fn load_policies() -> PolicyEngine {
+    let Some(config_dir) = config::config_dir() else {
+        return PolicyEngine::empty();
+    };
+
+    let toml_path = config_dir.join("policies.toml");
+    let path = if toml_path.exists() { toml_path } else { config_dir.join("policies.json") };
+    match PolicyEngine::load(&path) {
+        Ok(policies) => policies,
+        Err(e) => {
+            warn!("ignoring permission policies: {}", e);
+            PolicyEngine::empty()
+        }
+    }
+}
 
 #[cfg_attr(mobile, tauri::mobile_entry_point)]
 pub fn run() {
+    let bridge_stopping = AtomicBool::new(false);
+
     tauri::Builder::default()
         .plugin(tauri_plugin_opener::init())
         .manage(Mutex::new(AppState::new()))
         .setup(|app| {
+            let logs = logging::init(app.path().app_log_dir().ok().as_deref())?;
+            app.manage(logs);
+
+            // Sessions are shared between the bridge (which ends them) and the commands
+            let sessions = Arc::new(SessionManager::new());
+            app.manage(Arc::clone(&sessions));
+
+            // Pending permission requests are recorded by the bridge and answered by commands
+            let permissions = Arc::new(PermissionBroker::with_policy(load_policies()));
+            app.manage(Arc::clone(&permissions));
+
+            // Lets profiles be listed while the bridge is down, and follows changes by the CLI
+            if let Some(store) = profile_store::ProfileStore::from_env() {
+                let store = Arc::new(store);
+                app.manage(Arc::clone(&store));
+                match profile_watcher::ProfileWatcher::start(app.handle().clone(), store) {
+                    Ok(watcher) => {
+                        app.manage(watcher);
+                    }
+                    Err(e) => warn!("not watching profiles for changes: {}", e),
+                }
+            }
+
             // Initialize bridge client wrapped in Arc
             let app_handle = app.handle().clone();
-            match bridge::BridgeClient::new(app_handle.clone()) {
+            let config = AppConfig::load();
+            let client = bridge::BridgeClient::transport_from_config(&app_handle, &config.bridge)
+                .and_then(|transport| {
+                    bridge::BridgeClient::new(Arc::new(app_handle.clone()), transport, sessions, permissions)
+                })
+                .inspect(|client| {
+                    if let Some(timeout) = config.bridge.timeout_ms {
+                        client.set_default_request_timeout(Duration::from_millis(timeout));
+                    }
+                    for (method, timeout) in &config.bridge.method_timeouts_ms {
+                        client.set_request_timeout(method, Duration::from_millis(*timeout));
+                    }
+                });
+            match client {
                 Ok(client) => {
                     // Wrap in Arc so cloning only increases reference count
-                    app.manage(Arc::new(client));
-                    println!("Bridge client initialized successfully");
+                    let client = Arc::new(client);
+                    app.manage(Arc::clone(&client));
+                    info!("bridge client initialized, waiting for bridge in the background");
+
+                    // Startup no longer blocks the UI; just report when the bridge comes up
+                    tauri::async_runtime::spawn(async move {
+                        if !client.wait_until_ready(Duration::from_secs(10)).await {
+                            warn!("bridge service did not send ready event within 10 seconds, some features may not work");
+                            return;
+                        }
+
+                        // Seed AppState with the profile the bridge restored
+                        match client.get_current_profile().await {
+                            Ok(result) => {
+                                let app_state = app_handle.state::<Mutex<AppState>>();
+                                commands::sync_current_profile(&app_handle, &app_state, commands::profile_name(&result));
+                            }
+                            Err(e) => error!("failed to load current profile: {}", e),
+                        }
+                    });
                 }
                 Err(e) => {
-                    eprintln!("Failed to initialize bridge client: {}", e);
-                    eprintln!("WARNING: Application will not function properly without bridge client!");
+                    error!("failed to initialize bridge client, the application will not work without it: {}", e);
                 }
             }
             Ok(())
@@ -31,6 +130,10 @@ pub fn run() {
             // Chat commands
             commands::send_message,
             commands::stop_message_stream,
+            // Session commands
+            commands::list_sessions,
+            commands::get_session,
+            commands::close_session,
             // Profile commands
             commands::create_profile,
             commands::switch_profile,
@@ -38,6 +141,8 @@ pub fn run() {
             commands::delete_profile,
             commands::get_current_profile,
             commands::login_with_api_key,
+            commands::export_profiles,
+            commands::import_profiles,
             // Context commands
             commands::add_context_paths,
             commands::read_file,
@@ -49,8 +154,30 @@ pub fn run() {
             // Permission commands
             commands::approve_action,
             commands::reject_action,
+            commands::list_pending_actions,
             commands::set_permission_mode,
+            // Diagnostics
+            commands::bridge_health,
+            commands::get_logs,
         ])
-        .run(tauri::generate_context!())
-        .expect("error while running tauri application");
+        .build(tauri::generate_context!())
+        .expect("error while building tauri application")
+        .run(move |app, event| {
+            if let RunEvent::ExitRequested { api, .. } = event {
+                // Let the bridge wind down before the process goes away; exit again once it has
+                let Some(client) = app.try_state::<Arc<bridge::BridgeClient>>() else {
+                    return;
+                };
+                if bridge_stopping.swap(true, Ordering::SeqCst) {
+                    return;
+                }
+                api.prevent_exit();
+                let client = Arc::clone(&client);
+                let app = app.clone();
+                tauri::async_runtime::spawn(async move {
+                    client.shutdown().await;
+                    app.exit(0);
+                });
+            }
+        });
 }
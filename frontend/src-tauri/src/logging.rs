@@ -0,0 +1,298 @@
+//! Logging setup: everything goes to stderr, to daily rotated files in the app
+//! log directory and into an in-memory ring buffer that `get_logs` reads from.
+
+use serde::{Serialize, Serializer};
+use std::collections::VecDeque;
+use std::fmt::{self, Write as _};
+use std::path::Path;
+use std::sync::{Arc, Mutex};
+use std::time::{SystemTime, UNIX_EPOCH};
+use tracing::field::{Field, Visit};
+use tracing::span::{Attributes, Id, Record};
+use tracing::{Event, Level, Subscriber};
+use tracing_appender::rolling::{RollingFileAppender, Rotation};
+use tracing_subscriber::layer::{Context, SubscriberExt};
+use tracing_subscriber::registry::LookupSpan;
+use tracing_subscriber::util::{SubscriberInitExt, TryInitError};
+use tracing_subscriber::{EnvFilter, Layer};
+
+/// Environment variable holding the log filter, e.g. `debug` or `info,tauri_app_lib::bridge=trace`
+pub const LOG_ENV: &str = "MULTICODER_LOG";
+
+const DEFAULT_FILTER: &str = "info";
+
+/// Entries kept in memory; older ones are dropped first
+const BUFFER_CAPACITY: usize = 5000;
+
+/// Daily log files kept in the log directory
+const MAX_LOG_FILES: usize = 7;
+
+/// One log event as returned by `get_logs`
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct LogEntry {
+    /// Milliseconds since the Unix epoch
+    pub timestamp: u64,
+    #[serde(serialize_with = "serialize_level")]
+    pub level: Level,
+    pub target: String,
+    /// The message followed by the event's fields as `key=value`
+    pub message: String,
+    /// Enclosing spans, outermost first, e.g. `request{id=3 method=listProfiles}`
+    pub spans: Vec<String>,
+}
+
+fn serialize_level<S: Serializer>(level: &Level, serializer: S) -> Result<S::Ok, S::Error> {
+    serializer.serialize_str(&level.as_str().to_lowercase())
+}
+
+/// The most recent log entries
+#[derive(Debug)]
+pub struct LogBuffer {
+    entries: Mutex<VecDeque<LogEntry>>,
+    capacity: usize,
+}
+
+impl LogBuffer {
+    pub fn new(capacity: usize) -> Self {
+        Self {
+            entries: Mutex::new(VecDeque::with_capacity(capacity)),
+            capacity,
+        }
+    }
+
+    fn push(&self, entry: LogEntry) {
+        let mut entries = self.entries.lock().unwrap();
+        if entries.len() == self.capacity {
+            entries.pop_front();
+        }
+        entries.push_back(entry);
+    }
+
+    /// Entries at `level` or more severe logged after `since` (ms since the epoch),
+    /// oldest first. With a `limit` only the newest ones are returned.
+    pub fn query(&self, level: Level, since: Option<u64>, limit: Option<usize>) -> Vec<LogEntry> {
+        let entries = self.entries.lock().unwrap();
+        let mut matching: Vec<LogEntry> = entries
+            .iter()
+            .rev()
+            .filter(|entry| entry.level <= level && since.is_none_or(|since| entry.timestamp > since))
+            .take(limit.unwrap_or(usize::MAX))
+            .cloned()
+            .collect();
+        matching.reverse();
+        matching
+    }
+}
+
+/// Install the global subscriber. Log files are written to `log_dir` when given.
+/// Returns the buffer backing `get_logs`; fails if a global subscriber is already
+/// installed, which would leave that buffer empty.
+pub fn init(log_dir: Option<&Path>) -> Result<Arc<LogBuffer>, TryInitError> {
+    let buffer = Arc::new(LogBuffer::new(BUFFER_CAPACITY));
+
+    let filter = EnvFilter::try_from_env(LOG_ENV).unwrap_or_else(|_| EnvFilter::new(DEFAULT_FILTER));
+
+    let mut file_error = None;
+    let file_layer = log_dir.and_then(|dir| {
+        if let Err(e) = std::fs::create_dir_all(dir) {
+            file_error = Some(format!("failed to create log directory {}: {}", dir.display(), e));
+            return None;
+        }
+        match RollingFileAppender::builder()
+            .rotation(Rotation::DAILY)
+            .filename_prefix("multicoder")
+            .filename_suffix("log")
+            .max_log_files(MAX_LOG_FILES)
+            .build(dir)
+        {
+            Ok(appender) => Some(tracing_subscriber::fmt::layer().with_writer(appender).with_ansi(false)),
+            Err(e) => {
+                file_error = Some(format!("failed to open log directory {}: {}", dir.display(), e));
+                None
+            }
+        }
+    });
+
+    tracing_subscriber::registry()
+        .with(filter)
+        .with(tracing_subscriber::fmt::layer().with_writer(std::io::stderr))
+        .with(file_layer)
+        .with(RingBufferLayer { buffer: Arc::clone(&buffer) })
+        .try_init()?;
+
+    if let Some(e) = file_error {
+        tracing::warn!("{}", e);
+    }
+
+    Ok(buffer)
+}
+
+/// Copies every event into a [`LogBuffer`]
+struct RingBufferLayer {
+    buffer: Arc<LogBuffer>,
+}
+
+/// Formatted fields of a span, kept in its extensions
+struct SpanFields(String);
+
+impl<S> Layer<S> for RingBufferLayer
+where
+    S: Subscriber + for<'a> LookupSpan<'a>,
+{
+    fn on_new_span(&self, attrs: &Attributes<'_>, id: &Id, ctx: Context<'_, S>) {
+        let Some(span) = ctx.span(id) else {
+            return;
+        };
+        let mut visitor = FieldVisitor::default();
+        attrs.record(&mut visitor);
+        span.extensions_mut().insert(SpanFields(visitor.fields));
+    }
+
+    fn on_record(&self, id: &Id, values: &Record<'_>, ctx: Context<'_, S>) {
+        let Some(span) = ctx.span(id) else {
+            return;
+        };
+        let mut extensions = span.extensions_mut();
+        if let Some(SpanFields(fields)) = extensions.get_mut::<SpanFields>() {
+            let mut visitor = FieldVisitor { fields: std::mem::take(fields), ..Default::default() };
+            values.record(&mut visitor);
+            *fields = visitor.fields;
+        }
+    }
+
+    fn on_event(&self, event: &Event<'_>, ctx: Context<'_, S>) {
+        let mut visitor = FieldVisitor::default();
+        event.record(&mut visitor);
+
+        let mut message = visitor.message;
+        if !visitor.fields.is_empty() {
+            if !message.is_empty() {
+                message.push(' ');
+            }
+            message.push_str(&visitor.fields);
+        }
+
+        let spans = ctx
+            .event_scope(event)
+            .map(|scope| {
+                scope
+                    .from_root()
+                    .map(|span| match span.extensions().get::<SpanFields>() {
+                        Some(SpanFields(fields)) if !fields.is_empty() => format!("{}{{{}}}", span.name(), fields),
+                        _ => span.name().to_string(),
+                    })
+                    .collect()
+            })
+            .unwrap_or_default();
+
+        let timestamp = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map(|elapsed| elapsed.as_millis() as u64)
+            .unwrap_or(0);
+
+        self.buffer.push(LogEntry {
+            timestamp,
+            level: *event.metadata().level(),
+            target: event.metadata().target().to_string(),
+            message,
+            spans,
+        });
+    }
+}
+
+/// Collects the `message` field and the remaining fields as `key=value`
+#[derive(Default)]
+struct FieldVisitor {
+    message: String,
+    fields: String,
+}
+
+impl Visit for FieldVisitor {
+    fn record_str(&mut self, field: &Field, value: &str) {
+        if field.name() == "message" {
+            self.message.push_str(value);
+        } else {
+            self.record_debug(field, &value);
+        }
+    }
+
+    fn record_debug(&mut self, field: &Field, value: &dyn fmt::Debug) {
+        if field.name() == "message" {
+            let _ = write!(self.message, "{:?}", value);
+            return;
+        }
+        if !self.fields.is_empty() {
+            self.fields.push(' ');
+        }
+        let _ = write!(self.fields, "{}={:?}", field.name(), value);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn entry(timestamp: u64, level: Level, message: &str) -> LogEntry {
+        LogEntry {
+            timestamp,
+            level,
+            target: "test".to_string(),
+            message: message.to_string(),
+            spans: Vec::new(),
+        }
+    }
+
+    fn messages(entries: &[LogEntry]) -> Vec<&str> {
+        entries.iter().map(|entry| entry.message.as_str()).collect()
+    }
+
+    #[test]
+    fn drops_the_oldest_entries_once_full() {
+        let buffer = LogBuffer::new(3);
+        for (timestamp, message) in ["one", "two", "three", "four", "five"].into_iter().enumerate() {
+            buffer.push(entry(timestamp as u64, Level::INFO, message));
+        }
+
+        assert_eq!(messages(&buffer.query(Level::TRACE, None, None)), ["three", "four", "five"]);
+    }
+
+    #[test]
+    fn queries_by_level_time_and_limit() {
+        let buffer = LogBuffer::new(10);
+        buffer.push(entry(1, Level::ERROR, "failed"));
+        buffer.push(entry(2, Level::DEBUG, "details"));
+        buffer.push(entry(3, Level::WARN, "slow"));
+        buffer.push(entry(4, Level::INFO, "started"));
+        buffer.push(entry(5, Level::ERROR, "failed again"));
+
+        assert_eq!(messages(&buffer.query(Level::WARN, None, None)), ["failed", "slow", "failed again"]);
+        assert_eq!(messages(&buffer.query(Level::INFO, Some(3), None)), ["started", "failed again"]);
+        // The newest entries, still oldest first
+        assert_eq!(messages(&buffer.query(Level::TRACE, None, Some(2))), ["started", "failed again"]);
+        assert_eq!(messages(&buffer.query(Level::WARN, Some(1), Some(1))), ["failed again"]);
+    }
+
+    #[test]
+    fn records_filtered_events_with_their_fields_and_spans() {
+        let buffer = Arc::new(LogBuffer::new(10));
+        let subscriber = tracing_subscriber::registry()
+            .with(EnvFilter::new("info,noisy=warn"))
+            .with(RingBufferLayer { buffer: Arc::clone(&buffer) });
+
+        tracing::subscriber::with_default(subscriber, || {
+            let span = tracing::info_span!("request", id = 3, method = "listProfiles");
+            let _entered = span.enter();
+            tracing::info!(target: "app", attempt = 2, "connected");
+            tracing::debug!(target: "app", "hidden by the level");
+            tracing::info!(target: "noisy", "hidden by the target");
+            tracing::warn!(target: "noisy", "shown");
+        });
+
+        let entries = buffer.query(Level::TRACE, None, None);
+        assert_eq!(messages(&entries), ["connected attempt=2", "shown"]);
+        assert_eq!(entries[0].target, "app");
+        assert_eq!(entries[0].spans, [r#"request{id=3 method="listProfiles"}"#]);
+        assert_eq!(entries[1].level, Level::WARN);
+    }
+}
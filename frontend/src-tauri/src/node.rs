@@ -0,0 +1,442 @@
+use std::collections::HashMap;
+use std::ffi::OsString;
+use std::fmt;
+use std::path::{Path, PathBuf};
+use std::process::Stdio;
+use std::time::Duration;
+use tokio::process::Command;
+use tracing::info;
+
+/// Environment variable naming the Node executable; overrides `config.json`
+pub const NODE_ENV: &str = "MULTICODER_NODE";
+
+/// Oldest Node release the bridge runs on; keep in sync with `engines` in package.json
+pub const MIN_NODE_VERSION: NodeVersion = NodeVersion { major: 18, minor: 0, patch: 0 };
+
+/// How long `node --version` or the login shell may take before the candidate is skipped
+const PROBE_TIMEOUT: Duration = Duration::from_secs(5);
+
+#[cfg(target_os = "windows")]
+const NODE_BIN: &str = "node.exe";
+#[cfg(not(target_os = "windows"))]
+const NODE_BIN: &str = "node";
+
+/// Version reported by `node --version`
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub struct NodeVersion {
+    pub major: u32,
+    pub minor: u32,
+    pub patch: u32,
+}
+
+impl std::str::FromStr for NodeVersion {
+    type Err = String;
+
+    fn from_str(version: &str) -> Result<Self, Self::Err> {
+        let trimmed = version.trim();
+        let numbers = trimmed.strip_prefix('v').unwrap_or(trimmed);
+        let mut parts = numbers.splitn(3, '.').map(|part| {
+            // Drop pre-release suffixes such as `-nightly2024`
+            let digits: String = part.chars().take_while(|c| c.is_ascii_digit()).collect();
+            digits.parse::<u32>()
+        });
+
+        match (parts.next(), parts.next(), parts.next()) {
+            (Some(Ok(major)), minor, patch) => Ok(NodeVersion {
+                major,
+                minor: minor.and_then(Result::ok).unwrap_or(0),
+                patch: patch.and_then(Result::ok).unwrap_or(0),
+            }),
+            _ => Err(format!("unrecognized version '{}'", trimmed)),
+        }
+    }
+}
+
+impl fmt::Display for NodeVersion {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "v{}.{}.{}", self.major, self.minor, self.patch)
+    }
+}
+
+/// Where a candidate executable came from
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum NodeSource {
+    /// `MULTICODER_NODE` or `bridge.node` in `config.json`
+    Explicit,
+    /// The PATH the app was started with
+    Path,
+    /// `command -v node` in the user's login shell
+    LoginShell,
+    /// Install locations of nvm, volta, fnm and asdf
+    VersionManager,
+    /// The node binary bundled next to the app executable
+    Sidecar,
+}
+
+impl fmt::Display for NodeSource {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            NodeSource::Explicit => "explicit setting",
+            NodeSource::LoginShell => "login shell",
+            NodeSource::Path => "PATH",
+            NodeSource::VersionManager => "version manager",
+            NodeSource::Sidecar => "bundled sidecar",
+        })
+    }
+}
+
+/// A usable Node executable
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct NodeRuntime {
+    pub path: PathBuf,
+    pub version: NodeVersion,
+    pub source: NodeSource,
+}
+
+/// Every candidate that was tried, with the reason it was rejected
+#[derive(Debug, Clone, Default)]
+pub struct NodeNotFound {
+    pub attempts: Vec<(NodeSource, PathBuf, String)>,
+}
+
+impl fmt::Display for NodeNotFound {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "Could not find Node.js {} or newer. Install it or set {} to the node executable.",
+            MIN_NODE_VERSION, NODE_ENV
+        )?;
+        if self.attempts.is_empty() {
+            return write!(f, " No candidates were found.");
+        }
+        write!(f, " Tried:")?;
+        for (source, path, reason) in &self.attempts {
+            write!(f, "\n  - {} ({}): {}", path.display(), source, reason)?;
+        }
+        Ok(())
+    }
+}
+
+impl std::error::Error for NodeNotFound {}
+
+/// Finds a Node executable new enough to run the bridge
+#[derive(Debug, Clone, Default)]
+pub struct NodeResolver {
+    /// `bridge.node` from `config.json`; `MULTICODER_NODE` takes precedence
+    configured: Option<PathBuf>,
+    /// Environment read instead of the app's, so tests don't have to change it
+    vars: Option<HashMap<String, OsString>>,
+}
+
+impl NodeResolver {
+    pub fn new(configured: Option<PathBuf>) -> Self {
+        Self { configured, vars: None }
+    }
+
+    /// Non-empty value of the environment variable `name`
+    fn var(&self, name: &str) -> Option<OsString> {
+        let value = match &self.vars {
+            Some(vars) => vars.get(name).cloned(),
+            None => std::env::var_os(name),
+        };
+        value.filter(|value| !value.is_empty())
+    }
+
+    /// Try the candidates in order and return the first one that runs and is recent enough.
+    /// An explicit setting is the only candidate when present, so a bad setting is reported
+    /// instead of silently replaced.
+    pub async fn resolve(&self) -> Result<NodeRuntime, NodeNotFound> {
+        let mut not_found = NodeNotFound::default();
+
+        let candidates = match self.explicit() {
+            Some(path) => vec![(NodeSource::Explicit, path)],
+            None => self.discover().await,
+        };
+
+        for (source, path) in candidates {
+            match Self::probe(&path).await {
+                Ok(version) if version >= MIN_NODE_VERSION => {
+                    info!("using node {} {} ({})", path.display(), version, source);
+                    return Ok(NodeRuntime { path, version, source });
+                }
+                Ok(version) => not_found.attempts.push((
+                    source,
+                    path,
+                    format!("version {} is older than {}", version, MIN_NODE_VERSION),
+                )),
+                Err(reason) => not_found.attempts.push((source, path, reason)),
+            }
+        }
+
+        Err(not_found)
+    }
+
+    fn explicit(&self) -> Option<PathBuf> {
+        self.var(NODE_ENV).map(PathBuf::from).or_else(|| self.configured.clone())
+    }
+
+    /// Candidates when nothing was configured, in the order of `NodeSource`, without
+    /// duplicates. The app's PATH comes first; the login shell is asked for the
+    /// common case of a GUI launch without the shell's PATH.
+    async fn discover(&self) -> Vec<(NodeSource, PathBuf)> {
+        let mut candidates: Vec<(NodeSource, PathBuf)> = Vec::new();
+        let mut push = |source: NodeSource, path: PathBuf| {
+            if !candidates.iter().any(|(_, existing)| *existing == path) {
+                candidates.push((source, path));
+            }
+        };
+
+        if let Some(path) = self.path_node() {
+            push(NodeSource::Path, path);
+        }
+        if let Some(path) = self.login_shell_node().await {
+            push(NodeSource::LoginShell, path);
+        }
+        for path in self.version_manager_nodes() {
+            push(NodeSource::VersionManager, path);
+        }
+        if let Some(path) = Self::sidecar_node() {
+            push(NodeSource::Sidecar, path);
+        }
+
+        candidates
+    }
+
+    /// Ask the user's login shell, which has the PATH set up by nvm and friends.
+    /// GUI launches on Linux and macOS do not inherit it.
+    #[cfg(unix)]
+    async fn login_shell_node(&self) -> Option<PathBuf> {
+        let shell = self.var("SHELL")?;
+
+        let mut command = Command::new(shell);
+        command
+            .args(["-ilc", "command -v node"])
+            .stdin(Stdio::null())
+            .stdout(Stdio::piped())
+            .stderr(Stdio::null())
+            .kill_on_drop(true);
+
+        let output = tokio::time::timeout(PROBE_TIMEOUT, command.output()).await.ok()?.ok()?;
+        // Interactive startup files may print banners; the path is the last line
+        String::from_utf8_lossy(&output.stdout)
+            .lines()
+            .map(str::trim)
+            .rfind(|line| line.starts_with('/'))
+            .map(PathBuf::from)
+    }
+
+    #[cfg(not(unix))]
+    async fn login_shell_node(&self) -> Option<PathBuf> {
+        None
+    }
+
+    fn path_node(&self) -> Option<PathBuf> {
+        let path = self.var("PATH")?;
+        std::env::split_paths(&path)
+            .map(|dir| dir.join(NODE_BIN))
+            .find(|candidate| candidate.is_file())
+    }
+
+    /// Node binaries installed by nvm, volta, fnm and asdf, newest version first per manager
+    fn version_manager_nodes(&self) -> Vec<PathBuf> {
+        let Some(home) = dirs::home_dir() else {
+            return Vec::new();
+        };
+        let env_dir = |name: &str| self.var(name).map(PathBuf::from);
+
+        let mut nodes = Vec::new();
+
+        // nvm
+        let nvm_dir = env_dir("NVM_DIR").unwrap_or_else(|| home.join(".nvm"));
+        nodes.extend(Self::versioned(&nvm_dir.join("versions").join("node"), &["bin", NODE_BIN]));
+        #[cfg(target_os = "windows")]
+        if let Some(nvm_home) = env_dir("NVM_HOME") {
+            nodes.extend(Self::versioned(&nvm_home, &[NODE_BIN]));
+        }
+
+        // volta
+        let volta_home = env_dir("VOLTA_HOME").unwrap_or_else(|| home.join(".volta"));
+        nodes.push(volta_home.join("bin").join(NODE_BIN));
+
+        // fnm
+        let mut fnm_dirs: Vec<PathBuf> = env_dir("FNM_DIR").into_iter().collect();
+        fnm_dirs.push(home.join(".fnm"));
+        fnm_dirs.extend(dirs::data_dir().map(|dir| dir.join("fnm")));
+        for fnm_dir in fnm_dirs {
+            let installation: &[&str] = if cfg!(target_os = "windows") {
+                &["installation", NODE_BIN]
+            } else {
+                &["installation", "bin", NODE_BIN]
+            };
+            nodes.extend(Self::versioned(&fnm_dir.join("node-versions"), installation));
+        }
+
+        // asdf
+        let asdf_dir = env_dir("ASDF_DATA_DIR").unwrap_or_else(|| home.join(".asdf"));
+        nodes.push(asdf_dir.join("shims").join(NODE_BIN));
+        nodes.extend(Self::versioned(&asdf_dir.join("installs").join("nodejs"), &["bin", NODE_BIN]));
+
+        nodes.into_iter().filter(|node| node.is_file()).collect()
+    }
+
+    /// `<dir>/<version>/<rest..>` for every version directory, newest first
+    fn versioned(dir: &Path, rest: &[&str]) -> Vec<PathBuf> {
+        let Ok(entries) = std::fs::read_dir(dir) else {
+            return Vec::new();
+        };
+
+        let mut versions: Vec<(NodeVersion, PathBuf)> = entries
+            .filter_map(Result::ok)
+            .filter_map(|entry| {
+                let version = entry.file_name().to_string_lossy().parse::<NodeVersion>().ok()?;
+                let path = rest.iter().fold(entry.path(), |path, part| path.join(part));
+                Some((version, path))
+            })
+            .collect();
+        versions.sort_by_key(|(version, _)| std::cmp::Reverse(*version));
+        versions.into_iter().map(|(_, path)| path).collect()
+    }
+
+    /// A node binary shipped as a Tauri sidecar, which is installed next to the executable
+    fn sidecar_node() -> Option<PathBuf> {
+        let exe = std::env::current_exe().ok()?;
+        let node = exe.parent()?.join(NODE_BIN);
+        node.is_file().then_some(node)
+    }
+
+    /// Run `node --version`
+    async fn probe(path: &Path) -> Result<NodeVersion, String> {
+        let mut command = Command::new(path);
+        command
+            .arg("--version")
+            .stdin(Stdio::null())
+            .stdout(Stdio::piped())
+            .stderr(Stdio::null())
+            .kill_on_drop(true);
+
+        #[cfg(target_os = "windows")]
+        {
+            const CREATE_NO_WINDOW: u32 = 0x08000000;
+            command.creation_flags(CREATE_NO_WINDOW);
+        }
+
+        let output = tokio::time::timeout(PROBE_TIMEOUT, command.output())
+            .await
+            .map_err(|_| format!("`--version` did not finish within {} seconds", PROBE_TIMEOUT.as_secs()))?
+            .map_err(|e| format!("failed to run: {}", e))?;
+
+        if !output.status.success() {
+            return Err(format!("`--version` exited with {}", output.status));
+        }
+
+        String::from_utf8_lossy(&output.stdout).parse()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_node_versions() {
+        assert_eq!("v18.19.0".parse(), Ok(NodeVersion { major: 18, minor: 19, patch: 0 }));
+        assert_eq!("v20.0.0-nightly20230801\n".parse(), Ok(NodeVersion { major: 20, minor: 0, patch: 0 }));
+        assert_eq!("22.1".parse(), Ok(NodeVersion { major: 22, minor: 1, patch: 0 }));
+        for garbage in ["", "v", "node", "bash: node: command not found"] {
+            assert!(garbage.parse::<NodeVersion>().is_err(), "{:?}", garbage);
+        }
+    }
+
+    #[test]
+    fn compares_against_the_minimum_version() {
+        let version = |v: &str| v.parse::<NodeVersion>().unwrap();
+        assert!(version("v17.9.1") < MIN_NODE_VERSION);
+        assert!(version("v18.0.0") >= MIN_NODE_VERSION);
+        assert!(version("v18.0.0-rc.1") >= MIN_NODE_VERSION);
+        assert!(version("v20.11.1") > MIN_NODE_VERSION);
+    }
+
+    #[cfg(unix)]
+    mod resolve {
+        use super::*;
+        use std::os::unix::fs::PermissionsExt;
+
+        /// A temporary directory with fake node executables
+        struct Fakes(PathBuf);
+
+        impl Fakes {
+            fn new() -> Self {
+                let dir = std::env::temp_dir().join(format!("multicoder-node-{}", uuid::Uuid::new_v4()));
+                std::fs::create_dir_all(&dir).unwrap();
+                Self(dir)
+            }
+
+            /// An executable `<dir>/<name>` printing `output`
+            fn script(&self, name: &str, output: &str) -> PathBuf {
+                let path = self.0.join(name);
+                std::fs::create_dir_all(path.parent().unwrap()).unwrap();
+                std::fs::write(&path, format!("#!/bin/sh\necho '{}'\n", output)).unwrap();
+                std::fs::set_permissions(&path, std::fs::Permissions::from_mode(0o755)).unwrap();
+                path
+            }
+
+            /// `<dir>/<name>/node` reporting `version`, and the directory for PATH
+            fn node(&self, name: &str, version: &str) -> (PathBuf, PathBuf) {
+                let node = self.script(&format!("{}/{}", name, NODE_BIN), version);
+                (node.clone(), node.parent().unwrap().to_path_buf())
+            }
+        }
+
+        impl Drop for Fakes {
+            fn drop(&mut self) {
+                let _ = std::fs::remove_dir_all(&self.0);
+            }
+        }
+
+        fn resolver(configured: Option<&Path>, vars: &[(&str, &Path)]) -> NodeResolver {
+            NodeResolver {
+                configured: configured.map(Path::to_path_buf),
+                vars: Some(vars.iter().map(|(name, value)| (name.to_string(), value.as_os_str().to_owned())).collect()),
+            }
+        }
+
+        #[tokio::test]
+        async fn tries_override_config_path_then_login_shell() {
+            let fakes = Fakes::new();
+            let (overridden, _) = fakes.node("override", "v20.1.0");
+            let (configured, _) = fakes.node("config", "v20.2.0");
+            let (on_path, path_dir) = fakes.node("path", "v20.3.0");
+            let (from_shell, _) = fakes.node("shell", "v20.4.0");
+            let shell = fakes.script("login-shell", &from_shell.display().to_string());
+
+            let everything = [(NODE_ENV, overridden.as_path()), ("PATH", &path_dir), ("SHELL", &shell)];
+            let found = resolver(Some(&configured), &everything).resolve().await.unwrap();
+            assert_eq!((found.path, found.source), (overridden.clone(), NodeSource::Explicit));
+
+            let found = resolver(Some(&configured), &everything[1..]).resolve().await.unwrap();
+            assert_eq!((found.path, found.version.minor), (configured, 2));
+
+            let found = resolver(None, &everything[1..]).resolve().await.unwrap();
+            assert_eq!((found.path, found.source), (on_path, NodeSource::Path));
+
+            let found = resolver(None, &everything[2..]).resolve().await.unwrap();
+            assert_eq!((found.path, found.source), (from_shell, NodeSource::LoginShell));
+        }
+
+        #[tokio::test]
+        async fn skips_nodes_older_than_the_minimum() {
+            let fakes = Fakes::new();
+            let (old, path_dir) = fakes.node("path", "v16.20.2");
+            let (current, _) = fakes.node("shell", "v18.19.0");
+            let shell = fakes.script("login-shell", &current.display().to_string());
+
+            let found = resolver(None, &[("PATH", &path_dir), ("SHELL", &shell)]).resolve().await.unwrap();
+            assert_eq!((found.path, found.source), (current, NodeSource::LoginShell));
+
+            // An explicit setting is not replaced by a working node elsewhere
+            let error = resolver(Some(&old), &[("PATH", &path_dir)]).resolve().await.unwrap_err();
+            assert_eq!(error.attempts.len(), 1);
+            assert_eq!(error.attempts[0].2, "version v16.20.2 is older than v18.0.0");
+        }
+    }
+}
@@ -0,0 +1,255 @@
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, HashSet};
+use std::path::Path;
+use std::sync::{Mutex, RwLock};
+
+use crate::policy::{PolicyAction, PolicyEffect, PolicyEngine};
+
+/// How a profile answers the permission requests of its provider
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum PermissionMode {
+    /// Ask the user for every request
+    Ask,
+    /// Approve every request
+    Allow,
+    /// Reject every request
+    Deny,
+}
+
+impl PermissionMode {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            PermissionMode::Ask => "ask",
+            PermissionMode::Allow => "allow",
+            PermissionMode::Deny => "deny",
+        }
+    }
+}
+
+impl std::str::FromStr for PermissionMode {
+    type Err = String;
+
+    fn from_str(mode: &str) -> Result<Self, Self::Err> {
+        match mode {
+            "ask" => Ok(PermissionMode::Ask),
+            "allow" => Ok(PermissionMode::Allow),
+            "deny" => Ok(PermissionMode::Deny),
+            other => Err(format!("Invalid permission mode: {} (expected ask, allow or deny)", other)),
+        }
+    }
+}
+
+/// A `ProviderEvent::Ask` waiting for the user's decision
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PendingAction {
+    pub id: String,
+    pub session_id: String,
+    pub profile: String,
+    pub provider: String,
+    /// Kind of action, e.g. `shell_exec` or `file_write`
+    pub action: String,
+    pub reason: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub command: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub path: Option<String>,
+    /// Milliseconds since the Unix epoch
+    pub requested_at: u64,
+}
+
+impl PendingAction {
+    /// The shell command or file path the policies are checked against
+    fn policy_action(&self) -> Option<PolicyAction<'_>> {
+        match (self.action.as_str(), &self.command, &self.path) {
+            ("shell_exec", Some(command), _) => Some(PolicyAction::Shell { command }),
+            ("file_write" | "file_delete", _, Some(path)) => Some(PolicyAction::File { path }),
+            _ => None,
+        }
+    }
+
+    /// What "apply to session" approves: the same kind of action on the same
+    /// command or file path, never the whole kind
+    fn grant(&self) -> Grant {
+        (self.action.clone(), self.command.clone().or_else(|| self.path.clone()))
+    }
+}
+
+/// Kind of action and its command or path
+type Grant = (String, Option<String>);
+
+/// Decision taken without asking the user
+#[derive(Debug, Clone, PartialEq)]
+pub struct AutoDecision {
+    pub approved: bool,
+    pub reason: String,
+}
+
+/// Holds pending permission requests and the grants given for a whole session
+#[derive(Debug, Default)]
+pub struct PermissionBroker {
+    pending: Mutex<HashMap<String, PendingAction>>,
+    /// Actions approved with "apply to session", keyed by session id
+    grants: Mutex<HashMap<String, HashSet<Grant>>>,
+    policy: RwLock<PolicyEngine>,
+}
+
+impl PermissionBroker {
+    pub fn with_policy(policy: PolicyEngine) -> Self {
+        Self {
+            policy: RwLock::new(policy),
+            ..Self::default()
+        }
+    }
+
+    /// Decide a request without the user if the mode, a policy rule or an earlier
+    /// grant allows it. Returns `None` if the user must answer. A profile in deny
+    /// mode rejects everything; otherwise policy rules come first since they are
+    /// the most specific.
+    pub fn decide(&self, action: &PendingAction, mode: PermissionMode, workspace: Option<&Path>) -> Option<AutoDecision> {
+        if mode == PermissionMode::Deny {
+            return Some(AutoDecision { approved: false, reason: "Denied by the profile's permission mode".to_string() });
+        }
+
+        let policy = action
+            .policy_action()
+            .and_then(|target| self.policy.read().unwrap().evaluate(&action.profile, &target, workspace));
+        if let Some(decision) = policy {
+            return match decision.effect {
+                PolicyEffect::Allow => Some(AutoDecision { approved: true, reason: decision.reason }),
+                PolicyEffect::Deny => Some(AutoDecision { approved: false, reason: decision.reason }),
+                PolicyEffect::Ask => None,
+            };
+        }
+
+        let reason = match mode {
+            PermissionMode::Allow => "Allowed by the profile's permission mode",
+            _ if self.is_granted(action) => "Allowed for this session",
+            _ => return None,
+        };
+        Some(AutoDecision { approved: true, reason: reason.to_string() })
+    }
+
+    /// Hold a request until the user answers it
+    pub fn add_pending(&self, action: PendingAction) {
+        self.pending.lock().unwrap().insert(action.id.clone(), action);
+    }
+
+    /// Remove and return a pending request
+    pub fn take(&self, action_id: &str) -> Option<PendingAction> {
+        self.pending.lock().unwrap().remove(action_id)
+    }
+
+    /// Pending requests, oldest first
+    pub fn pending(&self) -> Vec<PendingAction> {
+        let mut pending: Vec<PendingAction> = self.pending.lock().unwrap().values().cloned().collect();
+        pending.sort_by_key(|action| action.requested_at);
+        pending
+    }
+
+    /// Approve `action`'s command or path for the rest of its session
+    pub fn grant(&self, action: &PendingAction) {
+        self.grants
+            .lock()
+            .unwrap()
+            .entry(action.session_id.clone())
+            .or_default()
+            .insert(action.grant());
+    }
+
+    pub fn is_granted(&self, action: &PendingAction) -> bool {
+        self.grants
+            .lock()
+            .unwrap()
+            .get(&action.session_id)
+            .is_some_and(|grants| grants.contains(&action.grant()))
+    }
+
+    /// Drop the requests still pending for a session, e.g. because a new message was sent
+    pub fn drop_pending(&self, session_id: &str) {
+        self.pending.lock().unwrap().retain(|_, action| action.session_id != session_id);
+    }
+
+    /// Forget the requests and grants of a session that ended
+    pub fn forget_session(&self, session_id: &str) {
+        self.drop_pending(session_id);
+        self.grants.lock().unwrap().remove(session_id);
+    }
+
+    /// Forget everything, e.g. because the bridge process exited
+    pub fn clear(&self) {
+        self.pending.lock().unwrap().clear();
+        self.grants.lock().unwrap().clear();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::policy::PolicyFile;
+
+    const POLICY: &str = r#"
+        [[rules]]
+        name = "git-status"
+        action = "shell"
+        effect = "allow"
+        glob = "git status"
+
+        [[rules]]
+        name = "no-push"
+        action = "shell"
+        effect = "deny"
+        glob = "git push*"
+    "#;
+
+    fn broker() -> PermissionBroker {
+        PermissionBroker::with_policy(PolicyEngine::from_file(&PolicyFile::from_toml(POLICY).unwrap()).unwrap())
+    }
+
+    fn shell(command: &str) -> PendingAction {
+        PendingAction {
+            id: "action-1".to_string(),
+            session_id: "session-1".to_string(),
+            profile: "default".to_string(),
+            provider: "claude".to_string(),
+            action: "shell_exec".to_string(),
+            reason: "Run a command".to_string(),
+            command: Some(command.to_string()),
+            path: None,
+            requested_at: 1,
+        }
+    }
+
+    fn approved(decision: Option<AutoDecision>) -> Option<bool> {
+        decision.map(|decision| decision.approved)
+    }
+
+    #[test]
+    fn deny_mode_wins_over_allow_rules() {
+        let broker = broker();
+        let decision = broker.decide(&shell("git status"), PermissionMode::Deny, None).unwrap();
+        assert!(!decision.approved);
+        assert_eq!(decision.reason, "Denied by the profile's permission mode");
+    }
+
+    #[test]
+    fn policy_rules_win_over_allow_and_ask_modes() {
+        let broker = broker();
+        assert_eq!(approved(broker.decide(&shell("git status"), PermissionMode::Ask, None)), Some(true));
+        assert_eq!(approved(broker.decide(&shell("git push"), PermissionMode::Allow, None)), Some(false));
+        assert_eq!(approved(broker.decide(&shell("ls"), PermissionMode::Allow, None)), Some(true));
+        assert_eq!(approved(broker.decide(&shell("ls"), PermissionMode::Ask, None)), None);
+    }
+    #[test]
+    fn grants_cover_only_the_approved_command() {
+        let broker = broker();
+        broker.grant(&shell("cargo build"));
+
+        let decision = broker.decide(&shell("cargo build"), PermissionMode::Ask, None).unwrap();
+        assert_eq!(decision.reason, "Allowed for this session");
+        assert_eq!(approved(broker.decide(&shell("cargo clean"), PermissionMode::Ask, None)), None);
+        let other_session = PendingAction { session_id: "session-2".to_string(), ..shell("cargo build") };
+        assert_eq!(approved(broker.decide(&other_session, PermissionMode::Ask, None)), None);
+    }
+}
@@ -0,0 +1,319 @@
+//! Keeps the bridge process tree from outliving the app: the bridge runs in
+//! its own process group together with the provider CLIs it starts, dies with
+//! the app on Linux, and is recorded in a pidfile so a later start can clean
+//! up after an app that crashed.
+
+use serde::{Deserialize, Serialize};
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+use tokio::process::Command;
+use tracing::{debug, warn};
+
+/// Name of the pidfile in the app data directory
+pub const PIDFILE_NAME: &str = "bridge.pid";
+
+/// How long a stale bridge gets to exit after SIGTERM before it is killed
+#[cfg(unix)]
+const STALE_EXIT_GRACE: Duration = Duration::from_secs(2);
+
+/// Start `command` as the leader of a new process group, so the bridge and every
+/// provider CLI it spawns can be signalled at once. On Linux the bridge also gets
+/// SIGTERM when the app dies, even if the app is SIGKILLed.
+pub fn isolate(command: &mut Command) {
+    #[cfg(unix)]
+    command.process_group(0);
+
+    #[cfg(target_os = "linux")]
+    {
+        let parent = std::process::id() as libc::pid_t;
+        // SAFETY: the closure runs between fork and exec and only makes
+        // async-signal-safe calls without allocating
+        unsafe {
+            command.pre_exec(move || {
+                // Note the signal fires when the spawning thread exits; the async
+                // runtime's worker threads live as long as the app
+                if libc::prctl(libc::PR_SET_PDEATHSIG, libc::SIGTERM) == -1 {
+                    return Err(std::io::Error::last_os_error());
+                }
+                // The app died before the death signal was armed
+                if libc::getppid() != parent {
+                    return Err(std::io::Error::from_raw_os_error(libc::ESRCH));
+                }
+                Ok(())
+            });
+        }
+    }
+}
+
+/// Send `signal` to every process in the group led by `pgid`.
+/// Returns `false` if the group no longer exists.
+#[cfg(unix)]
+pub fn signal_group(pgid: u32, signal: libc::c_int) -> bool {
+    // SAFETY: killpg has no memory safety requirements
+    unsafe { libc::killpg(pgid as libc::pid_t, signal) == 0 }
+}
+
+/// Whether a process with this PID exists, including ones owned by other users
+#[cfg(unix)]
+pub fn process_alive(pid: u32) -> bool {
+    // SAFETY: signal 0 only checks that the process exists
+    let exists = unsafe { libc::kill(pid as libc::pid_t, 0) == 0 };
+    exists || std::io::Error::last_os_error().raw_os_error() == Some(libc::EPERM)
+}
+
+/// What identifies a running process beyond its PID, which the system reuses
+#[derive(Debug, Clone, PartialEq)]
+struct ProcessIdentity {
+    /// Start time in clock ticks since boot; only known on Linux
+    start_time: Option<u64>,
+    /// Arguments joined by spaces
+    command_line: String,
+}
+
+impl ProcessIdentity {
+    /// Identity of `pid`, or `None` if no such process exists
+    #[cfg(target_os = "linux")]
+    fn of(pid: u32) -> Option<Self> {
+        let stat = std::fs::read_to_string(format!("/proc/{}/stat", pid)).ok()?;
+        // The command name in parentheses may contain spaces; starttime is the
+        // 22nd field, the 20th after it
+        let start_time = stat
+            .rsplit_once(')')
+            .and_then(|(_, fields)| fields.split_whitespace().nth(19))
+            .and_then(|field| field.parse().ok());
+        let command_line = std::fs::read(format!("/proc/{}/cmdline", pid)).ok()?;
+        let command_line = String::from_utf8_lossy(&command_line)
+            .split('\0')
+            .filter(|arg| !arg.is_empty())
+            .collect::<Vec<_>>()
+            .join(" ");
+        Some(Self { start_time, command_line })
+    }
+
+    #[cfg(all(unix, not(target_os = "linux")))]
+    fn of(pid: u32) -> Option<Self> {
+        let output = std::process::Command::new("ps")
+            .args(["-o", "command=", "-p", &pid.to_string()])
+            .output()
+            .ok()?;
+        let command_line = String::from_utf8_lossy(&output.stdout).trim().to_string();
+        (output.status.success() && !command_line.is_empty()).then_some(Self {
+            start_time: None,
+            command_line,
+        })
+    }
+
+    #[cfg(not(unix))]
+    fn of(_pid: u32) -> Option<Self> {
+        None
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct PidRecord {
+    /// The bridge, which leads its own process group
+    pid: u32,
+    /// The app that started it
+    app_pid: u32,
+    /// Bridge script the process runs
+    #[serde(default)]
+    script: Option<PathBuf>,
+    /// Start time of the bridge process, see `ProcessIdentity`
+    #[serde(default)]
+    start_time: Option<u64>,
+}
+
+impl PidRecord {
+    /// Whether `pid` is still the recorded bridge and not an unrelated process
+    /// that got the same PID
+    fn is_bridge(&self, identity: &ProcessIdentity) -> bool {
+        let Some(script) = &self.script else {
+            return false;
+        };
+        let same_start = match (self.start_time, identity.start_time) {
+            (Some(recorded), Some(current)) => recorded == current,
+            _ => true,
+        };
+        same_start && identity.command_line.contains(&*script.to_string_lossy())
+    }
+}
+
+/// Remembers the running bridge in the app data directory
+#[derive(Debug, Clone)]
+pub struct PidFile {
+    path: PathBuf,
+}
+
+impl PidFile {
+    pub fn new(path: PathBuf) -> Self {
+        Self { path }
+    }
+
+    /// Record a freshly started bridge running `script`
+    pub fn record(&self, pid: u32, script: &Path) {
+        let record = PidRecord {
+            pid,
+            app_pid: std::process::id(),
+            script: Some(script.to_path_buf()),
+            start_time: ProcessIdentity::of(pid).and_then(|identity| identity.start_time),
+        };
+        let written = self
+            .path
+            .parent()
+            .map_or(Ok(()), std::fs::create_dir_all)
+            .and_then(|_| std::fs::write(&self.path, serde_json::to_string(&record).unwrap_or_default()));
+        if let Err(e) = written {
+            warn!("failed to write {}: {}", self.path.display(), e);
+        }
+    }
+
+    pub fn clear(&self) {
+        match std::fs::remove_file(&self.path) {
+            Ok(()) => {}
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => {}
+            Err(e) => warn!("failed to remove {}: {}", self.path.display(), e),
+        }
+    }
+
+    /// Stop whatever is left of the recorded bridge's process group, unless the
+    /// app instance that started it is still running or the PID now belongs to
+    /// another program
+    pub async fn reap_stale(&self) {
+        let Ok(source) = std::fs::read_to_string(&self.path) else {
+            return;
+        };
+        let record = match serde_json::from_str::<PidRecord>(&source) {
+            Ok(record) => record,
+            Err(e) => {
+                warn!("ignoring invalid {}: {}", self.path.display(), e);
+                self.clear();
+                return;
+            }
+        };
+
+        #[cfg(unix)]
+        {
+            if record.app_pid != std::process::id() && process_alive(record.app_pid) {
+                debug!("bridge {} belongs to running app instance {}", record.pid, record.app_pid);
+                return;
+            }
+
+            // While the group exists its ID cannot be handed to a new process, so a
+            // group without a leader still holds the bridge's provider CLIs
+            match ProcessIdentity::of(record.pid) {
+                Some(identity) if !record.is_bridge(&identity) => {
+                    debug!("pid {} is no longer the bridge: {}", record.pid, identity.command_line);
+                    self.clear();
+                    return;
+                }
+                _ => {}
+            }
+
+            if signal_group(record.pid, libc::SIGTERM) {
+                warn!("stopping bridge process group {} left behind by a previous run", record.pid);
+                let deadline = tokio::time::Instant::now() + STALE_EXIT_GRACE;
+                while signal_group(record.pid, 0) && tokio::time::Instant::now() < deadline {
+                    tokio::time::sleep(Duration::from_millis(100)).await;
+                }
+                signal_group(record.pid, libc::SIGKILL);
+            }
+        }
+        #[cfg(not(unix))]
+        debug!("not cleaning up bridge {} on this platform", record.pid);
+
+        self.clear();
+    }
+}
+
+#[cfg(all(test, unix))]
+mod tests {
+    use super::*;
+
+    const SCRIPT: &str = "/opt/multicoder/dist/bridge/provider-bridge.js";
+
+    /// A process group led by a shell whose command line names `script`
+    fn spawn_group(script: &str) -> std::process::Child {
+        use std::os::unix::process::CommandExt;
+        std::process::Command::new("sh")
+            .args(["-c", "sleep 30; :", script])
+            .stdout(std::process::Stdio::null())
+            .stderr(std::process::Stdio::null())
+            .process_group(0)
+            .spawn()
+            .unwrap()
+    }
+
+    fn pidfile() -> PidFile {
+        PidFile::new(std::env::temp_dir().join(format!("multicoder-{}.pid", uuid::Uuid::new_v4())))
+    }
+
+    /// PID of an app instance that has exited
+    fn exited_app() -> u32 {
+        let mut app = std::process::Command::new("true").spawn().unwrap();
+        app.wait().unwrap();
+        app.id()
+    }
+
+    /// Write a pidfile as a previous app instance would have
+    fn write_record(pidfile: &PidFile, record: PidRecord) {
+        std::fs::write(&pidfile.path, serde_json::to_string(&record).unwrap()).unwrap();
+    }
+
+    #[tokio::test]
+    async fn reaps_the_recorded_bridge() {
+        let mut bridge = spawn_group(SCRIPT);
+        let pidfile = pidfile();
+        pidfile.record(bridge.id(), Path::new(SCRIPT));
+        let mut record: PidRecord = serde_json::from_str(&std::fs::read_to_string(&pidfile.path).unwrap()).unwrap();
+        record.app_pid = exited_app();
+        write_record(&pidfile, record);
+
+        pidfile.reap_stale().await;
+
+        assert!(bridge.wait().unwrap().code().is_none(), "the bridge was signalled");
+        assert!(!pidfile.path.exists());
+    }
+
+    #[tokio::test]
+    async fn leaves_a_process_that_reused_the_pid_alone() {
+        let mut other = spawn_group("/usr/local/bin/unrelated");
+        let pidfile = pidfile();
+        write_record(
+            &pidfile,
+            PidRecord {
+                pid: other.id(),
+                app_pid: exited_app(),
+                script: Some(PathBuf::from(SCRIPT)),
+                start_time: None,
+            },
+        );
+
+        pidfile.reap_stale().await;
+
+        assert!(other.try_wait().unwrap().is_none(), "the unrelated process was signalled");
+        assert!(!pidfile.path.exists());
+        signal_group(other.id(), libc::SIGKILL);
+        other.wait().unwrap();
+    }
+
+    #[cfg(target_os = "linux")]
+    #[test]
+    fn a_different_start_time_is_another_process() {
+        let identity = ProcessIdentity::of(std::process::id()).unwrap();
+        assert!(identity.start_time.is_some());
+
+        let record = PidRecord {
+            pid: std::process::id(),
+            app_pid: u32::MAX,
+            script: Some(PathBuf::from(identity.command_line.split(' ').next().unwrap())),
+            start_time: identity.start_time,
+        };
+        assert!(record.is_bridge(&identity));
+        let restarted = ProcessIdentity {
+            start_time: identity.start_time.map(|ticks| ticks + 1),
+            ..identity.clone()
+        };
+        assert!(!record.is_bridge(&restarted));
+    }
+}
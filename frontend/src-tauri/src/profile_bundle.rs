@@ -0,0 +1,724 @@
+//! Encrypted bundles for moving profiles between machines. A bundle holds the
+//! metadata of each exported profile and its managed credentials, encrypted
+//! with XChaCha20-Poly1305 under a key derived from a passphrase with Argon2id.
+//! The unencrypted header is authenticated as associated data, so any change
+//! to the file is detected on import.
+
+use argon2::{Algorithm, Argon2, Params, Version};
+use base64::engine::general_purpose::STANDARD as BASE64;
+use base64::Engine;
+use chacha20poly1305::aead::{Aead, AeadCore, KeyInit, OsRng, Payload};
+use chacha20poly1305::aead::rand_core::RngCore;
+use chacha20poly1305::{XChaCha20Poly1305, XNonce};
+use serde::{Deserialize, Serialize};
+use std::fs::OpenOptions;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use zeroize::Zeroizing;
+
+use crate::error::CommandError;
+use crate::profile_store::{CredentialSource, ProfileData, ProfileStore, ProfileStoreData};
+
+/// Identifies the file as a profile bundle
+const BUNDLE_FORMAT: &str = "multicoder-profiles";
+
+/// Version of the bundle layout
+const BUNDLE_VERSION: u32 = 1;
+
+const KDF_ALGORITHM: &str = "argon2id";
+const CIPHER: &str = "xchacha20poly1305";
+const KEY_LEN: usize = 32;
+const SALT_LEN: usize = 16;
+
+/// Largest Argon2 memory cost accepted from a bundle, 1 GiB
+const MAX_MEMORY_KIB: u32 = 1 << 20;
+
+/// Largest Argon2 time cost accepted from a bundle
+const MAX_ITERATIONS: u32 = 16;
+
+/// Managed credential files of a profile, next to each other in the provider's
+/// directory: the credential itself and the provider's environment variables
+const CREDENTIAL_SUFFIXES: &[&str] = &[".json", ".env.json"];
+
+/// What to do with a bundled profile whose name is already taken
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum OnConflict {
+    /// Keep the existing profile
+    Skip,
+    /// Replace the existing profile and its managed credentials
+    Overwrite,
+    /// Import under the first free `<name>-imported` name
+    Rename,
+}
+
+impl std::str::FromStr for OnConflict {
+    type Err = String;
+
+    fn from_str(on_conflict: &str) -> Result<Self, Self::Err> {
+        match on_conflict {
+            "skip" => Ok(OnConflict::Skip),
+            "overwrite" => Ok(OnConflict::Overwrite),
+            "rename" => Ok(OnConflict::Rename),
+            other => Err(format!("Invalid conflict handling: {} (expected skip, overwrite or rename)", other)),
+        }
+    }
+}
+
+/// Argon2id cost and salt of a bundle
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct KdfParams {
+    algorithm: String,
+    salt: String,
+    memory_kib: u32,
+    iterations: u32,
+    parallelism: u32,
+}
+
+impl KdfParams {
+    /// OWASP's recommended Argon2id cost with a fresh salt
+    fn generate() -> Self {
+        let mut salt = [0u8; SALT_LEN];
+        OsRng.fill_bytes(&mut salt);
+        Self {
+            algorithm: KDF_ALGORITHM.to_string(),
+            salt: BASE64.encode(salt),
+            memory_kib: 19 * 1024,
+            iterations: 2,
+            parallelism: 1,
+        }
+    }
+
+    fn derive_key(&self, passphrase: &str) -> Result<Zeroizing<[u8; KEY_LEN]>, CommandError> {
+        if self.algorithm != KDF_ALGORITHM {
+            return Err(CommandError::Validation(format!("Unsupported key derivation {}", self.algorithm)));
+        }
+        if self.memory_kib > MAX_MEMORY_KIB || self.iterations > MAX_ITERATIONS {
+            return Err(CommandError::Validation("Key derivation cost of the bundle is too high".to_string()));
+        }
+        let salt = decode(&self.salt, "salt")?;
+        let params = Params::new(self.memory_kib, self.iterations, self.parallelism, Some(KEY_LEN))
+            .map_err(|e| CommandError::Validation(format!("Invalid key derivation parameters: {}", e)))?;
+
+        let mut key = Zeroizing::new([0u8; KEY_LEN]);
+        Argon2::new(Algorithm::Argon2id, Version::V0x13, params)
+            .hash_password_into(passphrase.as_bytes(), &salt, key.as_mut())
+            .map_err(|e| CommandError::Internal(format!("Key derivation failed: {}", e)))?;
+        Ok(key)
+    }
+}
+
+/// The unencrypted part of a bundle
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct Header {
+    format: String,
+    version: u32,
+    kdf: KdfParams,
+    cipher: String,
+    nonce: String,
+}
+
+/// A bundle as written to disk
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct BundleFile {
+    #[serde(flatten)]
+    header: Header,
+    ciphertext: String,
+}
+
+/// The encrypted part of a bundle
+#[derive(Debug, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct Contents {
+    profiles: Vec<BundledProfile>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct BundledProfile {
+    profile: ProfileData,
+    #[serde(default)]
+    credentials: Vec<BundledCredential>,
+}
+
+/// One managed credential file, `credentials/<provider>/<profile><suffix>`
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct BundledCredential {
+    provider: String,
+    suffix: String,
+    contents: String,
+}
+
+/// Result of `export_profiles`
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ExportSummary {
+    pub path: String,
+    pub profiles: Vec<String>,
+    /// Number of managed credential files in the bundle
+    pub credentials: usize,
+}
+
+/// A bundled profile imported under another name
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RenamedProfile {
+    pub from: String,
+    pub to: String,
+}
+
+/// Result of `import_profiles`
+#[derive(Debug, Clone, Default, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ImportSummary {
+    /// Names the profiles were imported under
+    pub imported: Vec<String>,
+    /// Existing profiles that were replaced
+    pub overwritten: Vec<String>,
+    pub renamed: Vec<RenamedProfile>,
+    /// Bundled profiles left out because the name was taken
+    pub skipped: Vec<String>,
+}
+
+fn decode(value: &str, field: &str) -> Result<Vec<u8>, CommandError> {
+    BASE64
+        .decode(value)
+        .map_err(|e| CommandError::Validation(format!("Invalid {} in bundle: {}", field, e)))
+}
+
+/// Profile and provider names become file names; refuse anything that could leave
+/// the credentials directory
+fn check_file_name(name: &str, what: &str) -> Result<(), CommandError> {
+    let valid = !name.is_empty()
+        && name != "."
+        && name != ".."
+        && !name.contains(['/', '\\', '\0'])
+        && Path::new(name).components().count() == 1;
+    if valid {
+        Ok(())
+    } else {
+        Err(CommandError::Validation(format!("Invalid {} name {:?} in bundle", what, name)))
+    }
+}
+
+fn credential_path(store: &ProfileStore, provider: &str, profile: &str, suffix: &str) -> PathBuf {
+    store.credentials_dir().join(provider).join(format!("{}{}", profile, suffix))
+}
+
+/// Encrypt `contents` with a key derived from `passphrase` using `kdf`
+fn seal(contents: &Contents, passphrase: &str, kdf: KdfParams) -> Result<BundleFile, CommandError> {
+    let key = kdf.derive_key(passphrase)?;
+    let nonce = XChaCha20Poly1305::generate_nonce(&mut OsRng);
+    let header = Header {
+        format: BUNDLE_FORMAT.to_string(),
+        version: BUNDLE_VERSION,
+        kdf,
+        cipher: CIPHER.to_string(),
+        nonce: BASE64.encode(nonce),
+    };
+
+    let plaintext = Zeroizing::new(serde_json::to_vec(contents).map_err(|e| CommandError::Internal(e.to_string()))?);
+    let aad = serde_json::to_vec(&header).map_err(|e| CommandError::Internal(e.to_string()))?;
+    let ciphertext = XChaCha20Poly1305::new(key.as_ref().into())
+        .encrypt(&nonce, Payload { msg: &plaintext, aad: &aad })
+        .map_err(|_| CommandError::Internal("Encryption failed".to_string()))?;
+
+    Ok(BundleFile { header, ciphertext: BASE64.encode(ciphertext) })
+}
+
+/// Check the header and decrypt a bundle; a wrong passphrase and a modified file
+/// can't be told apart
+fn open(bundle: &BundleFile, passphrase: &str) -> Result<Contents, CommandError> {
+    let header = &bundle.header;
+    if header.format != BUNDLE_FORMAT {
+        return Err(CommandError::Validation("Not a profile bundle".to_string()));
+    }
+    if header.version != BUNDLE_VERSION || header.cipher != CIPHER {
+        return Err(CommandError::Validation(format!(
+            "Unsupported bundle version {} ({}); update the app",
+            header.version, header.cipher
+        )));
+    }
+
+    let nonce = decode(&header.nonce, "nonce")?;
+    if nonce.len() != 24 {
+        return Err(CommandError::Validation("Invalid nonce in bundle".to_string()));
+    }
+    let ciphertext = decode(&bundle.ciphertext, "ciphertext")?;
+    let key = header.kdf.derive_key(passphrase)?;
+    let aad = serde_json::to_vec(header).map_err(|e| CommandError::Internal(e.to_string()))?;
+
+    let plaintext = Zeroizing::new(
+        XChaCha20Poly1305::new(key.as_ref().into())
+            .decrypt(XNonce::from_slice(&nonce), Payload { msg: &ciphertext, aad: &aad })
+            .map_err(|_| CommandError::Validation("Wrong passphrase or damaged bundle".to_string()))?,
+    );
+    serde_json::from_slice(&plaintext)
+        .map_err(|e| CommandError::Validation(format!("Invalid bundle contents: {}", e)))
+}
+
+/// Write the profiles `names` of `store`, or all profiles when `names` is empty,
+/// with their managed credentials to an encrypted bundle at `path`
+pub fn export_profiles(
+    store: &ProfileStore,
+    names: &[String],
+    passphrase: &str,
+    path: &Path,
+) -> Result<ExportSummary, CommandError> {
+    export_with(store, names, passphrase, path, KdfParams::generate())
+}
+
+fn export_with(
+    store: &ProfileStore,
+    names: &[String],
+    passphrase: &str,
+    path: &Path,
+    kdf: KdfParams,
+) -> Result<ExportSummary, CommandError> {
+    if passphrase.is_empty() {
+        return Err(CommandError::Validation("A passphrase is required".to_string()));
+    }
+
+    let data = store.load()?;
+    let selected: Vec<&ProfileData> = if names.is_empty() {
+        data.list()
+    } else {
+        names
+            .iter()
+            .map(|name| data.profiles.get(name).ok_or_else(|| CommandError::ProfileNotFound(name.clone())))
+            .collect::<Result<_, _>>()?
+    };
+    if selected.is_empty() {
+        return Err(CommandError::Validation("There are no profiles to export".to_string()));
+    }
+
+    let mut contents = Contents::default();
+    let mut credentials = 0;
+    for profile in selected {
+        let mut bundled = BundledProfile { profile: profile.clone(), credentials: Vec::new() };
+        for provider in profile.providers.keys() {
+            for suffix in CREDENTIAL_SUFFIXES {
+                let file = credential_path(store, provider, &profile.name, suffix);
+                match std::fs::read(&file) {
+                    Ok(bytes) => bundled.credentials.push(BundledCredential {
+                        provider: provider.clone(),
+                        suffix: suffix.to_string(),
+                        contents: BASE64.encode(Zeroizing::new(bytes)),
+                    }),
+                    Err(e) if e.kind() == std::io::ErrorKind::NotFound => {}
+                    Err(e) => return Err(CommandError::io("read", file.display().to_string(), e)),
+                }
+            }
+        }
+        credentials += bundled.credentials.len();
+        contents.profiles.push(bundled);
+    }
+
+    let bundle = seal(&contents, passphrase, kdf)?;
+    let json = serde_json::to_string_pretty(&bundle).map_err(|e| CommandError::Internal(e.to_string()))?;
+    write_private(path, json.as_bytes())?;
+
+    Ok(ExportSummary {
+        path: path.display().to_string(),
+        profiles: contents.profiles.iter().map(|bundled| bundled.profile.name.clone()).collect(),
+        credentials,
+    })
+}
+
+/// Decrypt the bundle at `path` and add its profiles and managed credentials to
+/// `store`, resolving name clashes with `on_conflict`
+pub fn import_profiles(
+    store: &ProfileStore,
+    path: &Path,
+    passphrase: &str,
+    on_conflict: OnConflict,
+) -> Result<ImportSummary, CommandError> {
+    let source = std::fs::read_to_string(path).map_err(|e| CommandError::io("read", path.display().to_string(), e))?;
+    let bundle: BundleFile = serde_json::from_str(&source)
+        .map_err(|_| CommandError::Validation(format!("{} is not a profile bundle", path.display())))?;
+    let contents = open(&bundle, passphrase)?;
+
+    for bundled in &contents.profiles {
+        check_file_name(&bundled.profile.name, "profile")?;
+        for provider in bundled.profile.providers.keys() {
+            check_file_name(provider, "provider")?;
+        }
+        for credential in &bundled.credentials {
+            check_file_name(&credential.provider, "provider")?;
+            if !CREDENTIAL_SUFFIXES.contains(&credential.suffix.as_str()) {
+                return Err(CommandError::Validation(format!("Unknown credential file {:?} in bundle", credential.suffix)));
+            }
+        }
+    }
+
+    // Credentials are written to a staging directory first and only moved into
+    // place once the new profiles.json is, so a failed import changes nothing
+    let staging = Staging::create(store)?;
+    store.update_then(
+        |data| {
+            let mut summary = ImportSummary::default();
+            let mut plan = InstallPlan::default();
+            for bundled in contents.profiles {
+                let original = bundled.profile.name.clone();
+                let name = match (data.profiles.contains_key(&original), on_conflict) {
+                    (false, _) => original.clone(),
+                    (true, OnConflict::Skip) => {
+                        summary.skipped.push(original);
+                        continue;
+                    }
+                    (true, OnConflict::Overwrite) => {
+                        summary.overwritten.push(original.clone());
+                        original.clone()
+                    }
+                    (true, OnConflict::Rename) => {
+                        let name = free_name(data, &original);
+                        summary.renamed.push(RenamedProfile { from: original.clone(), to: name.clone() });
+                        name
+                    }
+                };
+
+                let profile = install(store, &staging, &mut plan, bundled, &name, data.profiles.get(&name))?;
+                data.profiles.insert(name.clone(), profile);
+                summary.imported.push(name);
+            }
+            Ok((summary, plan))
+        },
+        |(summary, plan)| {
+            plan.apply()?;
+            Ok(summary)
+        },
+    )
+}
+
+/// Directory next to `profiles.json` that imported credentials are written to
+/// before they are moved into place; removed when dropped
+struct Staging {
+    dir: PathBuf,
+}
+
+impl Staging {
+    fn create(store: &ProfileStore) -> Result<Self, CommandError> {
+        let dir = store.dir().join(format!(".import-{}", uuid::Uuid::new_v4()));
+        create_private_dir(&dir)?;
+        Ok(Self { dir })
+    }
+}
+
+impl Drop for Staging {
+    fn drop(&mut self) {
+        let _ = std::fs::remove_dir_all(&self.dir);
+    }
+}
+
+/// Credential files to change once the imported profiles are stored
+#[derive(Default)]
+struct InstallPlan {
+    /// Staged file and where it goes
+    moves: Vec<(PathBuf, PathBuf)>,
+    /// Credentials of a replaced profile the bundle has no counterpart for
+    stale: Vec<PathBuf>,
+}
+
+impl InstallPlan {
+    fn apply(self) -> Result<(), CommandError> {
+        for path in &self.stale {
+            match std::fs::remove_file(path) {
+                Ok(()) => {}
+                Err(e) if e.kind() == std::io::ErrorKind::NotFound => {}
+                Err(e) => return Err(CommandError::io("remove", path.display().to_string(), e)),
+            }
+        }
+        for (staged, target) in &self.moves {
+            std::fs::rename(staged, target).map_err(|e| CommandError::io("replace", target.display().to_string(), e))?;
+        }
+        Ok(())
+    }
+}
+
+/// First name `<name>-imported`, `<name>-imported-2`, ... not taken in `data`
+fn free_name(data: &ProfileStoreData, name: &str) -> String {
+    let base = format!("{}-imported", name);
+    std::iter::once(base.clone())
+        .chain((2..).map(|n| format!("{}-{}", base, n)))
+        .find(|candidate| !data.profiles.contains_key(candidate))
+        .expect("unbounded candidates")
+}
+
+/// Stage the credentials of `bundled` for the profile `name` and point its managed
+/// providers at them. Credentials `previous`, the profile it replaces, had that the
+/// bundle doesn't bring are removed along with it.
+fn install(
+    store: &ProfileStore,
+    staging: &Staging,
+    plan: &mut InstallPlan,
+    bundled: BundledProfile,
+    name: &str,
+    previous: Option<&ProfileData>,
+) -> Result<ProfileData, CommandError> {
+    let mut profile = bundled.profile;
+    profile.name = name.to_string();
+
+    let mut targets = Vec::new();
+    for credential in &bundled.credentials {
+        let bytes = Zeroizing::new(decode(&credential.contents, "credential")?);
+        let staged_dir = staging.dir.join(&credential.provider);
+        create_private_dir(&staged_dir)?;
+        let staged = staged_dir.join(format!("{}{}", name, credential.suffix));
+        write_private(&staged, &bytes)?;
+
+        let target = credential_path(store, &credential.provider, name, &credential.suffix);
+        if let Some(dir) = target.parent() {
+            create_private_dir(dir)?;
+        }
+        // A profile imported twice under the same name keeps the last credentials
+        plan.moves.retain(|(_, planned)| planned != &target);
+        plan.moves.push((staged, target.clone()));
+        targets.push(target);
+    }
+
+    let providers = previous.into_iter().flat_map(|previous| previous.providers.keys()).chain(profile.providers.keys());
+    for provider in providers {
+        for suffix in CREDENTIAL_SUFFIXES {
+            let file = credential_path(store, provider, name, suffix);
+            if !targets.contains(&file) && !plan.stale.contains(&file) && file.exists() {
+                plan.stale.push(file);
+            }
+        }
+    }
+
+    // Paths of the exporting machine mean nothing here, and a bundle must not point
+    // this machine at files outside the credentials directory
+    for (provider, info) in profile.providers.iter_mut() {
+        info.credential_path = (info.credential_source == CredentialSource::Managed)
+            .then(|| credential_path(store, provider, name, ".json").display().to_string());
+    }
+    profile.touch();
+    Ok(profile)
+}
+
+fn create_private_dir(dir: &Path) -> Result<(), CommandError> {
+    let mut builder = std::fs::DirBuilder::new();
+    builder.recursive(true);
+    #[cfg(unix)]
+    std::os::unix::fs::DirBuilderExt::mode(&mut builder, 0o700);
+    builder.create(dir).map_err(|e| CommandError::io("create", dir.display().to_string(), e))
+}
+
+/// Replace `path` with `contents`, readable only by the user
+fn write_private(path: &Path, contents: &[u8]) -> Result<(), CommandError> {
+    let mut options = OpenOptions::new();
+    options.create(true).truncate(true).write(true);
+    #[cfg(unix)]
+    std::os::unix::fs::OpenOptionsExt::mode(&mut options, 0o600);
+
+    options
+        .open(path)
+        .and_then(|mut file| file.write_all(contents))
+        .map_err(|e| CommandError::io("write", path.display().to_string(), e))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::profile_store::PROFILES_FILE;
+
+    /// Cheap enough for debug builds
+    fn test_kdf() -> KdfParams {
+        KdfParams { memory_kib: 64, iterations: 1, ..KdfParams::generate() }
+    }
+
+    fn store_with(profiles: &str) -> ProfileStore {
+        let dir = std::env::temp_dir().join(format!("multicoder-bundle-{}", uuid::Uuid::new_v4()));
+        std::fs::create_dir_all(dir.join("credentials").join("claude")).unwrap();
+        std::fs::write(dir.join("profiles.json"), profiles).unwrap();
+        ProfileStore::new(dir, Vec::new())
+    }
+
+    const WORK: &str = r#"{ "current": "work", "profiles": [{
+        "name": "work",
+        "providers": { "claude": { "credentialSource": "managed", "credentialPath": "/home/old/.multicoder/credentials/claude/work.json" } },
+        "permissionMode": "allow",
+        "createdAt": 1
+    }] }"#;
+
+    #[test]
+    fn round_trips_profiles_and_managed_credentials() {
+        let source = store_with(WORK);
+        std::fs::write(source.credentials_dir().join("claude").join("work.json"), r#"{"token":"t"}"#).unwrap();
+        let bundle = source.dir().join("profiles.bundle");
+
+        let exported = export_with(&source, &[], "correct horse", &bundle, test_kdf()).unwrap();
+        assert_eq!((exported.profiles, exported.credentials), (vec!["work".to_string()], 1));
+        assert!(!std::fs::read_to_string(&bundle).unwrap().contains("token"));
+
+        let target = store_with(r#"{ "current": null, "profiles": [] }"#);
+        let imported = import_profiles(&target, &bundle, "correct horse", OnConflict::Skip).unwrap();
+        assert_eq!(imported.imported, ["work"]);
+
+        let credential = target.credentials_dir().join("claude").join("work.json");
+        assert_eq!(std::fs::read_to_string(&credential).unwrap(), r#"{"token":"t"}"#);
+        let data = target.load().unwrap();
+        let work = &data.profiles["work"];
+        assert_eq!(work.permission_mode, Some(crate::permissions::PermissionMode::Allow));
+        assert_eq!(work.providers["claude"].credential_path.as_deref(), Some(credential.to_str().unwrap()));
+        assert_eq!(data.current_profile, None);
+
+        std::fs::remove_dir_all(source.dir()).unwrap();
+        std::fs::remove_dir_all(target.dir()).unwrap();
+    }
+
+    #[test]
+    fn resolves_name_conflicts() {
+        let store = store_with(WORK);
+        let bundle = store.dir().join("profiles.bundle");
+        export_with(&store, &["work".to_string()], "pw", &bundle, test_kdf()).unwrap();
+
+        let skipped = import_profiles(&store, &bundle, "pw", OnConflict::Skip).unwrap();
+        assert_eq!((skipped.imported.len(), skipped.skipped), (0, vec!["work".to_string()]));
+
+        let renamed = import_profiles(&store, &bundle, "pw", OnConflict::Rename).unwrap();
+        assert_eq!(renamed.renamed, [RenamedProfile { from: "work".to_string(), to: "work-imported".to_string() }]);
+        let again = import_profiles(&store, &bundle, "pw", OnConflict::Rename).unwrap();
+        assert_eq!(again.imported, ["work-imported-2"]);
+
+        let overwritten = import_profiles(&store, &bundle, "pw", OnConflict::Overwrite).unwrap();
+        assert_eq!(overwritten.overwritten, ["work"]);
+        assert_eq!(store.load().unwrap().profiles.len(), 3);
+
+        std::fs::remove_dir_all(store.dir()).unwrap();
+    }
+
+    /// A store whose "work" profile has claude and codex credentials that differ
+    /// from the ones in a bundle exported from `WORK`
+    fn overwrite_setup() -> (ProfileStore, ProfileStore, PathBuf) {
+        let source = store_with(WORK);
+        std::fs::write(source.credentials_dir().join("claude").join("work.json"), "new").unwrap();
+        let bundle = source.dir().join("profiles.bundle");
+        export_with(&source, &[], "pw", &bundle, test_kdf()).unwrap();
+
+        let target = store_with(
+            r#"{ "current": "work", "profiles": [{ "name": "work", "providers": {
+                "claude": { "credentialSource": "managed" },
+                "codex": { "credentialSource": "managed" }
+            } }] }"#,
+        );
+        std::fs::create_dir_all(target.credentials_dir().join("codex")).unwrap();
+        std::fs::write(target.credentials_dir().join("claude").join("work.json"), "old").unwrap();
+        std::fs::write(target.credentials_dir().join("codex").join("work.json"), "old").unwrap();
+        (source, target, bundle)
+    }
+
+    #[test]
+    fn overwriting_replaces_the_previous_credentials() {
+        let (source, target, bundle) = overwrite_setup();
+
+        import_profiles(&target, &bundle, "pw", OnConflict::Overwrite).unwrap();
+
+        let credentials = target.credentials_dir();
+        assert_eq!(std::fs::read_to_string(credentials.join("claude").join("work.json")).unwrap(), "new");
+        assert!(!credentials.join("codex").join("work.json").exists());
+        assert!(!target.load().unwrap().profiles["work"].providers.contains_key("codex"));
+
+        std::fs::remove_dir_all(source.dir()).unwrap();
+        std::fs::remove_dir_all(target.dir()).unwrap();
+    }
+
+    #[test]
+    fn failed_imports_change_nothing() {
+        let (source, target, bundle) = overwrite_setup();
+        let profiles = std::fs::read_to_string(target.dir().join(PROFILES_FILE)).unwrap();
+        // The credentials are staged by now; replacing profiles.json fails
+        let blocker = target.dir().join(format!("{}.{}.tmp", PROFILES_FILE, std::process::id()));
+        std::fs::create_dir(&blocker).unwrap();
+
+        assert!(import_profiles(&target, &bundle, "pw", OnConflict::Overwrite).is_err());
+
+        let credentials = target.credentials_dir();
+        assert_eq!(std::fs::read_to_string(target.dir().join(PROFILES_FILE)).unwrap(), profiles);
+        assert_eq!(std::fs::read_to_string(credentials.join("claude").join("work.json")).unwrap(), "old");
+        assert_eq!(std::fs::read_to_string(credentials.join("codex").join("work.json")).unwrap(), "old");
+        let leftovers: Vec<_> = std::fs::read_dir(target.dir())
+            .unwrap()
+            .map(|entry| entry.unwrap().file_name().to_string_lossy().into_owned())
+            .filter(|name| name.starts_with(".import-"))
+            .collect();
+        assert!(leftovers.is_empty(), "{:?}", leftovers);
+
+        std::fs::remove_dir(&blocker).unwrap();
+        import_profiles(&target, &bundle, "pw", OnConflict::Overwrite).unwrap();
+        assert_eq!(std::fs::read_to_string(credentials.join("claude").join("work.json")).unwrap(), "new");
+
+        std::fs::remove_dir_all(source.dir()).unwrap();
+        std::fs::remove_dir_all(target.dir()).unwrap();
+    }
+
+    #[test]
+    fn rejects_wrong_passphrases_and_modified_bundles() {
+        let store = store_with(WORK);
+        let bundle = store.dir().join("profiles.bundle");
+        export_with(&store, &[], "pw", &bundle, test_kdf()).unwrap();
+        let wrong = Err(CommandError::Validation("Wrong passphrase or damaged bundle".to_string()));
+
+        assert_eq!(import_profiles(&store, &bundle, "not pw", OnConflict::Rename), wrong);
+
+        // The header is authenticated too
+        let mut file: serde_json::Value = serde_json::from_str(&std::fs::read_to_string(&bundle).unwrap()).unwrap();
+        file["kdf"]["parallelism"] = serde_json::json!(2);
+        std::fs::write(&bundle, file.to_string()).unwrap();
+        assert_eq!(import_profiles(&store, &bundle, "pw", OnConflict::Rename), wrong);
+        assert_eq!(store.load().unwrap().profiles.len(), 1);
+
+        std::fs::remove_dir_all(store.dir()).unwrap();
+    }
+
+    #[test]
+    fn refuses_provider_keys_that_leave_the_credentials_directory() {
+        let source = store_with(
+            r#"{ "current": null, "profiles": [{ "name": "work", "providers": {
+                "../outside": { "credentialSource": "managed" }
+            } }] }"#,
+        );
+        let bundle = source.dir().join("profiles.bundle");
+        export_with(&source, &[], "pw", &bundle, test_kdf()).unwrap();
+
+        // Where credentials/../outside/work.json would land
+        let target = store_with(r#"{ "current": null, "profiles": [] }"#);
+        let outside = target.dir().join("outside").join("work.json");
+        std::fs::create_dir_all(outside.parent().unwrap()).unwrap();
+        std::fs::write(&outside, "keep").unwrap();
+        let error = import_profiles(&target, &bundle, "pw", OnConflict::Overwrite).unwrap_err();
+        assert_eq!(error, CommandError::Validation("Invalid provider name \"../outside\" in bundle".to_string()));
+        assert_eq!(std::fs::read_to_string(&outside).unwrap(), "keep");
+        assert!(target.load().unwrap().profiles.is_empty());
+
+        std::fs::remove_dir_all(source.dir()).unwrap();
+        std::fs::remove_dir_all(target.dir()).unwrap();
+    }
+
+    #[test]
+    fn drops_credential_paths_of_unmanaged_providers() {
+        let source = store_with(
+            r#"{ "current": null, "profiles": [{ "name": "work", "providers": {
+                "gemini": { "credentialSource": "native", "credentialPath": "/etc/shadow" }
+            } }] }"#,
+        );
+        let bundle = source.dir().join("profiles.bundle");
+        export_with(&source, &[], "pw", &bundle, test_kdf()).unwrap();
+
+        import_profiles(&source, &bundle, "pw", OnConflict::Overwrite).unwrap();
+        assert_eq!(source.load().unwrap().profiles["work"].providers["gemini"].credential_path, None);
+
+        std::fs::remove_dir_all(source.dir()).unwrap();
+    }
+
+    #[test]
+    fn refuses_names_that_leave_the_credentials_directory() {
+        assert!(check_file_name("work", "profile").is_ok());
+        for name in ["", ".", "..", "../escape", "a/b", "a\\b"] {
+            assert!(check_file_name(name, "profile").is_err(), "{:?}", name);
+        }
+    }
+}
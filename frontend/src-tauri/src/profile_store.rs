@@ -0,0 +1,753 @@
+//! Access to the profiles the bridge and the CLI keep in `profiles.json`, so
+//! profiles can be read and edited while the bridge is not running. The types
+//! mirror `src/profile/types.ts`.
+//!
+//! Changes hold the profiles lock shared with the bridge and the CLI
+//! (`src/profile/lock.ts`) and replace the file by renaming a fully written
+//! temporary file over it, so concurrent writers don't lose each other's
+//! changes and readers never see a partial file.
+
+use serde::{Deserialize, Deserializer, Serialize};
+use std::collections::BTreeMap;
+use std::fs::OpenOptions;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+use tracing::warn;
+
+use crate::config::custom_config_dir;
+use crate::error::CommandError;
+use crate::permissions::PermissionMode;
+
+/// Name of the profile store in the config directory
+pub const PROFILES_FILE: &str = "profiles.json";
+
+/// Lock taken around every change of the profile store
+const LOCK_FILE: &str = "profiles.json.lock";
+
+/// Held while taking over a lock whose owner is gone
+const BREAK_FILE: &str = "profiles.json.lock.break";
+
+/// A lock file without a PID this old was left behind by a crash right after creating it
+const LOCK_UNWRITTEN: Duration = Duration::from_secs(10);
+
+const LOCK_TIMEOUT: Duration = Duration::from_secs(15);
+
+const LOCK_RETRY: Duration = Duration::from_millis(10);
+
+/// Directory of managed credentials in the config directory
+const CREDENTIALS_DIR: &str = "credentials";
+
+/// Version written by the bridge's `ProfileStore`
+const STORE_VERSION: &str = "2.0";
+
+/// Where a provider's credentials for a profile come from
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum CredentialSource {
+    /// The provider CLI's own credential file
+    #[default]
+    Native,
+    /// A copy kept in the multicoder config directory
+    Managed,
+    /// Environment variables
+    Env,
+}
+
+/// Unknown sources are read as `native`, like the bridge does
+fn lenient_source<'de, D: Deserializer<'de>>(deserializer: D) -> Result<CredentialSource, D::Error> {
+    let source = Option::<serde_json::Value>::deserialize(deserializer)?;
+    Ok(source
+        .and_then(|source| serde_json::from_value(source).ok())
+        .unwrap_or_default())
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ProviderAuthInfo {
+    #[serde(default, deserialize_with = "lenient_source")]
+    pub credential_source: CredentialSource,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub credential_path: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub last_auth: Option<u64>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub expires_at: Option<u64>,
+    /// Fields only the bridge knows about, written back unchanged
+    #[serde(flatten)]
+    pub extra: serde_json::Map<String, serde_json::Value>,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ProfileData {
+    /// Taken from the key when the store keeps profiles in a map
+    #[serde(default)]
+    pub name: String,
+    #[serde(default)]
+    pub providers: BTreeMap<String, ProviderAuthInfo>,
+    /// Provider used last with this profile
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub last_provider: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub last_used_at: Option<u64>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub permission_mode: Option<PermissionMode>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub model: Option<String>,
+    #[serde(default)]
+    pub created_at: u64,
+    #[serde(default)]
+    pub updated_at: u64,
+    /// Fields only the bridge knows about, written back unchanged
+    #[serde(flatten)]
+    pub extra: serde_json::Map<String, serde_json::Value>,
+}
+
+impl ProfileData {
+    /// Record a change at the current time, like the bridge's `updatedAt`
+    pub fn touch(&mut self) {
+        self.updated_at = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map(|elapsed| elapsed.as_millis() as u64)
+            .unwrap_or(0);
+    }
+}
+
+/// Contents of `profiles.json`
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ProfileStoreData {
+    pub version: String,
+    pub current_profile: Option<String>,
+    pub profiles: BTreeMap<String, ProfileData>,
+    /// Profiles that could not be read; the store is not rewritten while there are any
+    #[serde(skip)]
+    unreadable: Vec<String>,
+    /// Top-level fields only the bridge knows about, written back unchanged
+    #[serde(skip)]
+    extra: serde_json::Map<String, serde_json::Value>,
+}
+
+impl Default for ProfileStoreData {
+    fn default() -> Self {
+        Self {
+            version: STORE_VERSION.to_string(),
+            current_profile: None,
+            profiles: BTreeMap::new(),
+            unreadable: Vec::new(),
+            extra: serde_json::Map::new(),
+        }
+    }
+}
+
+/// The file as written by either of the bridge's stores: `ProfileManager` writes
+/// `current` and a list, `ProfileStore` also `currentProfile` and `version`
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct RawStore {
+    version: Option<String>,
+    current_profile: Option<String>,
+    current: Option<String>,
+    #[serde(default)]
+    profiles: RawProfiles,
+    #[serde(flatten)]
+    extra: serde_json::Map<String, serde_json::Value>,
+}
+
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum RawProfiles {
+    List(Vec<serde_json::Value>),
+    Map(BTreeMap<String, serde_json::Value>),
+}
+
+impl Default for RawProfiles {
+    fn default() -> Self {
+        RawProfiles::List(Vec::new())
+    }
+}
+
+impl ProfileStoreData {
+    /// Parse `profiles.json`, skipping profiles that can't be read
+    pub fn parse(source: &str) -> Result<Self, serde_json::Error> {
+        let raw: RawStore = serde_json::from_str(source)?;
+
+        let entries: Vec<(Option<String>, serde_json::Value)> = match raw.profiles {
+            RawProfiles::List(profiles) => profiles.into_iter().map(|profile| (None, profile)).collect(),
+            RawProfiles::Map(profiles) => profiles.into_iter().map(|(name, profile)| (Some(name), profile)).collect(),
+        };
+
+        let mut profiles = BTreeMap::new();
+        let mut unreadable = Vec::new();
+        for (key, value) in entries {
+            let label = key.clone().unwrap_or_else(|| "in list".to_string());
+            let mut profile = match serde_json::from_value::<ProfileData>(value) {
+                Ok(profile) => profile,
+                Err(e) => {
+                    warn!("skipping unreadable profile {}: {}", label, e);
+                    unreadable.push(label);
+                    continue;
+                }
+            };
+            if profile.name.is_empty() {
+                match key {
+                    Some(key) if !key.is_empty() => profile.name = key,
+                    _ => {
+                        warn!("skipping profile without a name");
+                        unreadable.push(label);
+                        continue;
+                    }
+                }
+            }
+            if profile.updated_at == 0 {
+                profile.updated_at = profile.created_at;
+            }
+            profiles.insert(profile.name.clone(), profile);
+        }
+
+        let current_profile = raw
+            .current_profile
+            .or(raw.current)
+            .filter(|name| profiles.contains_key(name));
+
+        Ok(Self {
+            version: raw.version.unwrap_or_else(|| STORE_VERSION.to_string()),
+            current_profile,
+            profiles,
+            unreadable,
+            extra: raw.extra,
+        })
+    }
+
+    /// Every profile, ordered by name
+    pub fn list(&self) -> Vec<&ProfileData> {
+        self.profiles.values().collect()
+    }
+
+    /// The profile selected last, if it still exists
+    pub fn current(&self) -> Option<&ProfileData> {
+        self.current_profile.as_ref().and_then(|name| self.profiles.get(name))
+    }
+
+    /// The file contents, readable by both of the bridge's stores
+    fn to_file(&self) -> serde_json::Value {
+        let mut file = self.extra.clone();
+        file.insert("version".to_string(), self.version.clone().into());
+        file.insert("current".to_string(), self.current_profile.clone().into());
+        file.insert("currentProfile".to_string(), self.current_profile.clone().into());
+        file.insert("profiles".to_string(), serde_json::json!(self.list()));
+        file.into()
+    }
+}
+
+/// Locates, reads and updates `profiles.json`
+#[derive(Debug, Clone)]
+pub struct ProfileStore {
+    /// Config directory the bridge uses
+    dir: PathBuf,
+    /// Older config directories, read until the bridge migrated them
+    legacy_dirs: Vec<PathBuf>,
+}
+
+impl ProfileStore {
+    pub fn new(dir: PathBuf, legacy_dirs: Vec<PathBuf>) -> Self {
+        Self { dir, legacy_dirs }
+    }
+
+    /// The config directory holding `profiles.json`
+    pub fn dir(&self) -> &Path {
+        &self.dir
+    }
+
+    /// Where the bridge keeps managed credentials
+    pub fn credentials_dir(&self) -> PathBuf {
+        self.dir.join(CREDENTIALS_DIR)
+    }
+
+    /// The store the bridge uses: in the custom config directory when one is set,
+    /// otherwise in `~/.multicoder` or, until migrated, a legacy `.unycode` or
+    /// `unycoding` directory
+    pub fn from_env() -> Option<Self> {
+        if let Some(dir) = custom_config_dir() {
+            return Some(Self::new(dir, Vec::new()));
+        }
+
+        let home = dirs::home_dir()?;
+        let legacy_dirs = [
+            // Legacy
+            home.join(".unycode"),
+            home.join(".config").join("unycoding"),
+            home.join("AppData").join("Roaming").join("unycoding"),
+            home.join("Library").join("Application Support").join("unycoding"),
+            // Transitional
+            home.join(".config").join("multicoder"),
+            home.join("AppData").join("Roaming").join("multicoder"),
+            home.join("Library").join("Application Support").join("multicoder"),
+        ];
+        Some(Self::new(home.join(".multicoder"), legacy_dirs.into()))
+    }
+
+    /// The file to read: the current one, or the first legacy file if that doesn't exist yet
+    pub fn path(&self) -> PathBuf {
+        let current = self.dir.join(PROFILES_FILE);
+        if current.exists() {
+            return current;
+        }
+        self.legacy_dirs
+            .iter()
+            .map(|dir| dir.join(PROFILES_FILE))
+            .find(|path| path.exists())
+            .unwrap_or(current)
+    }
+
+    /// Read the store; a missing file holds no profiles
+    pub fn load(&self) -> Result<ProfileStoreData, CommandError> {
+        let path = self.path();
+        match std::fs::read_to_string(&path) {
+            Ok(source) => Self::parse_file(&path, &source),
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(ProfileStoreData::default()),
+            Err(e) => Err(CommandError::io("read", path.display().to_string(), e)),
+        }
+    }
+
+    fn parse_file(path: &Path, source: &str) -> Result<ProfileStoreData, CommandError> {
+        ProfileStoreData::parse(source).map_err(|e| CommandError::Io {
+            path: Some(path.display().to_string()),
+            message: format!("Failed to parse {}: {}", path.display(), e),
+        })
+    }
+
+    /// Apply `change` to the stored profiles while holding the store's lock and
+    /// write the result; nothing is written when `change` fails
+    pub fn update<T>(
+        &self,
+        change: impl FnOnce(&mut ProfileStoreData) -> Result<T, CommandError>,
+    ) -> Result<T, CommandError> {
+        self.update_then(change, Ok)
+    }
+
+    /// Like `update`, then hand the result of `change` to `commit` once the new
+    /// `profiles.json` is in place, still holding the lock
+    pub fn update_then<T, U>(
+        &self,
+        change: impl FnOnce(&mut ProfileStoreData) -> Result<T, CommandError>,
+        commit: impl FnOnce(T) -> Result<U, CommandError>,
+    ) -> Result<U, CommandError> {
+        let _lock = StoreLock::acquire(&self.dir)?;
+        let mut data = self.load()?;
+        // Rewriting the file would drop them
+        if !data.unreadable.is_empty() {
+            let path = self.path();
+            return Err(CommandError::Io {
+                path: Some(path.display().to_string()),
+                message: format!(
+                    "Not changing {}: it holds profiles that can't be read ({}); fix or remove them first",
+                    path.display(),
+                    data.unreadable.join(", ")
+                ),
+            });
+        }
+        let result = change(&mut data)?;
+        self.write(&data)?;
+        commit(result)
+    }
+
+    /// Write to a temporary file next to `profiles.json` and rename it over the original
+    fn write(&self, data: &ProfileStoreData) -> Result<(), CommandError> {
+        let path = self.dir.join(PROFILES_FILE);
+        let temp = self.dir.join(format!("{}.{}.tmp", PROFILES_FILE, std::process::id()));
+        let io_error = |e: std::io::Error| CommandError::io("write", path.display().to_string(), e);
+
+        let contents = serde_json::to_string_pretty(&data.to_file()).map_err(|e| CommandError::Internal(e.to_string()))?;
+        let mut options = OpenOptions::new();
+        options.create(true).truncate(true).write(true);
+        #[cfg(unix)]
+        std::os::unix::fs::OpenOptionsExt::mode(&mut options, 0o600);
+
+        let written = options.open(&temp).and_then(|mut file| {
+            file.write_all(contents.as_bytes())?;
+            file.sync_all()
+        });
+        if let Err(e) = written.and_then(|_| std::fs::rename(&temp, &path)) {
+            let _ = std::fs::remove_file(&temp);
+            return Err(io_error(e));
+        }
+        Ok(())
+    }
+}
+
+/// The profiles lock, held by whoever created `profiles.json.lock` and released by
+/// removing it. The file holds the owner's PID and a token of its own. A lock is
+/// taken over only once its owner is gone, and only while holding
+/// `profiles.json.lock.break`, so two waiters cannot both remove it and one of them
+/// delete the lock the other just took. Node has no `flock`, so this is the
+/// protocol `src/profile/lock.ts` implements as well.
+struct StoreLock {
+    path: PathBuf,
+    contents: String,
+}
+
+impl StoreLock {
+    /// Block until no other writer holds the lock
+    fn acquire(dir: &Path) -> Result<Self, CommandError> {
+        let path = dir.join(LOCK_FILE);
+        std::fs::create_dir_all(dir).map_err(|e| CommandError::io("create", dir.display().to_string(), e))?;
+
+        let contents = format!("{} {}\n", std::process::id(), uuid::Uuid::new_v4());
+        let deadline = Instant::now() + LOCK_TIMEOUT;
+        while !create_lock_file(&path, &contents).map_err(|e| CommandError::io("lock", path.display().to_string(), e))? {
+            Self::break_abandoned(dir);
+            if Instant::now() > deadline {
+                return Err(CommandError::Io {
+                    path: Some(path.display().to_string()),
+                    message: format!(
+                        "Timed out waiting for {}; remove it if no other multicoder process is running",
+                        path.display()
+                    ),
+                });
+            }
+            std::thread::sleep(LOCK_RETRY);
+        }
+        Ok(Self { path, contents })
+    }
+
+    /// Remove the lock if its owner is gone, so the next attempt can succeed
+    fn break_abandoned(dir: &Path) {
+        let path = dir.join(LOCK_FILE);
+        if !abandoned(&path) {
+            return;
+        }
+
+        let breaker = dir.join(BREAK_FILE);
+        match create_lock_file(&breaker, &format!("{}\n", std::process::id())) {
+            Ok(true) => {}
+            // Someone else is taking it over; clear their breaker only if they died doing so
+            Ok(false) => {
+                if abandoned(&breaker) {
+                    let _ = std::fs::remove_file(&breaker);
+                }
+                return;
+            }
+            Err(_) => return,
+        }
+        // Nobody else removes the lock while we hold the breaker, and an abandoned lock
+        // stays abandoned, so it is still the one we looked at if this holds
+        if abandoned(&path) {
+            warn!("taking over {} left behind by a process that is gone", path.display());
+            let _ = std::fs::remove_file(&path);
+        }
+        let _ = std::fs::remove_file(&breaker);
+    }
+}
+
+impl Drop for StoreLock {
+    fn drop(&mut self) {
+        // Only remove the lock if it is still ours
+        if std::fs::read_to_string(&self.path).is_ok_and(|contents| contents == self.contents) {
+            let _ = std::fs::remove_file(&self.path);
+        }
+    }
+}
+
+/// Exclusively create a lock file holding `contents`; false if it already exists
+fn create_lock_file(path: &Path, contents: &str) -> std::io::Result<bool> {
+    let mut options = OpenOptions::new();
+    options.write(true).create_new(true);
+    #[cfg(unix)]
+    std::os::unix::fs::OpenOptionsExt::mode(&mut options, 0o600);
+
+    match options.open(path) {
+        Ok(mut file) => {
+            if let Err(e) = file.write_all(contents.as_bytes()) {
+                let _ = std::fs::remove_file(path);
+                return Err(e);
+            }
+            Ok(true)
+        }
+        Err(e) if e.kind() == std::io::ErrorKind::AlreadyExists => Ok(false),
+        Err(e) => Err(e),
+    }
+}
+
+/// Whether the lock file at `path` was left behind by a process that is gone
+fn abandoned(path: &Path) -> bool {
+    // Released while we looked at it
+    let Ok(contents) = std::fs::read_to_string(path) else {
+        return false;
+    };
+    match contents.split_whitespace().next().and_then(|pid| pid.parse::<u32>().ok()) {
+        #[cfg(unix)]
+        Some(owner) => owner > 0 && !crate::process::process_alive(owner),
+        // Without a way to check the owner, the lock waits for its owner or the user
+        #[cfg(not(unix))]
+        Some(_) => false,
+        // The owner writes its PID right after creating the file, so a lock that still
+        // has none after a while belongs to a process that died in between
+        None => std::fs::metadata(path)
+            .and_then(|metadata| metadata.modified())
+            .ok()
+            .and_then(|at| at.elapsed().ok())
+            .is_some_and(|age| age > LOCK_UNWRITTEN),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn reads_the_list_written_by_the_profile_manager() {
+        let store = ProfileStoreData::parse(
+            r#"{
+                "current": "work",
+                "profiles": [
+                    {
+                        "name": "personal",
+                        "providers": {},
+                        "permissionMode": "ask",
+                        "createdAt": 1700000000000,
+                        "updatedAt": 1700000000500
+                    },
+                    {
+                        "name": "work",
+                        "providers": {
+                            "claude": { "credentialSource": "managed", "credentialPath": "/creds/claude.json", "lastAuth": 1700000001000 },
+                            "gemini": { "credentialSource": "keychain" }
+                        },
+                        "lastProvider": "claude",
+                        "permissionMode": "allow",
+                        "model": "sonnet",
+                        "createdAt": 1700000000000,
+                        "updatedAt": 1700000002000
+                    },
+                    { "providers": {} }
+                ]
+            }"#,
+        )
+        .unwrap();
+
+        assert_eq!(store.version, STORE_VERSION);
+        assert_eq!(store.list().iter().map(|p| p.name.as_str()).collect::<Vec<_>>(), ["personal", "work"]);
+
+        let work = store.current().unwrap();
+        assert_eq!(work.permission_mode, Some(PermissionMode::Allow));
+        assert_eq!(work.providers["claude"].credential_source, CredentialSource::Managed);
+        assert_eq!(work.providers["claude"].credential_path.as_deref(), Some("/creds/claude.json"));
+        assert_eq!(work.providers["gemini"].credential_source, CredentialSource::Native);
+    }
+
+    #[test]
+    fn reads_the_map_written_by_the_profile_store() {
+        let store = ProfileStoreData::parse(
+            r#"{
+                "version": "2.0",
+                "currentProfile": "gone",
+                "profiles": { "solo": { "providers": { "codex": { "credentialSource": "env" } }, "createdAt": 5 } }
+            }"#,
+        )
+        .unwrap();
+
+        let solo = &store.profiles["solo"];
+        assert_eq!(solo.name, "solo");
+        assert_eq!(solo.updated_at, 5);
+        assert_eq!(solo.providers["codex"].credential_source, CredentialSource::Env);
+        assert_eq!(store.current(), None);
+    }
+
+    #[test]
+    fn falls_back_to_a_legacy_store_until_it_is_migrated() {
+        let root = std::env::temp_dir().join(format!("multicoder-profile-store-{}", uuid::Uuid::new_v4()));
+        let current = root.join(".multicoder");
+        let legacy = root.join(".unycode");
+        std::fs::create_dir_all(&legacy).unwrap();
+        std::fs::write(legacy.join(PROFILES_FILE), r#"{ "current": "old", "profiles": [{ "name": "old" }] }"#).unwrap();
+
+        let store = ProfileStore::new(current.clone(), vec![legacy]);
+        assert_eq!(store.load().unwrap().current().unwrap().name, "old");
+
+        std::fs::create_dir_all(&current).unwrap();
+        std::fs::write(current.join(PROFILES_FILE), r#"{ "current": null, "profiles": [] }"#).unwrap();
+        assert!(store.load().unwrap().profiles.is_empty());
+
+        std::fs::remove_dir_all(root).unwrap();
+    }
+
+    #[test]
+    fn updates_replace_the_file_and_keep_both_formats_readable() {
+        let dir = std::env::temp_dir().join(format!("multicoder-profile-store-{}", uuid::Uuid::new_v4()));
+        let store = ProfileStore::new(dir.clone(), Vec::new());
+        std::fs::create_dir_all(&dir).unwrap();
+        std::fs::write(dir.join(PROFILES_FILE), r#"{ "current": "work", "profiles": [{ "name": "work", "createdAt": 1 }] }"#)
+            .unwrap();
+
+        store
+            .update(|data| {
+                data.profiles.get_mut("work").unwrap().permission_mode = Some(PermissionMode::Deny);
+                Ok(())
+            })
+            .unwrap();
+        let failed = store.update(|_| -> Result<(), CommandError> { Err(CommandError::ProfileNotFound("x".to_string())) });
+        assert_eq!(failed, Err(CommandError::ProfileNotFound("x".to_string())));
+
+        let written: serde_json::Value =
+            serde_json::from_str(&std::fs::read_to_string(dir.join(PROFILES_FILE)).unwrap()).unwrap();
+        assert_eq!(written["current"], "work");
+        assert_eq!(written["currentProfile"], "work");
+        assert_eq!(written["profiles"][0]["permissionMode"], "deny");
+        assert_eq!(store.load().unwrap().current().unwrap().permission_mode, Some(PermissionMode::Deny));
+        let leftovers: Vec<_> = std::fs::read_dir(&dir)
+            .unwrap()
+            .map(|entry| entry.unwrap().file_name().into_string().unwrap())
+            .filter(|name| name.ends_with(".tmp"))
+            .collect();
+        assert!(leftovers.is_empty(), "temporary files left behind: {:?}", leftovers);
+
+        std::fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn updates_keep_fields_they_do_not_know_and_refuse_unreadable_profiles() {
+        let dir = std::env::temp_dir().join(format!("multicoder-profile-store-{}", uuid::Uuid::new_v4()));
+        let store = ProfileStore::new(dir.clone(), Vec::new());
+        std::fs::create_dir_all(&dir).unwrap();
+        let path = dir.join(PROFILES_FILE);
+        std::fs::write(
+            &path,
+            r#"{ "settings": { "theme": "dark" }, "profiles": { "work": {
+                "tags": ["team"], "providers": { "claude": { "credentialSource": "managed", "scopes": ["a"] } } } } }"#,
+        )
+        .unwrap();
+
+        store
+            .update(|data| {
+                data.profiles.get_mut("work").unwrap().model = Some("opus".to_string());
+                Ok(())
+            })
+            .unwrap();
+        let written: serde_json::Value = serde_json::from_str(&std::fs::read_to_string(&path).unwrap()).unwrap();
+        assert_eq!(written["settings"]["theme"], "dark");
+        assert_eq!(written["profiles"][0]["model"], "opus");
+        assert_eq!(written["profiles"][0]["tags"][0], "team");
+        assert_eq!(written["profiles"][0]["providers"]["claude"]["scopes"][0], "a");
+
+        let unreadable = r#"{ "profiles": [{ "name": "work" }, { "name": "broken", "createdAt": "yesterday" }] }"#;
+        std::fs::write(&path, unreadable).unwrap();
+        assert_eq!(store.load().unwrap().profiles.len(), 1);
+        assert!(matches!(store.update(|_| Ok(())), Err(CommandError::Io { .. })));
+        assert_eq!(std::fs::read_to_string(&path).unwrap(), unreadable);
+
+        std::fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn takes_over_a_lock_left_behind_by_a_dead_process() {
+        let dir = std::env::temp_dir().join(format!("multicoder-profile-lock-{}", uuid::Uuid::new_v4()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let mut exited = std::process::Command::new("true").spawn().unwrap();
+        exited.wait().unwrap();
+        std::fs::write(dir.join(LOCK_FILE), format!("{}\n", exited.id())).unwrap();
+
+        let store = ProfileStore::new(dir.clone(), Vec::new());
+        store
+            .update(|data| {
+                data.profiles.insert("work".to_string(), ProfileData { name: "work".to_string(), ..ProfileData::default() });
+                Ok(())
+            })
+            .unwrap();
+        assert!(store.load().unwrap().profiles.contains_key("work"));
+        assert!(!dir.join(LOCK_FILE).exists());
+
+        std::fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn leaves_the_lock_of_a_live_process_alone() {
+        let dir = std::env::temp_dir().join(format!("multicoder-profile-lock-{}", uuid::Uuid::new_v4()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let path = dir.join(LOCK_FILE);
+        let mut exited = std::process::Command::new("true").spawn().unwrap();
+        exited.wait().unwrap();
+
+        std::fs::write(&path, format!("{} other\n", std::process::id())).unwrap();
+        assert!(!abandoned(&path));
+        std::fs::write(&path, format!("{} other\n", exited.id())).unwrap();
+        assert!(abandoned(&path));
+
+        // A lock taken over in the meantime is not ours to release
+        let lock = StoreLock::acquire(&dir).unwrap();
+        std::fs::write(&path, "1 other\n").unwrap();
+        drop(lock);
+        assert!(path.exists());
+
+        std::fs::remove_dir_all(dir).unwrap();
+    }
+
+    /// The CLI's `ProfileStore` and the bridge's `ProfileManager` write profiles.json
+    /// from Node while the app updates it; needs `npm run build` at the repository root
+    #[test]
+    fn node_and_rust_writers_do_not_lose_each_others_changes() {
+        const WRITES: usize = 20;
+        let dist = Path::new(env!("CARGO_MANIFEST_DIR")).join("../../dist/profile");
+        if !dist.join("store.js").exists() || std::process::Command::new("node").arg("--version").output().is_err() {
+            eprintln!("skipping: node or {} is missing, run `npm run build`", dist.display());
+            return;
+        }
+
+        let home = std::env::temp_dir().join(format!("multicoder-profile-race-{}", uuid::Uuid::new_v4()));
+        let store = ProfileStore::new(home.join(".multicoder"), Vec::new());
+        let script = format!(
+            r#"
+            import {{ pathToFileURL }} from 'node:url';
+            const [dist, home, configDir] = process.argv.slice(1);
+            const {{ ProfileStore }} = await import(pathToFileURL(`${{dist}}/store.js`).href);
+            const {{ ProfileManager }} = await import(pathToFileURL(`${{dist}}/profileManager.js`).href);
+            const cli = new ProfileStore(home);
+            const bridge = new ProfileManager({{ configDir }});
+            await bridge.load();
+            for (let i = 0; i < {WRITES}; i++) {{
+              cli.create(`cli-${{i}}`);
+              bridge.ensure(`bridge-${{i}}`);
+              await bridge.save();
+            }}
+            "#
+        );
+        let mut node = std::process::Command::new("node")
+            .args(["--input-type=module", "-e", &script])
+            .arg(&dist)
+            .arg(&home)
+            .arg(store.dir())
+            .spawn()
+            .unwrap();
+
+        // Keep writing until node is done so the writers overlap however slowly node starts
+        let mut written = 0;
+        let status = loop {
+            if let Some(status) = node.try_wait().unwrap() {
+                break status;
+            }
+            store
+                .update(|data| {
+                    let name = format!("app-{}", written);
+                    data.profiles.insert(name.clone(), ProfileData { name, ..ProfileData::default() });
+                    Ok(())
+                })
+                .unwrap();
+            written += 1;
+            // Give node a chance at the lock between writes
+            std::thread::sleep(Duration::from_millis(2));
+        };
+        assert!(status.success());
+
+        let names: Vec<String> = store.load().unwrap().profiles.into_keys().collect();
+        let expected = (0..written)
+            .map(|i| format!("app-{}", i))
+            .chain((0..WRITES).flat_map(|i| [format!("cli-{}", i), format!("bridge-{}", i)]));
+        for name in expected {
+            assert!(names.contains(&name), "{} was lost", name);
+        }
+        assert!(!store.dir().join(LOCK_FILE).exists());
+
+        std::fs::remove_dir_all(home).unwrap();
+    }
+}
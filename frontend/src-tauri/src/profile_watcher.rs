@@ -0,0 +1,181 @@
+//! Watches `profiles.json` and the managed credentials for changes made outside
+//! the app, e.g. `coders profile switch` in a terminal, and tells the windows
+//! with a `profiles-changed` event.
+
+use notify::{Event, RecommendedWatcher, RecursiveMode, Watcher};
+use serde::Serialize;
+use std::path::Path;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use tauri::{AppHandle, Emitter, Manager, Runtime};
+use tokio::sync::mpsc;
+use tracing::{debug, error, warn};
+
+use crate::bridge::BridgeClient;
+use crate::commands::{profile_name, sync_current_profile};
+use crate::error::CommandError;
+use crate::profile_store::{ProfileStore, PROFILES_FILE};
+use crate::state::AppState;
+
+/// Quiet period after the last change before the windows are told; writers
+/// touch several files in quick succession
+const DEBOUNCE: Duration = Duration::from_millis(200);
+
+/// Payload of the `profiles-changed` event
+#[derive(Debug, Clone, Default, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ProfilesChangedEvent {
+    /// `profiles.json` changed
+    pub profiles: bool,
+    /// Something in the managed credentials directory changed
+    pub credentials: bool,
+}
+
+impl ProfilesChangedEvent {
+    fn merge(&mut self, other: ProfilesChangedEvent) {
+        self.profiles |= other.profiles;
+        self.credentials |= other.credentials;
+    }
+}
+
+/// Keeps the watch alive; dropping it stops watching
+pub struct ProfileWatcher {
+    _watcher: RecommendedWatcher,
+}
+
+impl ProfileWatcher {
+    /// Start watching the config directory of `store`, creating it and the credentials
+    /// directory if needed so they can be watched before the bridge first runs
+    pub fn start<R: Runtime>(app: AppHandle<R>, store: Arc<ProfileStore>) -> Result<Self, String> {
+        let credentials_dir = store.credentials_dir();
+        std::fs::create_dir_all(&credentials_dir)
+            .map_err(|e| format!("failed to create {}: {}", credentials_dir.display(), e))?;
+
+        let (tx, rx) = mpsc::unbounded_channel();
+        let classify_dir = store.dir().to_path_buf();
+        let classify_credentials = credentials_dir.clone();
+        let mut watcher = notify::recommended_watcher(move |event: notify::Result<Event>| match event {
+            Ok(event) => {
+                let change = classify(&event, &classify_dir, &classify_credentials);
+                if change != ProfilesChangedEvent::default() {
+                    let _ = tx.send(change);
+                }
+            }
+            Err(e) => warn!("profile watcher error: {}", e),
+        })
+        .map_err(|e| format!("failed to create profile watcher: {}", e))?;
+
+        // Non-recursive for the config directory: the store is replaced by renames,
+        // which only show up as events of the directory
+        watcher
+            .watch(store.dir(), RecursiveMode::NonRecursive)
+            .map_err(|e| format!("failed to watch {}: {}", store.dir().display(), e))?;
+        watcher
+            .watch(&credentials_dir, RecursiveMode::Recursive)
+            .map_err(|e| format!("failed to watch {}: {}", credentials_dir.display(), e))?;
+
+        tauri::async_runtime::spawn(Self::forward(app, store, rx));
+        Ok(Self { _watcher: watcher })
+    }
+
+    /// Coalesce bursts of changes and report each burst once
+    async fn forward<R: Runtime>(
+        app: AppHandle<R>,
+        store: Arc<ProfileStore>,
+        mut changes: mpsc::UnboundedReceiver<ProfilesChangedEvent>,
+    ) {
+        while let Some(mut change) = changes.recv().await {
+            loop {
+                match tokio::time::timeout(DEBOUNCE, changes.recv()).await {
+                    Ok(Some(more)) => change.merge(more),
+                    Ok(None) => return,
+                    Err(_) => break,
+                }
+            }
+
+            debug!(?change, "profiles changed on disk");
+            if change.profiles {
+                Self::sync_current(&app, &store).await;
+            }
+            if let Err(e) = app.emit("profiles-changed", &change) {
+                error!("failed to emit profiles-changed event: {}", e);
+            }
+        }
+    }
+
+    /// Let the bridge pick up the new store and follow its current profile
+    async fn sync_current<R: Runtime>(app: &AppHandle<R>, store: &Arc<ProfileStore>) {
+        let bridge = app.try_state::<Arc<BridgeClient>>().map(|state| state.inner().clone());
+        let current = match bridge {
+            Some(bridge) if bridge.is_alive() => match bridge.reload_profiles().await {
+                Ok(result) => profile_name(&result),
+                Err(e) => {
+                    warn!("bridge failed to reload profiles: {}", e);
+                    return;
+                }
+            },
+            _ => {
+                let store = store.clone();
+                let loaded = tauri::async_runtime::spawn_blocking(move || store.load())
+                    .await
+                    .map_err(|e| CommandError::Internal(e.to_string()))
+                    .and_then(|loaded| loaded);
+                match loaded {
+                    Ok(data) => data.current_profile,
+                    Err(e) => {
+                        warn!("failed to read changed profiles: {}", e);
+                        return;
+                    }
+                }
+            }
+        };
+        sync_current_profile(app, &app.state::<Mutex<AppState>>(), current);
+    }
+}
+
+/// What part of the profile data `event` touched; the lock and temporary files
+/// written next to `profiles.json` are ignored
+fn classify(event: &Event, dir: &Path, credentials_dir: &Path) -> ProfilesChangedEvent {
+    let mut change = ProfilesChangedEvent::default();
+    if event.kind.is_access() {
+        return change;
+    }
+    for path in &event.paths {
+        if path.starts_with(credentials_dir) {
+            change.credentials = true;
+        } else if path.parent() == Some(dir) && path.file_name().is_some_and(|name| name == PROFILES_FILE) {
+            change.profiles = true;
+        }
+    }
+    change
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use notify::event::{CreateKind, EventKind, ModifyKind, RenameMode};
+    use std::path::PathBuf;
+
+    fn event(kind: EventKind, paths: &[&str]) -> Event {
+        paths.iter().fold(Event::new(kind), |event, path| event.add_path(PathBuf::from(path)))
+    }
+
+    #[test]
+    fn reports_the_store_and_credentials_but_not_lock_or_temporary_files() {
+        let (dir, credentials) = (Path::new("/cfg"), Path::new("/cfg/credentials"));
+        let rename = EventKind::Modify(ModifyKind::Name(RenameMode::Both));
+
+        let replaced = classify(&event(rename, &["/cfg/profiles.json.42.tmp", "/cfg/profiles.json"]), dir, credentials);
+        assert_eq!(replaced, ProfilesChangedEvent { profiles: true, credentials: false });
+
+        let locked = classify(&event(EventKind::Create(CreateKind::File), &["/cfg/profiles.json.lock"]), dir, credentials);
+        assert_eq!(locked, ProfilesChangedEvent::default());
+
+        let login = classify(
+            &event(EventKind::Create(CreateKind::File), &["/cfg/credentials/work/claude.json"]),
+            dir,
+            credentials,
+        );
+        assert_eq!(login, ProfilesChangedEvent { profiles: false, credentials: true });
+    }
+}
@@ -0,0 +1,160 @@
+//! Masks credentials in bridge traffic before it is logged or recorded.
+//!
+//! JSON is redacted structurally: values of secret fields are replaced
+//! wherever they occur. Plain text, such as the bridge's stderr, is redacted
+//! with patterns for `field: value` pairs. Both also mask values that look
+//! like provider API keys regardless of where they appear.
+
+use regex::Regex;
+use std::sync::LazyLock;
+
+/// Replacement for masked values
+pub const MASK: &str = "[REDACTED]";
+
+/// Fields whose values are always masked; matched ignoring case, `_` and `-`
+const SECRET_FIELDS: &[&str] = &["apiKey", "token", "access_token", "refresh_token", "id_token", "client_secret"];
+
+/// Values that are credentials wherever they show up: OpenAI/Anthropic style
+/// `sk-…` keys and Google `AIza…` keys
+static SECRET_VALUE: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r"\bsk-[A-Za-z0-9_\-]{8,}|\bAIza[0-9A-Za-z_\-]{20,}").unwrap());
+
+/// `apiKey: value`, `"token":"value"`, `client_secret=value` and similar in free
+/// text; the names are `SECRET_FIELDS` in camelCase, snake_case or kebab-case
+static SECRET_FIELD_TEXT: LazyLock<Regex> = LazyLock::new(|| {
+    Regex::new(
+        r#"(?i)(\b(?:api[_\-]?key|access[_\-]?token|refresh[_\-]?token|id[_\-]?token|client[_\-]?secret|token)"?\s*[:=]\s*"?)([^"\s,;&}]+)"#,
+    )
+    .unwrap()
+});
+
+fn normalize(field: &str) -> String {
+    field
+        .chars()
+        .filter(|c| *c != '_' && *c != '-')
+        .flat_map(char::to_lowercase)
+        .collect()
+}
+
+/// Whether values of `field` must be masked
+pub fn is_secret_field(field: &str) -> bool {
+    let field = normalize(field);
+    SECRET_FIELDS.iter().any(|secret| normalize(secret) == field)
+}
+
+/// Copy of `value` with secret fields and secret-looking strings masked
+pub fn redact_value(value: &serde_json::Value) -> serde_json::Value {
+    match value {
+        serde_json::Value::Object(map) => map
+            .iter()
+            .map(|(key, value)| {
+                let value = if is_secret_field(key) && !value.is_null() {
+                    serde_json::Value::String(MASK.to_string())
+                } else {
+                    redact_value(value)
+                };
+                (key.clone(), value)
+            })
+            .collect(),
+        serde_json::Value::Array(items) => items.iter().map(redact_value).collect(),
+        serde_json::Value::String(text) => serde_json::Value::String(redact_text(text)),
+        other => other.clone(),
+    }
+}
+
+/// Redact a serialized JSON message; falls back to text redaction if it does not parse
+pub fn redact_json(json: &str) -> String {
+    match serde_json::from_str::<serde_json::Value>(json) {
+        Ok(value) => redact_value(&value).to_string(),
+        Err(_) => redact_text(json),
+    }
+}
+
+/// Mask secret `field: value` pairs and secret-looking values in free text
+pub fn redact_text(text: &str) -> String {
+    let text = SECRET_FIELD_TEXT.replace_all(text, format!("${{1}}{}", MASK));
+    SECRET_VALUE.replace_all(&text, MASK).into_owned()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn masks_secret_fields_at_any_depth() {
+        let request = json!({
+            "jsonrpc": "2.0",
+            "id": 4,
+            "method": "loginWithApiKey",
+            "params": {
+                "profileName": "work",
+                "apiKey": "plain-key-without-prefix",
+                "metadata": { "oauth": [{ "access_token": "a", "refresh_token": "r", "id_token": "i" }] },
+                "client_secret": "s",
+                "token": "t",
+            },
+        });
+
+        assert_eq!(
+            redact_value(&request),
+            json!({
+                "jsonrpc": "2.0",
+                "id": 4,
+                "method": "loginWithApiKey",
+                "params": {
+                    "profileName": "work",
+                    "apiKey": MASK,
+                    "metadata": { "oauth": [{ "access_token": MASK, "refresh_token": MASK, "id_token": MASK }] },
+                    "client_secret": MASK,
+                    "token": MASK,
+                },
+            })
+        );
+    }
+
+    #[test]
+    fn matches_field_names_in_any_spelling() {
+        for field in ["apiKey", "api_key", "API-KEY", "accessToken", "ACCESS_TOKEN", "clientSecret", "idToken"] {
+            assert!(is_secret_field(field), "{} should be secret", field);
+        }
+        for field in ["tokens", "maxTokens", "tokenCount", "profileName", "key"] {
+            assert!(!is_secret_field(field), "{} should not be secret", field);
+        }
+    }
+
+    #[test]
+    fn masks_secret_looking_values_in_other_fields() {
+        let value = json!({
+            "message": "use sk-ant-REDACTED for this",
+            "env": ["GEMINI_API_KEY=AIzaSyA1234567890abcdefghijklmno"],
+            "count": 3,
+        });
+        let redacted = redact_value(&value).to_string();
+
+        assert!(!redacted.contains("sk-ant-REDACTED"));
+        assert!(!redacted.contains("AIzaSyA1234567890abcdefghijklmno"));
+        assert!(redacted.contains("use [REDACTED] for this"));
+        assert!(redacted.contains(r#""count":3"#));
+    }
+
+    #[test]
+    fn redacts_free_text() {
+        let cases = [
+            (r#"{"apiKey":"hunter2","name":"work"}"#, r#"{"apiKey":"[REDACTED]","name":"work"}"#),
+            ("refresh_token=abc123&grant_type=refresh", "refresh_token=[REDACTED]&grant_type=refresh"),
+            ("Authorization failed for token: xyz", "Authorization failed for token: [REDACTED]"),
+            ("key sk-proj-0123456789abcdef rejected", "key [REDACTED] rejected"),
+            ("used 512 tokens", "used 512 tokens"),
+        ];
+        for (text, expected) in cases {
+            assert_eq!(redact_text(text), expected);
+        }
+    }
+
+    #[test]
+    fn redacts_frames_that_are_not_json() {
+        let frame = r#"{"apiKey": "hunter2", "truncated"#;
+        assert_eq!(redact_json(frame), r#"{"apiKey": "[REDACTED]", "truncated"#);
+    }
+}
@@ -0,0 +1,159 @@
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::sync::Mutex;
+
+/// What a chat session is currently doing
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum SessionState {
+    /// Launched and waiting for the next message
+    Idle,
+    /// A reply is being generated
+    Running,
+    /// Stop was requested for the running turn
+    Stopping,
+}
+
+/// A provider chat session running in the bridge
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Session {
+    pub id: String,
+    pub profile: String,
+    pub provider: String,
+    pub working_dir: String,
+    pub permission_mode: String,
+    /// Milliseconds since the Unix epoch
+    pub started_at: u64,
+    pub state: SessionState,
+}
+
+/// Session as reported by the bridge's `launch` method
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct SessionSummary {
+    session_id: String,
+    profile: String,
+    provider: String,
+    working_dir: String,
+    permission_mode: String,
+    started_at: u64,
+    #[serde(default)]
+    running: bool,
+}
+
+impl Session {
+    /// Build a session from the result of the bridge's `launch` method
+    pub fn from_launch_result(result: &serde_json::Value) -> Result<Self, String> {
+        let summary = result.get("session").cloned().unwrap_or(serde_json::Value::Null);
+        let summary: SessionSummary = serde_json::from_value(summary)
+            .map_err(|e| format!("Invalid launch result from bridge: {}", e))?;
+
+        Ok(Self {
+            id: summary.session_id,
+            profile: summary.profile,
+            provider: summary.provider,
+            working_dir: summary.working_dir,
+            permission_mode: summary.permission_mode,
+            started_at: summary.started_at,
+            state: if summary.running { SessionState::Running } else { SessionState::Idle },
+        })
+    }
+}
+
+/// Payload of the `session-closed` event
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SessionClosedEvent {
+    pub session: Session,
+    pub reason: String,
+}
+
+/// Tracks the chat sessions the bridge is running, keyed by session id
+#[derive(Debug, Default)]
+pub struct SessionManager {
+    sessions: Mutex<HashMap<String, Session>>,
+}
+
+impl SessionManager {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Record a session. The bridge keeps one session per profile, so any
+    /// other session of the same profile is replaced and returned.
+    pub fn upsert(&self, session: Session) -> Vec<Session> {
+        let mut sessions = self.sessions.lock().unwrap();
+        let replaced: Vec<String> = sessions
+            .values()
+            .filter(|existing| existing.profile == session.profile && existing.id != session.id)
+            .map(|existing| existing.id.clone())
+            .collect();
+        let replaced = replaced
+            .iter()
+            .filter_map(|id| sessions.remove(id))
+            .collect();
+        sessions.insert(session.id.clone(), session);
+        replaced
+    }
+
+    /// Session of `profile` running `provider`, if any
+    pub fn find(&self, profile: &str, provider: &str) -> Option<Session> {
+        self.sessions
+            .lock()
+            .unwrap()
+            .values()
+            .find(|session| session.profile == profile && session.provider == provider)
+            .cloned()
+    }
+
+    /// Session of `profile`, whatever its provider
+    pub fn find_by_profile(&self, profile: &str) -> Option<Session> {
+        self.sessions
+            .lock()
+            .unwrap()
+            .values()
+            .find(|session| session.profile == profile)
+            .cloned()
+    }
+
+    pub fn get(&self, session_id: &str) -> Option<Session> {
+        self.sessions.lock().unwrap().get(session_id).cloned()
+    }
+
+    /// All sessions, oldest first
+    pub fn list(&self) -> Vec<Session> {
+        let mut sessions: Vec<Session> = self.sessions.lock().unwrap().values().cloned().collect();
+        sessions.sort_by_key(|session| session.started_at);
+        sessions
+    }
+
+    /// Update the state of a session; returns false if it no longer exists
+    pub fn set_state(&self, session_id: &str, state: SessionState) -> bool {
+        match self.sessions.lock().unwrap().get_mut(session_id) {
+            Some(session) => {
+                session.state = state;
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// Record the permission mode the profile's session now runs with
+    pub fn set_permission_mode(&self, profile: &str, mode: &str) {
+        for session in self.sessions.lock().unwrap().values_mut() {
+            if session.profile == profile {
+                session.permission_mode = mode.to_string();
+            }
+        }
+    }
+
+    pub fn remove(&self, session_id: &str) -> Option<Session> {
+        self.sessions.lock().unwrap().remove(session_id)
+    }
+
+    /// Forget every session, e.g. because the bridge process exited
+    pub fn clear(&self) -> Vec<Session> {
+        self.sessions.lock().unwrap().drain().map(|(_, session)| session).collect()
+    }
+}
@@ -1,6 +1,9 @@
+use serde::Serialize;
+
 /// Application state shared across commands
 #[derive(Debug, Default)]
 pub struct AppState {
+    /// Profile the bridge currently has selected, kept in sync by the profile commands
     pub current_profile_id: Option<String>,
 }
 
@@ -10,4 +13,20 @@ impl AppState {
             current_profile_id: None,
         }
     }
+
+    /// Record the current profile; returns true if it changed
+    pub fn set_current_profile(&mut self, profile_id: Option<String>) -> bool {
+        if self.current_profile_id == profile_id {
+            return false;
+        }
+        self.current_profile_id = profile_id;
+        true
+    }
+}
+
+/// Payload of the `profile-changed` event
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ProfileChangedEvent {
+    pub profile_id: Option<String>,
 }
@@ -0,0 +1,485 @@
+use std::future::Future;
+use std::net::SocketAddr;
+use std::path::PathBuf;
+use std::pin::Pin;
+use std::process::Stdio;
+use std::collections::VecDeque;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use tokio::io::{AsyncBufReadExt, AsyncRead, AsyncWrite, BufReader};
+use tokio::process::{Child, Command};
+use tracing::{debug, error, info, warn};
+
+use crate::config::BridgeConfig;
+use crate::environment::BridgeEnv;
+use crate::framing::read_frame;
+use crate::node::{NodeResolver, NodeRuntime};
+use crate::process::{self, PidFile};
+use crate::redact::redact_text;
+
+/// Environment variable selecting the transport; overrides `config.json`
+pub const TRANSPORT_ENV: &str = "MULTICODER_BRIDGE_TRANSPORT";
+
+pub type BridgeReader = Box<dyn AsyncRead + Send + Unpin>;
+pub type BridgeWriter = Box<dyn AsyncWrite + Send + Unpin>;
+pub type BoxFuture<'a, T> = Pin<Box<dyn Future<Output = T> + Send + 'a>>;
+
+/// An open connection to a bridge, carrying length-prefixed JSON-RPC frames
+pub struct BridgeConnection {
+    pub reader: BridgeReader,
+    pub writer: BridgeWriter,
+    /// Bridge process started for this connection; `None` when attached to a
+    /// bridge running on its own, which is then left running on shutdown
+    pub child: Option<Child>,
+}
+
+/// A way of reaching the bridge. `BridgeClient` calls `connect` again each
+/// time the previous connection is lost.
+pub trait BridgeTransport: Send + Sync {
+    /// Short description for logs and status events
+    fn describe(&self) -> String;
+
+    fn connect(&self) -> BoxFuture<'_, Result<BridgeConnection, String>>;
+
+    /// Last lines the bridge wrote to stderr, oldest first; empty when the
+    /// transport does not see the bridge's output
+    fn recent_stderr(&self) -> Vec<String> {
+        Vec::new()
+    }
+
+    /// Called once a connection has ended and its process, if any, has exited or
+    /// was killed, and after a failed `connect`
+    fn disconnected(&self) {}
+}
+
+// ============================================================================
+// Transport Selection
+// ============================================================================
+
+/// Parsed transport setting
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum TransportSpec {
+    /// Spawn the bridge as a child process and talk over its stdin/stdout
+    Stdio,
+    /// Attach to a bridge listening on a Unix domain socket
+    Unix(PathBuf),
+    /// Attach to a bridge listening on a localhost TCP port
+    Tcp(SocketAddr),
+}
+
+impl std::str::FromStr for TransportSpec {
+    type Err = String;
+
+    fn from_str(spec: &str) -> Result<Self, Self::Err> {
+        let spec = spec.trim();
+        if spec.is_empty() || spec == "stdio" {
+            return Ok(TransportSpec::Stdio);
+        }
+
+        if let Some(path) = spec.strip_prefix("unix:") {
+            if path.is_empty() {
+                return Err("unix transport needs a socket path, e.g. unix:/tmp/multicoder-bridge.sock".to_string());
+            }
+            return Ok(TransportSpec::Unix(PathBuf::from(path)));
+        }
+
+        if let Some(addr) = spec.strip_prefix("tcp:") {
+            let addr: SocketAddr = addr
+                .parse()
+                .map_err(|e| format!("Invalid tcp transport address {}: {}", addr, e))?;
+            // The protocol is unauthenticated; never talk to a bridge on another host
+            if !addr.ip().is_loopback() {
+                return Err(format!("tcp transport must use a loopback address, got {}", addr.ip()));
+            }
+            return Ok(TransportSpec::Tcp(addr));
+        }
+
+        Err(format!(
+            "Unknown bridge transport '{}' (expected stdio, unix:<path> or tcp:<host>:<port>)",
+            spec
+        ))
+    }
+}
+
+impl TransportSpec {
+    /// Transport chosen by `MULTICODER_BRIDGE_TRANSPORT`, then by `config.json`, defaulting to stdio
+    pub fn select(config: &BridgeConfig) -> Result<Self, String> {
+        match std::env::var(TRANSPORT_ENV) {
+            Ok(spec) if !spec.trim().is_empty() => spec.parse(),
+            _ => config.transport.as_deref().unwrap_or("stdio").parse(),
+        }
+    }
+}
+
+// ============================================================================
+// Stdio Transport
+// ============================================================================
+
+/// Environment variable carrying the token the spawned bridge presents on its channel
+const CHANNEL_TOKEN_ENV: &str = "MULTICODER_BRIDGE_TOKEN";
+
+/// How long the spawned bridge may take to connect to its channel
+const CHANNEL_CONNECT_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// Lines of the bridge's stderr kept for `bridge_health`
+const STDERR_TAIL_LINES: usize = 50;
+
+/// Private endpoint the spawned bridge connects back to. Protocol traffic
+/// goes over it instead of stdout, so output of provider libraries can't
+/// corrupt it.
+enum ChannelListener {
+    /// Socket in a directory only the current user can access
+    #[cfg(unix)]
+    Unix {
+        listener: tokio::net::UnixListener,
+        dir: PathBuf,
+        path: PathBuf,
+    },
+    Tcp(tokio::net::TcpListener),
+}
+
+impl ChannelListener {
+    #[cfg(unix)]
+    async fn bind() -> Result<Self, String> {
+        use std::os::unix::fs::DirBuilderExt;
+
+        // Keep the path short; socket paths are limited to about 100 bytes
+        let id = uuid::Uuid::new_v4().simple().to_string();
+        let dir = std::env::temp_dir().join(format!("multicoder-{}", &id[..12]));
+        std::fs::DirBuilder::new()
+            .mode(0o700)
+            .create(&dir)
+            .map_err(|e| format!("Failed to create bridge channel directory {}: {}", dir.display(), e))?;
+
+        let path = dir.join("bridge.sock");
+        match tokio::net::UnixListener::bind(&path) {
+            Ok(listener) => Ok(ChannelListener::Unix { listener, dir, path }),
+            Err(e) => {
+                let _ = std::fs::remove_dir_all(&dir);
+                Err(format!("Failed to bind bridge channel {}: {}", path.display(), e))
+            }
+        }
+    }
+
+    #[cfg(not(unix))]
+    async fn bind() -> Result<Self, String> {
+        tokio::net::TcpListener::bind(("127.0.0.1", 0))
+            .await
+            .map(ChannelListener::Tcp)
+            .map_err(|e| format!("Failed to bind bridge channel: {}", e))
+    }
+
+    /// Address passed to the bridge's `--channel` option
+    fn spec(&self) -> Result<String, String> {
+        match self {
+            #[cfg(unix)]
+            ChannelListener::Unix { path, .. } => Ok(format!("unix:{}", path.display())),
+            ChannelListener::Tcp(listener) => listener
+                .local_addr()
+                .map(|addr| format!("tcp:{}", addr))
+                .map_err(|e| format!("Failed to read bridge channel address: {}", e)),
+        }
+    }
+
+    /// Accept the bridge's connection and check that it presents `token` as its first frame
+    async fn accept(&self, token: &str) -> Result<(BridgeReader, BridgeWriter), String> {
+        let (mut reader, writer): (BridgeReader, BridgeWriter) = match self {
+            #[cfg(unix)]
+            ChannelListener::Unix { listener, .. } => {
+                let (stream, _) = listener
+                    .accept()
+                    .await
+                    .map_err(|e| format!("Failed to accept bridge channel: {}", e))?;
+                let (reader, writer) = stream.into_split();
+                (Box::new(reader), Box::new(writer))
+            }
+            ChannelListener::Tcp(listener) => {
+                let (stream, _) = listener
+                    .accept()
+                    .await
+                    .map_err(|e| format!("Failed to accept bridge channel: {}", e))?;
+                stream.set_nodelay(true).ok();
+                let (reader, writer) = stream.into_split();
+                (Box::new(reader), Box::new(writer))
+            }
+        };
+
+        let presented = read_frame(&mut reader)
+            .await
+            .map_err(|e| format!("Failed to read bridge channel token: {}", e))?;
+        if presented.as_deref() != Some(token.as_bytes()) {
+            return Err("Bridge channel was opened with an invalid token".to_string());
+        }
+
+        Ok((reader, writer))
+    }
+}
+
+#[cfg(unix)]
+impl Drop for ChannelListener {
+    fn drop(&mut self) {
+        // Connections stay open after the socket file is gone
+        if let ChannelListener::Unix { dir, .. } = self {
+            let _ = std::fs::remove_dir_all(dir);
+        }
+    }
+}
+
+/// Spawns `node <bridge_path> --channel <address>` and talks over the channel
+/// the bridge connects to. Its stdout and stderr are forwarded to our log.
+pub struct StdioTransport {
+    bridge_path: PathBuf,
+    resolver: NodeResolver,
+    /// Node found by the last successful resolve, reused for restarts
+    node: Mutex<Option<NodeRuntime>>,
+    /// Tail of the bridge's stderr, kept across restarts to explain crashes
+    stderr_tail: Arc<Mutex<VecDeque<String>>>,
+    /// Records the running bridge so a crashed app's bridge can be cleaned up
+    pidfile: Option<PidFile>,
+    /// Process group of the last bridge started, led by the bridge itself
+    group: Mutex<Option<u32>>,
+    /// The bridge's whole environment, apart from the channel token
+    env: BridgeEnv,
+}
+
+impl StdioTransport {
+    pub fn new(bridge_path: PathBuf, resolver: NodeResolver, pidfile: Option<PidFile>, env: BridgeEnv) -> Self {
+        Self {
+            bridge_path,
+            resolver,
+            node: Mutex::new(None),
+            stderr_tail: Arc::new(Mutex::new(VecDeque::with_capacity(STDERR_TAIL_LINES))),
+            pidfile,
+            group: Mutex::new(None),
+            env,
+        }
+    }
+
+    async fn node(&self) -> Result<NodeRuntime, String> {
+        if let Some(node) = self.node.lock().unwrap().clone() {
+            return Ok(node);
+        }
+
+        let node = self.resolver.resolve().await.map_err(|e| e.to_string())?;
+        *self.node.lock().unwrap() = Some(node.clone());
+        Ok(node)
+    }
+
+    /// Forward one of the bridge's output streams to our log, under the `bridge` target,
+    /// remembering the last lines in `tail` when given
+    async fn forward_output(
+        output: impl AsyncRead + Unpin,
+        name: &'static str,
+        tail: Option<Arc<Mutex<VecDeque<String>>>>,
+    ) {
+        let mut lines = BufReader::new(output).lines();
+
+        debug!("{} reader task started", name);
+
+        loop {
+            match lines.next_line().await {
+                Ok(Some(line)) if !line.trim().is_empty() => {
+                    let line = redact_text(&line);
+                    info!(target: "bridge", "[Bridge {}] {}", name, line);
+                    if let Some(tail) = &tail {
+                        let mut tail = tail.lock().unwrap();
+                        if tail.len() == STDERR_TAIL_LINES {
+                            tail.pop_front();
+                        }
+                        tail.push_back(line);
+                    }
+                }
+                Ok(Some(_)) => {
+                    // Empty line, continue
+                }
+                Ok(None) => break,
+                Err(e) => {
+                    error!("failed to read line from bridge {}: {}", name, e);
+                    break;
+                }
+            }
+        }
+
+        debug!("{} reader task exiting", name);
+    }
+}
+
+impl BridgeTransport for StdioTransport {
+    fn describe(&self) -> String {
+        format!("stdio ({})", self.bridge_path.display())
+    }
+
+    fn connect(&self) -> BoxFuture<'_, Result<BridgeConnection, String>> {
+        Box::pin(async move {
+            if let Some(pidfile) = &self.pidfile {
+                pidfile.reap_stale().await;
+            }
+
+            let node = self.node().await?;
+
+            // Use user's home directory as working directory
+            // This ensures bridge can access native CLI tools and profile configurations
+            // regardless of where the app is installed
+            let working_dir = dirs::home_dir()
+                .ok_or("Failed to determine user home directory")?;
+
+            debug!("bridge working directory: {:?}", working_dir);
+
+            let channel = ChannelListener::bind().await?;
+            let token = uuid::Uuid::new_v4().to_string();
+
+            // Spawn the Node.js bridge service; it connects back to the channel
+            // and anything it prints ends up in our log
+            let mut command = Command::new(&node.path);
+            self.env.configure(&mut command);
+            command
+                .arg(&self.bridge_path)
+                .arg("--channel")
+                .arg(channel.spec()?)
+                .env(CHANNEL_TOKEN_ENV, &token)
+                .current_dir(working_dir)
+                .stdin(Stdio::null())
+                .stdout(Stdio::piped())
+                .stderr(Stdio::piped())
+                .kill_on_drop(true);
+
+            // On Windows, hide the console window
+            #[cfg(target_os = "windows")]
+            {
+                const CREATE_NO_WINDOW: u32 = 0x08000000;
+                command.creation_flags(CREATE_NO_WINDOW);
+            }
+
+            process::isolate(&mut command);
+
+            let mut child = command.spawn().map_err(|e| {
+                // Look for node again next time, it may have been removed or upgraded
+                self.node.lock().unwrap().take();
+                format!("Failed to spawn bridge service with {}: {}", node.path.display(), e)
+            })?;
+
+            let stdout = child.stdout.take().ok_or("Failed to get stdout")?;
+            let stderr = child.stderr.take().ok_or("Failed to get stderr")?;
+
+            info!("bridge process spawned with pid {:?}", child.id());
+            if let Some(pid) = child.id() {
+                *self.group.lock().unwrap() = Some(pid);
+                if let Some(pidfile) = &self.pidfile {
+                    pidfile.record(pid, &self.bridge_path);
+                }
+            }
+
+            tauri::async_runtime::spawn(Self::forward_output(stdout, "stdout", None));
+            tauri::async_runtime::spawn(Self::forward_output(stderr, "stderr", Some(Arc::clone(&self.stderr_tail))));
+
+            // Dropping `child` on failure kills the process
+            let (reader, writer) = tokio::select! {
+                accepted = tokio::time::timeout(CHANNEL_CONNECT_TIMEOUT, channel.accept(&token)) => match accepted {
+                    Ok(accepted) => accepted?,
+                    Err(_) => {
+                        return Err(format!(
+                            "Bridge did not connect to its channel within {} seconds; it may be outdated, try rebuilding it",
+                            CHANNEL_CONNECT_TIMEOUT.as_secs()
+                        ))
+                    }
+                },
+                status = child.wait() => {
+                    let status = status.map(|status| status.to_string()).unwrap_or_else(|e| e.to_string());
+                    return Err(format!("Bridge exited with {} before connecting to its channel", status));
+                }
+            };
+
+            Ok(BridgeConnection {
+                reader,
+                writer,
+                child: Some(child),
+            })
+        })
+    }
+
+    fn disconnected(&self) {
+        let Some(group) = self.group.lock().unwrap().take() else {
+            return;
+        };
+        // Provider CLIs the bridge could not stop, or all of them if it crashed
+        #[cfg(unix)]
+        if process::signal_group(group, libc::SIGKILL) {
+            warn!("killed processes left behind by bridge {}", group);
+        }
+        #[cfg(not(unix))]
+        let _ = group;
+        if let Some(pidfile) = &self.pidfile {
+            pidfile.clear();
+        }
+    }
+}
+
+// ============================================================================
+// Socket Transports
+// ============================================================================
+
+/// Attaches to a bridge started with `--listen unix:<path>`
+#[cfg(unix)]
+pub struct UnixSocketTransport {
+    path: PathBuf,
+}
+
+#[cfg(unix)]
+impl UnixSocketTransport {
+    pub fn new(path: PathBuf) -> Self {
+        Self { path }
+    }
+}
+
+#[cfg(unix)]
+impl BridgeTransport for UnixSocketTransport {
+    fn describe(&self) -> String {
+        format!("unix socket {}", self.path.display())
+    }
+
+    fn connect(&self) -> BoxFuture<'_, Result<BridgeConnection, String>> {
+        Box::pin(async move {
+            let stream = tokio::net::UnixStream::connect(&self.path)
+                .await
+                .map_err(|e| format!("Failed to connect to bridge at {}: {}", self.path.display(), e))?;
+            let (reader, writer) = stream.into_split();
+            Ok(BridgeConnection {
+                reader: Box::new(reader),
+                writer: Box::new(writer),
+                child: None,
+            })
+        })
+    }
+}
+
+/// Attaches to a bridge started with `--listen tcp:<host>:<port>`
+pub struct TcpTransport {
+    addr: SocketAddr,
+}
+
+impl TcpTransport {
+    pub fn new(addr: SocketAddr) -> Self {
+        Self { addr }
+    }
+}
+
+impl BridgeTransport for TcpTransport {
+    fn describe(&self) -> String {
+        format!("tcp {}", self.addr)
+    }
+
+    fn connect(&self) -> BoxFuture<'_, Result<BridgeConnection, String>> {
+        Box::pin(async move {
+            let stream = tokio::net::TcpStream::connect(self.addr)
+                .await
+                .map_err(|e| format!("Failed to connect to bridge at {}: {}", self.addr, e))?;
+            stream.set_nodelay(true).ok();
+            let (reader, writer) = stream.into_split();
+            Ok(BridgeConnection {
+                reader: Box::new(reader),
+                writer: Box::new(writer),
+                child: None,
+            })
+        })
+    }
+}
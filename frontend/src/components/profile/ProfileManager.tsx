@@ -24,6 +24,7 @@ import {
   loginWithApiKey,
   triggerProviderLogin,
   getCurrentProfile,
+  onProfilesChanged,
   getAuthOptions as fetchAuthOptions,
   linkExistingCredential,
   type AuthOptions as ServiceAuthOptions,
@@ -126,6 +127,28 @@ export function ProfileManager({ onClose }: ProfileManagerProps) {
     // eslint-disable-next-line react-hooks/exhaustive-deps
   }, []);
 
+  // Follow changes made outside the app, e.g. `coders profile switch` in a terminal
+  useEffect(() => {
+    let unlisten: (() => void) | null = null;
+    let isMounted = true;
+
+    onProfilesChanged(() => {
+      loadProfiles();
+    }).then((fn) => {
+      if (isMounted) {
+        unlisten = fn;
+      } else {
+        fn();
+      }
+    });
+
+    return () => {
+      isMounted = false;
+      unlisten?.();
+    };
+    // eslint-disable-next-line react-hooks/exhaustive-deps
+  }, []);
+
   const loadProfiles = async () => {
     try {
       const { profile: currentProfile } = await getCurrentProfile();
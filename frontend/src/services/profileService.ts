@@ -1,5 +1,15 @@
 import { invoke } from '@tauri-apps/api/core';
-import type { Profile, ProviderId, Model } from '@/types';
+import { listen, type UnlistenFn } from '@tauri-apps/api/event';
+import type {
+  Profile,
+  ProviderId,
+  Model,
+  ProfileChangedEvent,
+  ProfilesChangedEvent,
+  ImportConflict,
+  ExportSummary,
+  ImportSummary,
+} from '@/types';
 
 export interface CreateProfileParams {
   name: string;
@@ -55,6 +65,24 @@ export async function getCurrentProfile(): Promise<{ profile: Profile | null }>
   return await invoke('get_current_profile');
 }
 
+/**
+ * Subscribe to current profile changes made from any window
+ */
+export async function onProfileChanged(
+  handler: (event: ProfileChangedEvent) => void
+): Promise<UnlistenFn> {
+  return await listen<ProfileChangedEvent>('profile-changed', (event) => handler(event.payload));
+}
+
+/**
+ * Subscribe to profile changes made outside the app, e.g. by the CLI
+ */
+export async function onProfilesChanged(
+  handler: (event: ProfilesChangedEvent) => void
+): Promise<UnlistenFn> {
+  return await listen<ProfilesChangedEvent>('profiles-changed', (event) => handler(event.payload));
+}
+
 /**
  * Login with API key
  */
@@ -67,6 +95,29 @@ export async function loginWithApiKey(
   return await invoke('login_with_api_key', { profileName, provider, apiKey, metadata });
 }
 
+/**
+ * Export profiles and their managed credentials to an encrypted bundle
+ * (all profiles when `names` is empty)
+ */
+export async function exportProfiles(
+  names: string[],
+  passphrase: string,
+  path: string
+): Promise<ExportSummary> {
+  return await invoke('export_profiles', { names, passphrase, path });
+}
+
+/**
+ * Import the profiles of an encrypted bundle
+ */
+export async function importProfiles(
+  path: string,
+  passphrase: string,
+  onConflict: ImportConflict
+): Promise<ImportSummary> {
+  return await invoke('import_profiles', { path, passphrase, onConflict });
+}
+
 /**
  * Check if a provider is logged in
  */
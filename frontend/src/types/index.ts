@@ -29,7 +29,7 @@ export interface Profile {
 // Permission Types
 export type PermissionMode = 'ask' | 'allow' | 'deny';
 
-export type ActionType = 'file_write' | 'file_delete' | 'shell_exec';
+export type ActionType = 'file_write' | 'file_delete' | 'shell_exec' | 'tool_use';
 
 export interface PermissionRequest {
   id: string;
@@ -41,6 +41,31 @@ export interface PermissionRequest {
   timestamp: Date;
 }
 
+// Permission request waiting for approve_action / reject_action
+// (mirrors PendingAction in src-tauri/src/permissions.rs)
+export interface PendingAction {
+  id: string;
+  sessionId: string;
+  profile: string;
+  provider: string;
+  action: ActionType;
+  reason: string;
+  command?: string;
+  path?: string;
+  requestedAt: number;
+}
+
+// Entry returned by get_logs (mirrors LogEntry in src-tauri/src/logging.rs)
+export type LogLevel = 'error' | 'warn' | 'info' | 'debug' | 'trace';
+
+export interface LogEntry {
+  timestamp: number;
+  level: LogLevel;
+  target: string;
+  message: string;
+  spans: string[];
+}
+
 // Message Types
 export type MessageRole = 'user' | 'assistant' | 'system';
 
@@ -67,11 +92,116 @@ export type ProviderEvent =
   | { type: 'text'; content: string }
   | { type: 'file'; path: string; contents: string }
   | { type: 'shell'; command: string }
-  | { type: 'ask'; reason: string; action: ActionType }
+  | { type: 'ask'; id: string; reason: string; action: ActionType; command?: string; path?: string }
   | { type: 'progress'; message: string }
   | { type: 'error'; message: string; recoverable?: boolean }
   | { type: 'done' };
 
+// Payload of the `message-stream` event
+export interface MessageStreamEvent {
+  profile: string;
+  provider: string;
+  sessionId: string;
+  event: ProviderEvent;
+}
+
+export interface ProfileChangedEvent {
+  profileId: string | null;
+}
+
+// Payload of the `profiles-changed` event, sent when profiles.json or the
+// managed credentials change on disk
+export interface ProfilesChangedEvent {
+  profiles: boolean;
+  credentials: boolean;
+}
+
+// What to do with a bundled profile whose name is already taken
+export type ImportConflict = 'skip' | 'overwrite' | 'rename';
+
+export interface ExportSummary {
+  path: string;
+  profiles: string[];
+  credentials: number;
+}
+
+export interface ImportSummary {
+  imported: string[];
+  overwritten: string[];
+  renamed: { from: string; to: string }[];
+  skipped: string[];
+}
+
+export type SessionState = 'idle' | 'running' | 'stopping';
+
+export interface Session {
+  id: string;
+  profile: string;
+  provider: string;
+  workingDir: string;
+  permissionMode: string;
+  startedAt: number;
+  state: SessionState;
+}
+
+export interface SessionClosedEvent {
+  session: Session;
+  reason: string;
+}
+
+// Bridge Types
+export type BridgeStatus = 'starting' | 'ready' | 'crashed' | 'restarting' | 'incompatible' | 'unhealthy';
+
+export interface BridgeStatusEvent {
+  status: BridgeStatus;
+  attempt: number;
+  message?: string;
+}
+
+// Returned by bridge_health (mirrors BridgeHealth in src-tauri/src/bridge.rs)
+export interface BridgeHealth {
+  transport: string;
+  ready: boolean;
+  healthy: boolean;
+  pid: number | null;
+  uptimeMs: number | null;
+  protocolVersion: string | null;
+  pendingRequests: number;
+  restartCount: number;
+  recentStderr: string[];
+  lastError: string | null;
+  latencyMs: number | null;
+}
+
+// Command Error Types (mirrors CommandError in src-tauri/src/error.rs)
+export type CommandErrorCode =
+  | 'BRIDGE_UNAVAILABLE'
+  | 'BRIDGE_TIMEOUT'
+  | 'BRIDGE_INCOMPATIBLE'
+  | 'BRIDGE_ERROR'
+  | 'PROFILE_NOT_FOUND'
+  | 'SESSION_NOT_FOUND'
+  | 'AUTH_REQUIRED'
+  | 'PROVIDER_UNKNOWN'
+  | 'IO'
+  | 'VALIDATION'
+  | 'INTERNAL';
+
+export interface CommandError {
+  code: CommandErrorCode;
+  message: string;
+  details?: Record<string, unknown>;
+}
+
+export function isCommandError(error: unknown): error is CommandError {
+  return (
+    typeof error === 'object' &&
+    error !== null &&
+    typeof (error as CommandError).code === 'string' &&
+    typeof (error as CommandError).message === 'string'
+  );
+}
+
 // Model Types
 export interface Model {
   id: string;
@@ -0,0 +1,57 @@
+/**
+ * Length-prefixed framing of the host <-> bridge channel
+ *
+ * Every message is a 4-byte big-endian payload length followed by the
+ * payload (UTF-8 JSON-RPC). Unlike newline delimited output, frames are
+ * binary safe and can't be broken up by stray writes to stdout.
+ * See `framing.rs` for the host side.
+ */
+
+/** Largest payload either side accepts; guards against reading garbage as a length */
+export const MAX_FRAME_LENGTH = 64 * 1024 * 1024;
+
+const HEADER_LENGTH = 4;
+
+/**
+ * Encode one frame
+ */
+export function encodeFrame(payload: string | Uint8Array): Buffer {
+  const body = typeof payload === 'string' ? Buffer.from(payload, 'utf8') : Buffer.from(payload);
+  if (body.length > MAX_FRAME_LENGTH) {
+    throw new Error(`Frame of ${body.length} bytes exceeds the limit of ${MAX_FRAME_LENGTH} bytes`);
+  }
+
+  const header = Buffer.alloc(HEADER_LENGTH);
+  header.writeUInt32BE(body.length, 0);
+  return Buffer.concat([header, body]);
+}
+
+/**
+ * Splits a byte stream back into frames
+ */
+export class FrameDecoder {
+  private buffered: Buffer = Buffer.alloc(0);
+
+  /**
+   * Add received bytes and return the frames completed by them.
+   * Throws if the stream announces an oversized frame; framing is lost then.
+   */
+  push(chunk: Buffer): Buffer[] {
+    this.buffered = this.buffered.length === 0 ? chunk : Buffer.concat([this.buffered, chunk]);
+
+    const frames: Buffer[] = [];
+    while (this.buffered.length >= HEADER_LENGTH) {
+      const length = this.buffered.readUInt32BE(0);
+      if (length > MAX_FRAME_LENGTH) {
+        throw new Error(`Frame of ${length} bytes exceeds the limit of ${MAX_FRAME_LENGTH} bytes`);
+      }
+      if (this.buffered.length < HEADER_LENGTH + length) {
+        break;
+      }
+
+      frames.push(this.buffered.subarray(HEADER_LENGTH, HEADER_LENGTH + length));
+      this.buffered = this.buffered.subarray(HEADER_LENGTH + length);
+    }
+    return frames;
+  }
+}
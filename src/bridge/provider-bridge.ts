@@ -6,35 +6,123 @@
  * This is a JSON-RPC bridge service that connects the Tauri Rust backend
  * with the Node.js profile management and provider authentication system.
  *
- * Communication Protocol:
- * - Input: JSON-RPC requests via stdin (one per line)
- * - Output: JSON-RPC responses via stdout (one per line)
- * - Events: JSON events via stdout (one per line)
+ * Communication Protocol (JSON-RPC 2.0, one message per length-prefixed
+ * frame, see framing.ts):
+ * - Input: requests, notifications or batches of them
+ * - Output: responses (or batches of responses)
+ * - Notifications: bridge-initiated notifications (e.g. `ready`)
+ *
+ * The desktop app starts the bridge with `--channel unix:<path>` or
+ * `--channel tcp:<host>:<port>`. The bridge connects to that address and
+ * sends the token from `MULTICODER_BRIDGE_TOKEN` as its first frame. Protocol
+ * traffic never touches stdout, so output of provider libraries only ends
+ * up in the app's log.
+ *
+ * The `ready` notification carries the bridge's protocol version so the
+ * host can refuse to talk to an incompatible bridge.
+ *
+ * The app sets `MULTICODER_LOG_LEVEL` (and `MULTICODER_CONFIG_DIR` for a
+ * custom config directory) and passes on only an allowlist of its own
+ * environment; provider API keys are never inherited.
+ *
+ * A `$/cancelRequest` notification with `{ id }` aborts the handler of a
+ * request the host no longer waits for; the request then fails with
+ * `RequestCancelled`.
+ *
+ * Before the app exits it sends a `shutdown` request. The bridge stops the
+ * provider CLIs, writes its state, answers and closes the connection; a
+ * bridge started with `--channel` then exits.
+ *
+ * Started with `--listen unix:<path>` or `--listen tcp:<host>:<port>` the
+ * bridge instead serves the same protocol to every client connecting to the
+ * socket, e.g. a desktop app attaching to a bridge running under a debugger.
  */
 
-import * as readline from 'node:readline';
+import * as fs from 'node:fs';
+import * as net from 'node:net';
 import { ProfileManager } from '../profile/profileManager.js';
 import { CredentialManager } from '../auth/credentialManager.js';
+import { authRegistry } from '../auth/providers/registry.js';
+import { ProviderSessions, type PermissionMode } from './providerSessions.js';
+import { FrameDecoder, encodeFrame } from './framing.js';
 
 // ============================================================================
 // Types
 // ============================================================================
 
+/**
+ * Version of the host <-> bridge protocol. Bump the major version for
+ * breaking changes; the host refuses bridges with a different major version.
+ */
+export const PROTOCOL_VERSION = '2.0';
+
+const JSONRPC_VERSION = '2.0';
+
+/** Notification sent by the host to abort a request it stopped waiting for */
+const CANCEL_REQUEST_METHOD = '$/cancelRequest';
+
+/** Log level of the desktop app, e.g. `debug`; more verbose levels log every request */
+const DEBUG_LOGGING = ['debug', 'trace'].includes(process.env.MULTICODER_LOG_LEVEL ?? '');
+
+/** How long `shutdown` waits for provider CLIs to exit; the host allows 5 seconds in total */
+const SHUTDOWN_CLI_TIMEOUT_MS = 3000;
+
+type JsonRpcId = number | string | null;
+
 interface JsonRpcRequest {
-  id: number;
+  jsonrpc: typeof JSONRPC_VERSION;
+  id?: JsonRpcId;
   method: string;
-  params: Record<string, any>;
+  params?: Record<string, any>;
+}
+
+interface JsonRpcErrorObject {
+  code: number;
+  message: string;
+  data?: any;
 }
 
 interface JsonRpcResponse {
-  id: number;
+  jsonrpc: typeof JSONRPC_VERSION;
+  id: JsonRpcId;
   result?: any;
-  error?: string;
+  error?: JsonRpcErrorObject;
 }
 
-interface JsonRpcEvent {
-  event: string;
-  data: any;
+interface JsonRpcNotification {
+  jsonrpc: typeof JSONRPC_VERSION;
+  method: string;
+  params?: any;
+}
+
+/**
+ * Error codes returned in `error.code`. The standard JSON-RPC range is
+ * -32768..-32000; application errors use -32001 and below.
+ */
+export const ErrorCode = {
+  ParseError: -32700,
+  InvalidRequest: -32600,
+  MethodNotFound: -32601,
+  InvalidParams: -32602,
+  InternalError: -32603,
+  ProfileNotFound: -32001,
+  AuthRequired: -32002,
+  ProviderUnknown: -32003,
+  RequestCancelled: -32800,
+} as const;
+
+/**
+ * Error thrown by handlers to produce a structured JSON-RPC error
+ */
+export class RpcError extends Error {
+  constructor(
+    readonly code: number,
+    message: string,
+    readonly data?: any
+  ) {
+    super(message);
+    this.name = 'RpcError';
+  }
 }
 
 // ============================================================================
@@ -44,88 +132,275 @@ interface JsonRpcEvent {
 class BridgeService {
   private profileManager: ProfileManager;
   private credentialManager: CredentialManager;
+  private sessions: ProviderSessions;
+  /** Abort controllers of the requests being handled, by request id */
+  private inFlight = new Map<number | string, AbortController>();
+  /** Set once `shutdown` was answered; the connection is closed after that */
+  shutdownRequested = false;
 
-  constructor() {
+  constructor(private readonly output: (message: string) => void) {
     this.profileManager = new ProfileManager();
     this.credentialManager = this.profileManager.getCredentialManager();
+    this.sessions = new ProviderSessions((session, event) => {
+      this.sendNotification('message', {
+        profile: session.profile,
+        provider: session.provider,
+        sessionId: session.id,
+        event,
+      });
+    }, (session, reason) => {
+      this.sendNotification('sessionClosed', {
+        sessionId: session.id,
+        profile: session.profile,
+        provider: session.provider,
+        reason,
+      });
+    });
   }
 
   async initialize(): Promise<void> {
     await this.profileManager.initialize();
-    this.sendEvent('ready', { status: 'initialized' });
+    this.sendNotification('ready', { status: 'initialized', protocolVersion: PROTOCOL_VERSION });
   }
 
   /**
-   * Send a JSON-RPC response
+   * Write a single JSON-RPC message (or batch) to the host
    */
-  private sendResponse(id: number, result?: any, error?: string): void {
-    const response: JsonRpcResponse = { id };
-    if (error) {
-      response.error = error;
-    } else {
-      response.result = result;
-    }
-    console.log(JSON.stringify(response));
+  private write(message: JsonRpcResponse | JsonRpcNotification | JsonRpcResponse[]): void {
+    this.output(JSON.stringify(message));
+  }
+
+  /**
+   * Send a JSON-RPC notification
+   */
+  private sendNotification(method: string, params: any): void {
+    this.write({ jsonrpc: JSONRPC_VERSION, method, params });
   }
 
   /**
-   * Send a JSON event
+   * Handle one received frame: a single message or a batch
    */
-  private sendEvent(event: string, data: any): void {
-    const eventMessage: JsonRpcEvent = { event, data };
-    console.log(JSON.stringify(eventMessage));
+  async handleFrame(frame: string): Promise<void> {
+    let payload: unknown;
+    try {
+      payload = JSON.parse(frame);
+    } catch (error) {
+      this.write(this.errorResponse(null, new RpcError(ErrorCode.ParseError, 'Parse error')));
+      return;
+    }
+
+    if (Array.isArray(payload)) {
+      if (payload.length === 0) {
+        this.write(this.errorResponse(null, new RpcError(ErrorCode.InvalidRequest, 'Empty batch')));
+        return;
+      }
+
+      const responses = await Promise.all(payload.map((message) => this.handleMessage(message)));
+      const replies = responses.filter((response): response is JsonRpcResponse => response !== undefined);
+      // A batch of notifications gets no reply at all
+      if (replies.length > 0) {
+        this.write(replies);
+      }
+      return;
+    }
+
+    const response = await this.handleMessage(payload);
+    if (response) {
+      this.write(response);
+    }
   }
 
   /**
-   * Handle incoming JSON-RPC requests
+   * Handle one request or notification. Returns the response to send,
+   * or undefined for notifications.
    */
-  async handleRequest(request: JsonRpcRequest): Promise<void> {
-    const { id, method, params } = request;
+  private async handleMessage(message: unknown): Promise<JsonRpcResponse | undefined> {
+    if (!this.isRequest(message)) {
+      const id = (message as { id?: JsonRpcId } | null)?.id ?? null;
+      return this.errorResponse(id, new RpcError(ErrorCode.InvalidRequest, 'Invalid request'));
+    }
+
+    const isNotification = message.id === undefined;
+    const id = message.id ?? null;
+    if (DEBUG_LOGGING) {
+      console.error(`[Bridge] Handling ${message.method}${isNotification ? '' : ` (id ${id})`}`);
+    }
+    const controller = new AbortController();
+    if (id !== null) {
+      this.inFlight.set(id, controller);
+    }
 
     try {
-      switch (method) {
-        case 'listProfiles':
-          await this.handleListProfiles(id);
-          break;
+      const result = await this.cancellable(
+        this.dispatch(message.method, message.params ?? {}, controller.signal),
+        controller.signal
+      );
+      if (isNotification) {
+        return undefined;
+      }
+      return { jsonrpc: JSONRPC_VERSION, id, result: result ?? null };
+    } catch (error) {
+      if (isNotification) {
+        console.error(`[Bridge] Notification ${message.method} failed:`, error);
+        return undefined;
+      }
+      return this.errorResponse(id, error);
+    } finally {
+      if (id !== null && this.inFlight.get(id) === controller) {
+        this.inFlight.delete(id);
+      }
+    }
+  }
 
-        case 'createProfile':
-          await this.handleCreateProfile(id, params);
-          break;
+  /**
+   * Settle with `work`, or fail with `RequestCancelled` as soon as the request is cancelled
+   */
+  private cancellable<T>(work: Promise<T>, signal: AbortSignal): Promise<T> {
+    return new Promise<T>((resolve, reject) => {
+      const onAbort = () => reject(new RpcError(ErrorCode.RequestCancelled, 'Request cancelled'));
+      if (signal.aborted) {
+        onAbort();
+        return;
+      }
+      signal.addEventListener('abort', onAbort, { once: true });
+      work.then(resolve, reject).finally(() => signal.removeEventListener('abort', onAbort));
+    });
+  }
 
-        case 'switchProfile':
-          await this.handleSwitchProfile(id, params);
-          break;
+  private isRequest(message: unknown): message is JsonRpcRequest {
+    if (typeof message !== 'object' || message === null) {
+      return false;
+    }
+    const candidate = message as Record<string, unknown>;
+    return (
+      candidate.jsonrpc === JSONRPC_VERSION &&
+      typeof candidate.method === 'string' &&
+      (candidate.params === undefined || (typeof candidate.params === 'object' && candidate.params !== null))
+    );
+  }
 
-        case 'deleteProfile':
-          await this.handleDeleteProfile(id, params);
-          break;
+  private errorResponse(id: JsonRpcId, error: unknown): JsonRpcResponse {
+    const rpcError =
+      error instanceof RpcError
+        ? error
+        : new RpcError(ErrorCode.InternalError, error instanceof Error ? error.message : String(error));
 
-        case 'getCurrentProfile':
-          await this.handleGetCurrentProfile(id);
-          break;
+    const errorObject: JsonRpcErrorObject = { code: rpcError.code, message: rpcError.message };
+    if (rpcError.data !== undefined) {
+      errorObject.data = rpcError.data;
+    }
+    return { jsonrpc: JSONRPC_VERSION, id, error: errorObject };
+  }
 
-        case 'checkAuth':
-          await this.handleCheckAuth(id, params);
-          break;
+  /**
+   * Route a method call to its handler
+   */
+  private async dispatch(method: string, params: Record<string, any>, signal: AbortSignal): Promise<any> {
+    switch (method) {
+      case CANCEL_REQUEST_METHOD:
+        return this.handleCancelRequest(params);
 
-        case 'loginWithApiKey':
-          await this.handleLoginWithApiKey(id, params);
-          break;
+      case 'ping':
+        return { pid: process.pid, uptimeMs: Math.round(process.uptime() * 1000) };
 
-        case 'getAuthOptions':
-          await this.handleGetAuthOptions(id, params);
-          break;
+      case 'shutdown':
+        return this.handleShutdown();
 
-        case 'linkExistingCredential':
-          await this.handleLinkExistingCredential(id, params);
-          break;
+      case 'listProfiles':
+        return this.handleListProfiles();
 
-        default:
-          this.sendResponse(id, undefined, `Unknown method: ${method}`);
-      }
-    } catch (error) {
-      const errorMessage = error instanceof Error ? error.message : String(error);
-      this.sendResponse(id, undefined, errorMessage);
+      case 'createProfile':
+        return this.handleCreateProfile(params);
+
+      case 'switchProfile':
+        return this.handleSwitchProfile(params);
+
+      case 'deleteProfile':
+        return this.handleDeleteProfile(params);
+
+      case 'getCurrentProfile':
+        return this.handleGetCurrentProfile();
+
+      case 'reloadProfiles':
+        return this.handleReloadProfiles();
+
+      case 'checkAuth':
+        return this.handleCheckAuth(params);
+
+      case 'loginWithApiKey':
+        return this.handleLoginWithApiKey(params);
+
+      case 'getAuthOptions':
+        return this.handleGetAuthOptions(params);
+
+      case 'linkExistingCredential':
+        return this.handleLinkExistingCredential(params);
+
+      case 'launch':
+        return this.handleLaunch(params);
+
+      case 'sendMessage':
+        return this.handleSendMessage(params, signal);
+
+      case 'stop':
+        return this.handleStop(params);
+
+      case 'closeSession':
+        return this.handleCloseSession(params);
+
+      case 'listSessions':
+        return { sessions: this.sessions.list() };
+
+      case 'respondPermission':
+        return this.handleRespondPermission(params);
+
+      case 'setPermissionMode':
+        return this.handleSetPermissionMode(params);
+
+      default:
+        throw new RpcError(ErrorCode.MethodNotFound, `Unknown method: ${method}`);
+    }
+  }
+
+  // ============================================================================
+  // Cancellation
+  // ============================================================================
+
+  private handleCancelRequest(params: Record<string, any>): void {
+    const id = params.id;
+    if (typeof id !== 'number' && typeof id !== 'string') {
+      throw new RpcError(ErrorCode.InvalidParams, 'Missing required parameter: id');
+    }
+    // The request may already have finished; that is not an error
+    this.inFlight.get(id)?.abort();
+  }
+
+  // ============================================================================
+  // Validation Helpers
+  // ============================================================================
+
+  private requireString(params: Record<string, any>, key: string): string {
+    const value = params[key];
+    if (typeof value !== 'string' || value.length === 0) {
+      throw new RpcError(ErrorCode.InvalidParams, `Missing or invalid parameter: ${key}`);
+    }
+    return value;
+  }
+
+  private requireProfile(name: string) {
+    const profile = this.profileManager.get(name);
+    if (!profile) {
+      throw new RpcError(ErrorCode.ProfileNotFound, `Profile ${name} not found`, { profile: name });
+    }
+    return profile;
+  }
+
+  private requireProvider(provider: string): void {
+    if (!authRegistry.has(provider)) {
+      throw new RpcError(ErrorCode.ProviderUnknown, `Unknown provider: ${provider}`, {
+        provider,
+        supported: authRegistry.listIds(),
+      });
     }
   }
 
@@ -133,64 +408,66 @@ class BridgeService {
   // Profile Commands
   // ============================================================================
 
-  private async handleListProfiles(id: number): Promise<void> {
+  private async handleListProfiles(): Promise<any> {
     const profiles = this.profileManager.list();
-    this.sendResponse(id, { profiles });
+    return { profiles };
   }
 
-  private async handleCreateProfile(id: number, params: Record<string, any>): Promise<void> {
-    const { name, provider } = params as { name: string; provider: string };
+  private async handleCreateProfile(params: Record<string, any>): Promise<any> {
+    const name = this.requireString(params, 'name');
 
     // Create profile (without credentials initially)
     const profile = this.profileManager.ensure(name);
 
-    this.sendResponse(id, { profile });
+    return { profile };
   }
 
-  private async handleSwitchProfile(id: number, params: Record<string, any>): Promise<void> {
-    const { profileId } = params as { profileId: string };
+  private async handleSwitchProfile(params: Record<string, any>): Promise<any> {
+    const profileId = this.requireString(params, 'profileId');
+    this.requireProfile(profileId);
 
-    const result = await this.profileManager.switchProfile(profileId);
-    this.sendResponse(id, result);
+    return this.profileManager.switchProfile(profileId);
   }
 
-  private async handleDeleteProfile(id: number, params: Record<string, any>): Promise<void> {
-    const { profileId } = params as { profileId: string };
+  private async handleDeleteProfile(params: Record<string, any>): Promise<any> {
+    const profileId = this.requireString(params, 'profileId');
 
     const success = this.profileManager.delete(profileId);
-    this.sendResponse(id, { success });
+    return { success };
   }
 
-  private async handleGetCurrentProfile(id: number): Promise<void> {
+  private async handleGetCurrentProfile(): Promise<any> {
     const profile = this.profileManager.getCurrent();
-    this.sendResponse(id, { profile });
+    return { profile };
+  }
+
+  private async handleReloadProfiles(): Promise<any> {
+    // profiles.json was changed by another process, e.g. the CLI
+    await this.profileManager.load();
+    return { profile: this.profileManager.getCurrent() };
   }
 
   // ============================================================================
   // Auth Commands
   // ============================================================================
 
-  private async handleCheckAuth(id: number, params: Record<string, any>): Promise<void> {
-    const { provider, profileName } = params as { provider: string; profileName: string };
+  private async handleCheckAuth(params: Record<string, any>): Promise<any> {
+    const provider = this.requireString(params, 'provider');
+    const profileName = this.requireString(params, 'profileName');
 
     try {
       const valid = await this.profileManager.hasValidCredentialsForProvider(profileName, provider);
-      this.sendResponse(id, { valid });
+      return { valid };
     } catch (error) {
-      this.sendResponse(id, { valid: false });
+      return { valid: false };
     }
   }
 
-  private async handleLoginWithApiKey(
-    id: number,
-    params: Record<string, any>
-  ): Promise<void> {
-    const { profileName, provider, apiKey, metadata } = params as {
-      profileName: string;
-      provider: string;
-      apiKey: string;
-      metadata?: any
-    };
+  private async handleLoginWithApiKey(params: Record<string, any>): Promise<any> {
+    const profileName = this.requireString(params, 'profileName');
+    const provider = this.requireString(params, 'provider');
+    const apiKey = this.requireString(params, 'apiKey');
+    this.requireProvider(provider);
 
     try {
       // Check if profile exists, if not create it
@@ -205,84 +482,327 @@ class BridgeService {
         profile = this.profileManager.get(profileName);
       }
 
-      this.sendResponse(id, { success: true, profile });
+      return { success: true, profile };
     } catch (error) {
       const errorMessage = error instanceof Error ? error.message : String(error);
-      this.sendResponse(id, { success: false }, errorMessage);
+      throw new RpcError(ErrorCode.InternalError, errorMessage, { success: false });
     }
   }
 
-  private async handleGetAuthOptions(
-    id: number,
-    params: Record<string, any>
-  ): Promise<void> {
-    const { profileName, provider } = params as { profileName: string; provider: string };
+  private async handleGetAuthOptions(params: Record<string, any>): Promise<any> {
+    const profileName = this.requireString(params, 'profileName');
+    const provider = this.requireString(params, 'provider');
+    this.requireProvider(provider);
+    this.requireProfile(profileName);
+
+    const options = await this.profileManager.getAuthOptions(profileName, provider);
+    return { options };
+  }
+
+  private async handleLinkExistingCredential(params: Record<string, any>): Promise<any> {
+    const profileName = this.requireString(params, 'profileName');
+    const provider = this.requireString(params, 'provider');
+    const { copyToManaged } = params as { copyToManaged?: boolean };
+    this.requireProvider(provider);
+    this.requireProfile(profileName);
 
     try {
-      const options = await this.profileManager.getAuthOptions(profileName, provider);
-      this.sendResponse(id, { options });
+      const profile = await this.profileManager.linkExistingCredential(profileName, provider, {
+        copyToManaged,
+      });
+      return { success: true, profile };
     } catch (error) {
       const errorMessage = error instanceof Error ? error.message : String(error);
-      this.sendResponse(id, undefined, errorMessage);
+      // Nothing to link means the user still has to log in with the provider
+      const code = errorMessage.startsWith('No existing credentials')
+        ? ErrorCode.AuthRequired
+        : ErrorCode.InternalError;
+      throw new RpcError(code, errorMessage, { success: false, provider });
     }
   }
 
-  private async handleLinkExistingCredential(
-    id: number,
-    params: Record<string, any>
-  ): Promise<void> {
-    const { profileName, provider, copyToManaged } = params as {
-      profileName: string;
-      provider: string;
-      copyToManaged?: boolean;
-    };
+  // ============================================================================
+  // Chat Commands
+  // ============================================================================
 
-    try {
-      const profile = await this.profileManager.linkExistingCredential(profileName, provider, {
-        copyToManaged,
+  private async handleLaunch(params: Record<string, any>): Promise<any> {
+    const profileName = this.requireString(params, 'profile');
+    const provider = this.requireString(params, 'provider');
+    const config = (params.config ?? {}) as Record<string, any>;
+    this.requireProvider(provider);
+    const profile = this.requireProfile(profileName);
+
+    if (!ProviderSessions.supports(provider)) {
+      throw new RpcError(ErrorCode.InvalidParams, `Chat is not supported for provider ${provider}`, { provider });
+    }
+    if (!profile.providers[provider]) {
+      throw new RpcError(
+        ErrorCode.AuthRequired,
+        `Provider ${provider} is not configured for profile ${profileName}`,
+        { provider, profile: profileName }
+      );
+    }
+
+    const permissionMode: PermissionMode = ['ask', 'allow', 'deny'].includes(config.permissionMode)
+      ? config.permissionMode
+      : profile.permissionMode;
+    const workingDir = typeof config.workingDir === 'string' && config.workingDir ? config.workingDir : process.cwd();
+
+    const existing = this.sessions.get(profileName);
+    if (!existing || existing.provider !== provider) {
+      // Point the provider CLI at this profile's credentials
+      try {
+        await this.credentialManager.applyCredentials(provider, profileName);
+      } catch (error) {
+        const errorMessage = error instanceof Error ? error.message : String(error);
+        throw new RpcError(ErrorCode.AuthRequired, errorMessage, { provider, profile: profileName });
+      }
+    }
+
+    return this.sessions.launch(profileName, provider, { workingDir, permissionMode });
+  }
+
+  private async handleSendMessage(params: Record<string, any>, signal: AbortSignal): Promise<any> {
+    const profileName = this.requireString(params, 'profile');
+    const message = this.requireString(params, 'message');
+
+    if (!this.sessions.get(profileName)) {
+      throw new RpcError(ErrorCode.InvalidParams, `No session launched for profile ${profileName}`, {
+        profile: profileName,
       });
-      this.sendResponse(id, { success: true, profile });
+    }
+
+    // Nobody waits for the reply anymore; stop generating it
+    const stop = () => this.sessions.stop(profileName);
+    signal.addEventListener('abort', stop, { once: true });
+    try {
+      return await this.sessions.sendMessage(profileName, message);
+    } finally {
+      signal.removeEventListener('abort', stop);
+    }
+  }
+
+  private async handleStop(params: Record<string, any>): Promise<any> {
+    const profileName = this.requireString(params, 'profile');
+    return this.sessions.stop(profileName);
+  }
+
+  private async handleRespondPermission(params: Record<string, any>): Promise<any> {
+    const profileName = this.requireString(params, 'profile');
+    const actionId = this.requireString(params, 'actionId');
+    if (typeof params.approved !== 'boolean') {
+      throw new RpcError(ErrorCode.InvalidParams, 'Missing or invalid parameter: approved');
+    }
+
+    try {
+      return this.sessions.respondPermission(profileName, actionId, params.approved, params.applyToSession === true);
     } catch (error) {
       const errorMessage = error instanceof Error ? error.message : String(error);
-      this.sendResponse(id, { success: false }, errorMessage);
+      throw new RpcError(ErrorCode.InvalidParams, errorMessage, { profile: profileName, actionId });
     }
   }
+
+  private async handleSetPermissionMode(params: Record<string, any>): Promise<any> {
+    const profileName = this.requireString(params, 'profile');
+    const mode = this.requireString(params, 'mode');
+    if (mode !== 'ask' && mode !== 'allow' && mode !== 'deny') {
+      throw new RpcError(ErrorCode.InvalidParams, `Invalid permission mode: ${mode}`, { mode });
+    }
+    this.requireProfile(profileName);
+
+    const profile = await this.profileManager.setPermissionMode(profileName, mode);
+    this.sessions.setPermissionMode(profileName, mode);
+    return { profile };
+  }
+
+  private async handleCloseSession(params: Record<string, any>): Promise<any> {
+    const profileName = this.requireString(params, 'profile');
+    return { closed: this.sessions.close(profileName) };
+  }
+
+  /**
+   * Stop every provider CLI started by this bridge
+   */
+  closeSessions(): void {
+    this.sessions.closeAll();
+  }
+
+  // ============================================================================
+  // Lifecycle
+  // ============================================================================
+
+  private async handleShutdown(): Promise<any> {
+    await this.sessions.closeAllAndWait(SHUTDOWN_CLI_TIMEOUT_MS);
+    // Profile changes are saved in the background; make sure the last state is on disk
+    await this.profileManager.save();
+    this.shutdownRequested = true;
+    return { success: true };
+  }
 }
 
 // ============================================================================
 // Main Entry Point
 // ============================================================================
 
-async function main() {
-  const bridge = new BridgeService();
+/** Environment variable carrying the token to present on the `--channel` connection */
+const CHANNEL_TOKEN_ENV = 'MULTICODER_BRIDGE_TOKEN';
 
-  try {
-    await bridge.initialize();
-  } catch (error) {
-    console.error('[Bridge] Failed to initialize:', error);
-    process.exit(1);
+/**
+ * Feed the frames received on `socket` to a bridge; resolves when the socket closes
+ */
+function serve(bridge: BridgeService, socket: net.Socket): Promise<void> {
+  const decoder = new FrameDecoder();
+
+  socket.on('data', (chunk: Buffer) => {
+    let frames: Buffer[];
+    try {
+      frames = decoder.push(chunk);
+    } catch (error) {
+      console.error('[Bridge] Invalid frame, closing the connection:', error);
+      socket.destroy();
+      return;
+    }
+
+    for (const frame of frames) {
+      bridge
+        .handleFrame(frame.toString('utf8'))
+        .catch((error) => {
+          console.error('[Bridge] Error processing request:', error);
+        })
+        .finally(() => {
+          if (bridge.shutdownRequested) {
+            // Flushes the `shutdown` response before closing
+            socket.end();
+          }
+        });
+    }
+  });
+
+  return new Promise((resolve) => socket.on('close', () => resolve()));
+}
+
+type SocketAddress = { path: string } | { host: string; port: number };
+
+function parseAddress(option: string, spec: string): SocketAddress {
+  if (spec.startsWith('unix:') && spec.length > 'unix:'.length) {
+    return { path: spec.slice('unix:'.length) };
+  }
+
+  const match = /^tcp:(.+):(\d+)$/.exec(spec);
+  if (match) {
+    return { host: match[1].replace(/^\[(.*)\]$/, '$1'), port: Number(match[2]) };
   }
 
-  // Set up stdin/stdout for JSON-RPC communication
-  const rl = readline.createInterface({
-    input: process.stdin,
-    output: process.stdout,
-    terminal: false,
+  throw new Error(`Invalid ${option} address ${spec} (expected unix:<path> or tcp:<host>:<port>)`);
+}
+
+function optionValue(option: string, example: string): string | undefined {
+  const index = process.argv.indexOf(option);
+  if (index === -1) {
+    return undefined;
+  }
+  const value = process.argv[index + 1];
+  if (!value) {
+    throw new Error(`${option} needs an address, e.g. ${option} ${example}`);
+  }
+  return value;
+}
+
+/**
+ * Serve every client connecting to `address` with its own bridge instance
+ */
+async function listen(spec: string): Promise<void> {
+  const address = parseAddress('--listen', spec);
+  if ('host' in address && address.host !== 'localhost' && !['127.0.0.1', '::1'].includes(address.host)) {
+    // The protocol is unauthenticated
+    throw new Error(`Refusing to listen on non-loopback address ${address.host}`);
+  }
+  if ('path' in address && fs.existsSync(address.path)) {
+    // Left behind by a previous run
+    fs.unlinkSync(address.path);
+  }
+
+  const server = net.createServer((socket) => {
+    console.error('[Bridge] Client connected');
+    const bridge = new BridgeService((message) => socket.write(encodeFrame(message)));
+    socket.on('error', (error) => console.error('[Bridge] Client connection error:', error));
+
+    bridge
+      .initialize()
+      .then(() => serve(bridge, socket))
+      .catch((error) => console.error('[Bridge] Failed to initialize:', error))
+      .finally(() => {
+        console.error('[Bridge] Client disconnected');
+        bridge.closeSessions();
+        socket.destroy();
+      });
   });
 
-  rl.on('line', async (line) => {
-    try {
-      const request = JSON.parse(line) as JsonRpcRequest;
-      await bridge.handleRequest(request);
-    } catch (error) {
-      console.error('[Bridge] Error processing request:', error);
+  await new Promise<void>((resolve, reject) => {
+    server.once('error', reject);
+    if ('path' in address) {
+      server.listen(address.path, resolve);
+    } else {
+      server.listen(address.port, address.host, resolve);
     }
   });
+  console.error(`[Bridge] Listening on ${spec}`);
+}
 
-  rl.on('close', () => {
-    console.error('[Bridge] stdin closed, exiting...');
+/**
+ * Connect to the channel opened by the desktop app and serve it until it closes
+ */
+async function connectChannel(spec: string): Promise<void> {
+  const address = parseAddress('--channel', spec);
+  const token = process.env[CHANNEL_TOKEN_ENV] ?? '';
+  // Provider CLIs inherit our environment; they have no business with the token
+  delete process.env[CHANNEL_TOKEN_ENV];
+
+  const socket = await new Promise<net.Socket>((resolve, reject) => {
+    const socket =
+      'path' in address ? net.createConnection(address.path) : net.createConnection(address.port, address.host);
+    socket.once('connect', () => resolve(socket));
+    socket.once('error', reject);
+  });
+  socket.setNoDelay(true);
+  socket.on('error', (error) => console.error('[Bridge] Channel error:', error));
+  socket.write(encodeFrame(token));
+
+  const bridge = new BridgeService((message) => socket.write(encodeFrame(message)));
+  // Sent by the app when the shutdown request went unanswered, or on Linux when the app died
+  process.once('SIGTERM', () => {
+    console.error('[Bridge] Terminated, exiting...');
+    bridge.closeSessions();
     process.exit(0);
   });
+  try {
+    await bridge.initialize();
+  } catch (error) {
+    console.error('[Bridge] Failed to initialize:', error);
+    process.exit(1);
+  }
+
+  await serve(bridge, socket);
+
+  console.error('[Bridge] Channel closed, exiting...');
+  bridge.closeSessions();
+  process.exit(0);
+}
+
+async function main() {
+  const listenSpec = optionValue('--listen', 'unix:/tmp/multicoder-bridge.sock');
+  if (listenSpec) {
+    await listen(listenSpec);
+    return;
+  }
+
+  const channelSpec = optionValue('--channel', 'unix:/tmp/multicoder-channel.sock');
+  if (channelSpec) {
+    await connectChannel(channelSpec);
+    return;
+  }
+
+  throw new Error('Usage: provider-bridge --channel <address> | --listen <address>');
 }
 
 main().catch((error) => {
@@ -0,0 +1,585 @@
+/**
+ * Provider Sessions
+ *
+ * Runs chat turns against the native provider CLIs (claude, codex, gemini)
+ * in non-interactive mode and translates their output into ProviderEvents.
+ *
+ * One session exists per profile. Each `sendMessage` spawns the provider CLI
+ * for a single turn; the provider's own conversation id is remembered so the
+ * next turn resumes the same conversation.
+ */
+
+import { spawn, type ChildProcess } from 'node:child_process';
+import { randomUUID } from 'node:crypto';
+import * as readline from 'node:readline';
+
+// ============================================================================
+// Types
+// ============================================================================
+
+export type PermissionMode = 'ask' | 'allow' | 'deny';
+
+export type ActionType = 'file_write' | 'file_delete' | 'shell_exec' | 'tool_use';
+
+export type ProviderEvent =
+  | { type: 'text'; content: string }
+  | { type: 'file'; path: string; contents: string }
+  | { type: 'shell'; command: string }
+  | { type: 'ask'; id: string; reason: string; action: ActionType; command?: string; path?: string }
+  | { type: 'progress'; message: string }
+  | { type: 'error'; message: string; recoverable?: boolean }
+  | { type: 'done' };
+
+export interface SessionConfig {
+  workingDir: string;
+  permissionMode: PermissionMode;
+}
+
+export interface ProviderSession {
+  id: string;
+  profile: string;
+  provider: string;
+  config: SessionConfig;
+  startedAt: number;
+  /** Conversation id reported by the provider CLI, used to resume the next turn */
+  resumeId?: string;
+  /** CLI process of the turn currently being generated */
+  running?: ChildProcess;
+  /** Set by stop() so the exit of the running turn is not reported as a failure */
+  stopping?: boolean;
+  /** Tool of every unanswered permission request, keyed by action id */
+  pendingAsks: Map<string, string>;
+  /** Tools approved for the next turn only */
+  approvedTools: Set<string>;
+  /** Tools approved for the rest of the session */
+  allowedTools: Set<string>;
+  /** Set when the last permission request was answered while the turn was still running */
+  resumeAfterTurn?: boolean;
+}
+
+export interface SessionSummary {
+  sessionId: string;
+  profile: string;
+  provider: string;
+  workingDir: string;
+  permissionMode: PermissionMode;
+  startedAt: number;
+  running: boolean;
+}
+
+export type TurnStatus = 'completed' | 'stopped' | 'failed';
+
+export type EventSink = (session: ProviderSession, event: ProviderEvent) => void;
+
+export type CloseSink = (session: ProviderSession, reason: string) => void;
+
+interface ProviderRunner {
+  /** Executable name without platform suffix */
+  command: string;
+  /** Arguments for one turn; the user message is written to stdin */
+  buildArgs(session: ProviderSession): string[];
+  /** Translate one line of CLI output into events */
+  parseLine(line: string, session: ProviderSession): ProviderEvent[];
+}
+
+// ============================================================================
+// Provider Runners
+// ============================================================================
+
+const CLAUDE_PERMISSION_MODES: Record<PermissionMode, string> = {
+  ask: 'default',
+  allow: 'bypassPermissions',
+  deny: 'plan',
+};
+
+/** Message sent to the provider to continue after permissions were granted */
+const PERMISSION_GRANTED_MESSAGE = 'The requested permissions have been granted. Please continue.';
+
+function claudeAskEvent(id: string, name: string, input: Record<string, any>): ProviderEvent {
+  switch (name) {
+    case 'Bash': {
+      const command = String(input.command ?? '');
+      return { type: 'ask', id, reason: `Run shell command: ${command}`, action: 'shell_exec', command };
+    }
+    case 'Write':
+    case 'Edit':
+    case 'MultiEdit':
+    case 'NotebookEdit': {
+      const path = String(input.file_path ?? input.notebook_path ?? '');
+      return { type: 'ask', id, reason: `Modify file: ${path}`, action: 'file_write', path };
+    }
+    default:
+      return { type: 'ask', id, reason: `Use ${name}`, action: 'tool_use' };
+  }
+}
+
+function claudeToolEvent(name: string, input: Record<string, any>): ProviderEvent {
+  switch (name) {
+    case 'Bash':
+      return { type: 'shell', command: String(input.command ?? '') };
+    case 'Write':
+      return { type: 'file', path: String(input.file_path ?? ''), contents: String(input.content ?? '') };
+    case 'Edit':
+    case 'MultiEdit':
+      return { type: 'file', path: String(input.file_path ?? ''), contents: String(input.new_string ?? '') };
+    default:
+      return { type: 'progress', message: `Using ${name}` };
+  }
+}
+
+const claudeRunner: ProviderRunner = {
+  command: 'claude',
+
+  buildArgs(session) {
+    const args = [
+      '-p',
+      '--output-format',
+      'stream-json',
+      '--verbose',
+      '--permission-mode',
+      CLAUDE_PERMISSION_MODES[session.config.permissionMode],
+    ];
+    const tools = [...session.allowedTools, ...session.approvedTools];
+    if (tools.length > 0) {
+      args.push('--allowedTools', Array.from(new Set(tools)).join(','));
+    }
+    if (session.resumeId) {
+      args.push('--resume', session.resumeId);
+    }
+    return args;
+  },
+
+  parseLine(line, session) {
+    const message = parseJson(line);
+    if (!message) {
+      return [];
+    }
+
+    if (typeof message.session_id === 'string') {
+      session.resumeId = message.session_id;
+    }
+
+    switch (message.type) {
+      case 'assistant': {
+        const content: any[] = Array.isArray(message.message?.content) ? message.message.content : [];
+        return content.flatMap((block): ProviderEvent[] => {
+          if (block.type === 'text' && typeof block.text === 'string') {
+            return [{ type: 'text', content: block.text }];
+          }
+          if (block.type === 'tool_use') {
+            return [claudeToolEvent(String(block.name), block.input ?? {})];
+          }
+          return [];
+        });
+      }
+
+      case 'result': {
+        const events: ProviderEvent[] = [];
+        if (message.is_error) {
+          events.push({ type: 'error', message: String(message.result ?? 'Claude reported an error'), recoverable: true });
+        }
+        // Tools refused by the permission mode; the user decides and the turn is resumed
+        const denials: any[] = Array.isArray(message.permission_denials) ? message.permission_denials : [];
+        for (const denial of denials) {
+          const id = typeof denial.tool_use_id === 'string' ? denial.tool_use_id : randomUUID();
+          const tool = String(denial.tool_name ?? '');
+          session.pendingAsks.set(id, tool);
+          events.push(claudeAskEvent(id, tool, denial.tool_input ?? {}));
+        }
+        return events;
+      }
+
+      default:
+        return [];
+    }
+  },
+};
+
+const codexRunner: ProviderRunner = {
+  command: 'codex',
+
+  buildArgs(session) {
+    const args = ['exec', '--json', '--skip-git-repo-check'];
+    switch (session.config.permissionMode) {
+      case 'allow':
+        args.push('--full-auto');
+        break;
+      case 'deny':
+        args.push('--sandbox', 'read-only');
+        break;
+      default:
+        break;
+    }
+    if (session.resumeId) {
+      args.push('resume', session.resumeId);
+    }
+    // Read the prompt from stdin
+    args.push('-');
+    return args;
+  },
+
+  parseLine(line, session) {
+    const message = parseJson(line);
+    if (!message) {
+      return [];
+    }
+
+    switch (message.type) {
+      case 'thread.started':
+        if (typeof message.thread_id === 'string') {
+          session.resumeId = message.thread_id;
+        }
+        return [];
+
+      case 'item.started':
+      case 'item.completed': {
+        const item = message.item ?? {};
+        if (item.type === 'command_execution' && message.type === 'item.started') {
+          return [{ type: 'shell', command: String(item.command ?? '') }];
+        }
+        if (message.type !== 'item.completed') {
+          return [];
+        }
+        if (item.type === 'agent_message') {
+          return [{ type: 'text', content: String(item.text ?? '') }];
+        }
+        if (item.type === 'reasoning') {
+          return [{ type: 'progress', message: String(item.text ?? '') }];
+        }
+        if (item.type === 'file_change' && Array.isArray(item.changes)) {
+          return item.changes.map((change: any): ProviderEvent => ({
+            type: 'file',
+            path: String(change.path ?? ''),
+            contents: '',
+          }));
+        }
+        return [];
+      }
+
+      case 'turn.failed':
+        return [{ type: 'error', message: String(message.error?.message ?? 'Codex turn failed'), recoverable: true }];
+
+      case 'error':
+        return [{ type: 'error', message: String(message.message ?? 'Codex reported an error'), recoverable: true }];
+
+      default:
+        return [];
+    }
+  },
+};
+
+const geminiRunner: ProviderRunner = {
+  command: 'gemini',
+
+  buildArgs(session) {
+    // Gemini runs non-interactively when the prompt arrives on stdin
+    return session.config.permissionMode === 'allow' ? ['--yolo'] : [];
+  },
+
+  parseLine(line) {
+    return [{ type: 'text', content: `${line}\n` }];
+  },
+};
+
+const RUNNERS: Record<string, ProviderRunner> = {
+  claude: claudeRunner,
+  codex: codexRunner,
+  gemini: geminiRunner,
+};
+
+function parseJson(line: string): Record<string, any> | null {
+  try {
+    const value = JSON.parse(line);
+    return typeof value === 'object' && value !== null ? value : null;
+  } catch {
+    return null;
+  }
+}
+
+// ============================================================================
+// Session Manager
+// ============================================================================
+
+export class ProviderSessions {
+  private readonly sessions = new Map<string, ProviderSession>();
+
+  constructor(
+    private readonly emit: EventSink,
+    private readonly onClosed: CloseSink = () => {}
+  ) {}
+
+  /**
+   * Providers that can be used for chat sessions
+   */
+  static supports(provider: string): boolean {
+    return provider in RUNNERS;
+  }
+
+  /**
+   * Start a session for a profile, or reuse the existing one if the
+   * provider and configuration match
+   */
+  launch(profile: string, provider: string, config: SessionConfig): { session: SessionSummary; reused: boolean } {
+    const existing = this.sessions.get(profile);
+    if (
+      existing &&
+      existing.provider === provider &&
+      existing.config.workingDir === config.workingDir &&
+      existing.config.permissionMode === config.permissionMode
+    ) {
+      return { session: this.summarize(existing), reused: true };
+    }
+
+    if (existing) {
+      this.close(profile, 'replaced');
+    }
+
+    const session: ProviderSession = {
+      id: randomUUID(),
+      profile,
+      provider,
+      config,
+      startedAt: Date.now(),
+      pendingAsks: new Map(),
+      approvedTools: new Set(),
+      allowedTools: new Set(),
+    };
+    this.sessions.set(profile, session);
+    return { session: this.summarize(session), reused: false };
+  }
+
+  get(profile: string): ProviderSession | undefined {
+    return this.sessions.get(profile);
+  }
+
+  list(): SessionSummary[] {
+    return Array.from(this.sessions.values()).map((session) => this.summarize(session));
+  }
+
+  /**
+   * Run one chat turn. Events are streamed through the sink; the promise
+   * resolves once the provider CLI has finished.
+   */
+  async sendMessage(profile: string, message: string): Promise<{ sessionId: string; status: TurnStatus }> {
+    const session = this.sessions.get(profile);
+    if (!session) {
+      throw new Error(`No session launched for profile ${profile}`);
+    }
+    if (session.running) {
+      throw new Error(`A message is already being generated for profile ${profile}`);
+    }
+
+    // A new message leaves earlier permission requests unanswered for good
+    session.pendingAsks.clear();
+    session.resumeAfterTurn = false;
+
+    const runner = RUNNERS[session.provider];
+    const command = process.platform === 'win32' ? `${runner.command}.cmd` : runner.command;
+    const args = runner.buildArgs(session);
+    session.approvedTools.clear();
+    const child = spawn(command, args, {
+      cwd: session.config.workingDir,
+      env: process.env,
+      stdio: ['pipe', 'pipe', 'pipe'],
+      // .cmd shims need a shell on Windows; the message itself goes through stdin
+      shell: process.platform === 'win32',
+      windowsHide: true,
+    });
+
+    session.running = child;
+    session.stopping = false;
+
+    const stderrLines: string[] = [];
+    readline.createInterface({ input: child.stderr! }).on('line', (line) => {
+      stderrLines.push(line);
+      if (stderrLines.length > 20) {
+        stderrLines.shift();
+      }
+    });
+
+    readline.createInterface({ input: child.stdout! }).on('line', (line) => {
+      if (!line.trim()) {
+        return;
+      }
+      for (const event of runner.parseLine(line, session)) {
+        this.emit(session, event);
+      }
+    });
+
+    child.stdin!.on('error', () => {
+      // The CLI may exit before reading its input; the exit handler reports it
+    });
+    child.stdin!.end(message);
+
+    const status = await new Promise<TurnStatus>((resolve) => {
+      child.on('error', (error) => {
+        this.emit(session, {
+          type: 'error',
+          message: `Failed to start ${runner.command}: ${error.message}`,
+          recoverable: false,
+        });
+        resolve('failed');
+      });
+
+      child.on('close', (code) => {
+        if (session.stopping) {
+          resolve('stopped');
+        } else if (code === 0) {
+          resolve('completed');
+        } else {
+          const detail = stderrLines.join('\n').trim();
+          this.emit(session, {
+            type: 'error',
+            message: detail || `${runner.command} exited with code ${code}`,
+            recoverable: true,
+          });
+          resolve('failed');
+        }
+      });
+    });
+
+    session.running = undefined;
+    session.stopping = false;
+    this.emit(session, { type: 'done' });
+
+    // A failed first turn never started a conversation that could be resumed
+    if (status === 'failed' && this.sessions.get(profile) === session && !session.resumeId) {
+      this.close(profile, 'failed');
+    } else if (session.resumeAfterTurn) {
+      session.resumeAfterTurn = false;
+      this.resume(session);
+    }
+
+    return { sessionId: session.id, status };
+  }
+
+  /**
+   * Answer a permission request raised by the last turn. Once every request
+   * has been answered and at least one was approved, the conversation is
+   * resumed with the approved tools allowed.
+   */
+  respondPermission(
+    profile: string,
+    actionId: string,
+    approved: boolean,
+    applyToSession: boolean
+  ): { resumed: boolean } {
+    const session = this.sessions.get(profile);
+    if (!session) {
+      throw new Error(`No session launched for profile ${profile}`);
+    }
+    const tool = session.pendingAsks.get(actionId);
+    if (tool === undefined) {
+      throw new Error(`No pending permission request ${actionId}`);
+    }
+
+    session.pendingAsks.delete(actionId);
+    if (approved) {
+      session.approvedTools.add(tool);
+      if (applyToSession) {
+        session.allowedTools.add(tool);
+      }
+    }
+
+    if (session.pendingAsks.size > 0 || session.approvedTools.size === 0) {
+      return { resumed: false };
+    }
+    if (session.running) {
+      session.resumeAfterTurn = true;
+    } else {
+      this.resume(session);
+    }
+    return { resumed: true };
+  }
+
+  /**
+   * Update the permission mode used by the next turns of a profile's session
+   */
+  setPermissionMode(profile: string, permissionMode: PermissionMode): boolean {
+    const session = this.sessions.get(profile);
+    if (!session) {
+      return false;
+    }
+    session.config = { ...session.config, permissionMode };
+    return true;
+  }
+
+  private resume(session: ProviderSession): void {
+    this.sendMessage(session.profile, PERMISSION_GRANTED_MESSAGE).catch((error) => {
+      this.emit(session, {
+        type: 'error',
+        message: `Failed to resume after permission grant: ${error instanceof Error ? error.message : String(error)}`,
+        recoverable: true,
+      });
+    });
+  }
+
+  /**
+   * Abort the turn currently being generated for a profile
+   */
+  stop(profile: string): { stopped: boolean } {
+    const session = this.sessions.get(profile);
+    if (!session?.running) {
+      return { stopped: false };
+    }
+
+    session.stopping = true;
+    session.running.kill();
+    return { stopped: true };
+  }
+
+  /**
+   * Stop and forget the session of a profile
+   */
+  close(profile: string, reason = 'closed'): boolean {
+    const session = this.sessions.get(profile);
+    if (!session) {
+      return false;
+    }
+
+    this.stop(profile);
+    this.sessions.delete(profile);
+    this.onClosed(session, reason);
+    return true;
+  }
+
+  /**
+   * Stop every running turn, e.g. before the bridge exits
+   */
+  closeAll(reason = 'shutdown'): void {
+    for (const profile of Array.from(this.sessions.keys())) {
+      this.close(profile, reason);
+    }
+  }
+
+  /**
+   * Close every session and wait up to `timeoutMs` for the running CLIs to exit
+   */
+  async closeAllAndWait(timeoutMs: number, reason = 'shutdown'): Promise<void> {
+    const exits = Array.from(this.sessions.values())
+      .map((session) => session.running)
+      .filter((child): child is ChildProcess => child !== undefined && child.exitCode === null && child.signalCode === null)
+      .map((child) => new Promise<void>((resolve) => child.once('exit', () => resolve())));
+
+    this.closeAll(reason);
+
+    let timer: NodeJS.Timeout | undefined;
+    const timeout = new Promise<void>((resolve) => {
+      timer = setTimeout(resolve, timeoutMs);
+    });
+    await Promise.race([Promise.all(exits), timeout]);
+    clearTimeout(timer);
+  }
+
+  summarize(session: ProviderSession): SessionSummary {
+    return {
+      sessionId: session.id,
+      profile: session.profile,
+      provider: session.provider,
+      workingDir: session.config.workingDir,
+      permissionMode: session.config.permissionMode,
+      startedAt: session.startedAt,
+      running: Boolean(session.running),
+    };
+  }
+}
@@ -0,0 +1,187 @@
+/**
+ * Profiles lock - keeps writers of profiles.json from losing each other's changes
+ *
+ * The CLI, the bridge and the desktop app all change profiles.json. Each of them
+ * reads, changes and writes the file while holding this lock. The lock is held by
+ * whoever created profiles.json.lock (exclusive create) and released by removing
+ * it. The file holds the owner's PID and a token of its own. A lock is taken over
+ * only once its owner is gone, and only while holding profiles.json.lock.break, so
+ * two waiters cannot both remove it and one of them delete the lock the other just
+ * took. Release removes the lock only while it still holds our token.
+ *
+ * The desktop app implements the same protocol in src-tauri/src/profile_store.rs.
+ */
+
+import { randomUUID } from 'node:crypto';
+import fs from 'node:fs';
+import path from 'node:path';
+
+export const LOCK_FILE = 'profiles.json.lock';
+
+/** Held while taking over a lock whose owner is gone */
+const BREAK_FILE = 'profiles.json.lock.break';
+
+/** A lock file without a PID this old was left behind by a crash right after creating it */
+const LOCK_UNWRITTEN_MS = 10_000;
+
+const LOCK_TIMEOUT_MS = 15_000;
+
+const RETRY_MS = 10;
+
+function isAlive(pid: number): boolean {
+  try {
+    process.kill(pid, 0);
+    return true;
+  } catch (error: any) {
+    // EPERM: the process exists but belongs to someone else
+    return error?.code === 'EPERM';
+  }
+}
+
+/**
+ * Exclusively create a lock file holding `contents`; false if it already exists
+ */
+function createLockFile(lockPath: string, contents: string): boolean {
+  let fd: number;
+  try {
+    fd = fs.openSync(lockPath, 'wx', 0o600);
+  } catch (error: any) {
+    if (error?.code === 'EEXIST') {
+      return false;
+    }
+    throw error;
+  }
+  try {
+    fs.writeSync(fd, contents);
+  } catch (error) {
+    fs.rmSync(lockPath, { force: true });
+    throw error;
+  } finally {
+    fs.closeSync(fd);
+  }
+  return true;
+}
+
+/**
+ * Whether the lock file at `lockPath` was left behind by a process that is gone
+ */
+function abandoned(lockPath: string): boolean {
+  try {
+    const owner = Number.parseInt(fs.readFileSync(lockPath, 'utf-8'), 10);
+    if (Number.isNaN(owner)) {
+      // The owner writes its PID right after creating the file
+      return Date.now() - fs.statSync(lockPath).mtimeMs > LOCK_UNWRITTEN_MS;
+    }
+    return owner > 0 && !isAlive(owner);
+  } catch {
+    // Released while we looked at it
+    return false;
+  }
+}
+
+/**
+ * Remove the lock if its owner is gone, so the next attempt can succeed
+ */
+function breakAbandoned(configDir: string): void {
+  const lockPath = path.join(configDir, LOCK_FILE);
+  if (!abandoned(lockPath)) {
+    return;
+  }
+
+  const breakPath = path.join(configDir, BREAK_FILE);
+  try {
+    if (!createLockFile(breakPath, `${process.pid}\n`)) {
+      // Someone else is taking it over; clear their breaker only if they died doing so
+      if (abandoned(breakPath)) {
+        fs.rmSync(breakPath, { force: true });
+      }
+      return;
+    }
+  } catch {
+    return;
+  }
+  try {
+    // Nobody else removes the lock while we hold the breaker, and an abandoned lock
+    // stays abandoned, so it is still the one we looked at if this holds
+    if (abandoned(lockPath)) {
+      fs.rmSync(lockPath, { force: true });
+    }
+  } finally {
+    fs.rmSync(breakPath, { force: true });
+  }
+}
+
+/**
+ * Try to take the lock once; takes over an abandoned lock so the next attempt can succeed
+ */
+function tryAcquire(configDir: string, contents: string): boolean {
+  if (createLockFile(path.join(configDir, LOCK_FILE), contents)) {
+    return true;
+  }
+  breakAbandoned(configDir);
+  return false;
+}
+
+function timeoutError(lockPath: string): Error {
+  return new Error(`Timed out waiting for ${lockPath}; remove it if no other multicoder process is running`);
+}
+
+function release(lockPath: string, contents: string): void {
+  try {
+    // Only remove the lock if it is still ours
+    if (fs.readFileSync(lockPath, 'utf-8') === contents) {
+      fs.rmSync(lockPath, { force: true });
+    }
+  } catch {
+    // Already gone
+  }
+}
+
+function lockContents(): string {
+  return `${process.pid} ${randomUUID()}\n`;
+}
+
+/**
+ * Run `fn` while holding the profiles lock of `configDir`
+ */
+export async function withProfilesLock<T>(configDir: string, fn: () => Promise<T> | T): Promise<T> {
+  await fs.promises.mkdir(configDir, { recursive: true, mode: 0o700 });
+  const lockPath = path.join(configDir, LOCK_FILE);
+  const deadline = Date.now() + LOCK_TIMEOUT_MS;
+  const contents = lockContents();
+  while (!tryAcquire(configDir, contents)) {
+    if (Date.now() > deadline) {
+      throw timeoutError(lockPath);
+    }
+    await new Promise((resolve) => setTimeout(resolve, RETRY_MS));
+  }
+
+  try {
+    return await fn();
+  } finally {
+    release(lockPath, contents);
+  }
+}
+
+/**
+ * Synchronous variant of withProfilesLock for the synchronous ProfileStore
+ */
+export function withProfilesLockSync<T>(configDir: string, fn: () => T): T {
+  fs.mkdirSync(configDir, { recursive: true, mode: 0o700 });
+  const lockPath = path.join(configDir, LOCK_FILE);
+  const deadline = Date.now() + LOCK_TIMEOUT_MS;
+  const sleeper = new Int32Array(new SharedArrayBuffer(4));
+  const contents = lockContents();
+  while (!tryAcquire(configDir, contents)) {
+    if (Date.now() > deadline) {
+      throw timeoutError(lockPath);
+    }
+    Atomics.wait(sleeper, 0, 0, RETRY_MS);
+  }
+
+  try {
+    return fn();
+  } finally {
+    release(lockPath, contents);
+  }
+}
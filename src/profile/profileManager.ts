@@ -2,6 +2,7 @@ import { promises as fs } from 'node:fs';
 import path from 'node:path';
 import os from 'node:os';
 import { CredentialManager } from '../auth/credentialManager.js';
+import { withProfilesLock } from './lock.js';
 
 export interface Profile {
   name: string;
@@ -34,12 +35,19 @@ export class ProfileManager {
   credentialManager: CredentialManager;
   configDir: string;
   private readonly usingCustomConfigDir: boolean;
+  /** Changes applied in memory but not yet written to profiles.json, oldest first */
+  private unsaved: Array<() => void> = [];
+  /** This manager's writes, one at a time */
+  private saving: Promise<void> = Promise.resolve();
 
   constructor(options: ProfileManagerOptions = {}) {
     this.defaultPermissionMode = options.defaultPermissionMode ?? 'ask';
     this.credentialManager = options.credentialManager ?? new CredentialManager();
-    this.configDir = options.configDir ?? this.getDefaultConfigDir();
-    this.usingCustomConfigDir = Boolean(options.configDir);
+    // Same directory as the credential manager, so profiles and credentials stay together
+    const envConfigDir = process.env.MULTICODER_CONFIG_DIR ?? process.env.UNYCODING_CONFIG_DIR;
+    const resolvedEnvDir = envConfigDir ? path.resolve(envConfigDir) : undefined;
+    this.configDir = options.configDir ?? resolvedEnvDir ?? this.getDefaultConfigDir();
+    this.usingCustomConfigDir = Boolean(options.configDir ?? resolvedEnvDir);
   }
 
   getDefaultConfigDir(): string {
@@ -113,25 +121,15 @@ export class ProfileManager {
   }
 
   setCurrent(profile: Profile | null): void {
-    if (!profile) {
-      this.current = null;
-      return;
-    }
-    const stored = this.profiles.get(profile.name);
-    this.current = stored ?? profile;
+    this.commit(() => {
+      this.current = profile ? this.profiles.get(profile.name) ?? profile : null;
+    }).catch(() => {}); // Auto-save (ignore errors)
   }
 
   ensure(name: string): Profile {
     const normalized = name.trim();
-    const existing = this.profiles.get(normalized);
-    if (existing) {
-      this.current = existing;
-      this.save().catch(() => {}); // Auto-save (ignore errors)
-      return existing;
-    }
-
     const now = Date.now();
-    const profile: Profile = {
+    const created: Profile = {
       name: normalized,
       providers: {},
       model: undefined,
@@ -140,10 +138,12 @@ export class ProfileManager {
       updatedAt: now,
     };
 
-    this.profiles.set(normalized, profile);
-    this.current = profile;
-    this.save().catch(() => {}); // Auto-save (ignore errors)
-    return profile;
+    this.commit(() => {
+      const profile = this.profiles.get(normalized) ?? created;
+      this.profiles.set(normalized, profile);
+      this.current = profile;
+    }).catch(() => {}); // Auto-save (ignore errors)
+    return this.profiles.get(normalized) ?? created;
   }
 
   update(profile: Profile): void {
@@ -152,11 +152,16 @@ export class ProfileManager {
     }
 
     const next = { ...profile, updatedAt: Date.now() };
-    this.profiles.set(profile.name, next);
-    if (this.current?.name === profile.name) {
-      this.current = next;
-    }
-    this.save().catch(() => {}); // Auto-save (ignore errors)
+    this.commit(() => {
+      // Deleted by another process in the meantime
+      if (!this.profiles.has(next.name)) {
+        return;
+      }
+      this.profiles.set(next.name, next);
+      if (this.current?.name === next.name) {
+        this.current = next;
+      }
+    }).catch(() => {}); // Auto-save (ignore errors)
   }
 
   delete(name: string): boolean {
@@ -165,14 +170,35 @@ export class ProfileManager {
       return false;
     }
 
-    this.profiles.delete(name);
-    if (this.current?.name === name) {
-      this.current = null;
-    }
-    this.save().catch(() => {}); // Auto-save (ignore errors)
+    this.commit(() => {
+      this.profiles.delete(name);
+      if (this.current?.name === name) {
+        this.current = null;
+      }
+    }).catch(() => {}); // Auto-save (ignore errors)
     return true;
   }
 
+  /**
+   * Change the stored profile `name` and persist the change; only the fields
+   * `change` touches are written over what other processes stored
+   */
+  private updateProfile(name: string, change: (profile: Profile) => void): Promise<void> {
+    const updatedAt = Date.now();
+    return this.commit(() => {
+      const stored = this.profiles.get(name);
+      if (!stored) {
+        return;
+      }
+      const next = { ...stored, providers: { ...stored.providers }, updatedAt };
+      change(next);
+      this.profiles.set(name, next);
+      if (this.current?.name === name) {
+        this.current = next;
+      }
+    });
+  }
+
   /**
    * Switch to a different profile
    * Returns information about whether a process restart is needed
@@ -221,14 +247,19 @@ export class ProfileManager {
       }
     }
 
-    // Update last used timestamp
-    profile.lastUsedAt = Date.now();
-    this.update(profile);
-
-    // Set as current
-    this.current = profile;
+    // Update last used timestamp and set as current
+    const lastUsedAt = Date.now();
+    await this.commit(() => {
+      const stored = this.profiles.get(name);
+      if (stored) {
+        const next = { ...stored, lastUsedAt, updatedAt: lastUsedAt };
+        this.profiles.set(name, next);
+        this.current = next;
+      }
+    });
 
-    return { profile, needsRestart, envVars: allEnvVars, appliedProviders, errors };
+    const switched = this.profiles.get(name) ?? profile;
+    return { profile: switched, needsRestart, envVars: allEnvVars, appliedProviders, errors };
   }
 
   /**
@@ -332,7 +363,9 @@ export class ProfileManager {
       updatedAt: now,
     };
 
-    this.profiles.set(name, profile);
+    await this.commit(() => {
+      this.profiles.set(name, profile);
+    });
     return profile;
   }
 
@@ -384,7 +417,9 @@ export class ProfileManager {
       updatedAt: now,
     };
 
-    this.profiles.set(name, profile);
+    await this.commit(() => {
+      this.profiles.set(name, profile);
+    });
     return profile;
   }
 
@@ -558,14 +593,14 @@ export class ProfileManager {
       // Ignore metadata lookup errors
     }
 
-    profile.providers[providerId] = providerAuth;
+    await this.updateProfile(profileName, (stored) => {
+      stored.providers[providerId] = providerAuth;
 
-    // Update last provider if this is the first provider
-    if (!profile.lastProvider) {
-      profile.lastProvider = providerId;
-    }
-
-    this.update(profile);
+      // Update last provider if this is the first provider
+      if (!stored.lastProvider) {
+        stored.lastProvider = providerId;
+      }
+    });
   }
 
   /**
@@ -688,15 +723,15 @@ export class ProfileManager {
       throw new Error(`Provider ${providerId} not found in profile ${profileName}`);
     }
 
-    delete profile.providers[providerId];
+    this.updateProfile(profileName, (stored) => {
+      delete stored.providers[providerId];
 
-    // Update last provider if it was the removed one
-    if (profile.lastProvider === providerId) {
-      const remainingProviders = Object.keys(profile.providers);
-      profile.lastProvider = remainingProviders.length > 0 ? remainingProviders[0] : undefined;
-    }
-
-    this.update(profile);
+      // Update last provider if it was the removed one
+      if (stored.lastProvider === providerId) {
+        const remainingProviders = Object.keys(stored.providers);
+        stored.lastProvider = remainingProviders.length > 0 ? remainingProviders[0] : undefined;
+      }
+    }).catch(() => {}); // Auto-save (ignore errors)
   }
 
   /**
@@ -712,53 +747,113 @@ export class ProfileManager {
       throw new Error(`Provider ${providerId} not found in profile ${profileName}`);
     }
 
-    profile.lastProvider = providerId;
-    this.update(profile);
+    this.updateProfile(profileName, (stored) => {
+      stored.lastProvider = providerId;
+    }).catch(() => {}); // Auto-save (ignore errors)
   }
 
   /**
-   * Save all profiles to profiles.json
+   * Set and persist the permission mode of a profile
    */
-  async save(): Promise<void> {
-    await fs.mkdir(this.configDir, { recursive: true, mode: 0o700 });
-    const data = {
-      current: this.current?.name ?? null,
-      profiles: this.list(),
-    };
-    const profilesPath = path.join(this.configDir, 'profiles.json');
-    await fs.writeFile(profilesPath, JSON.stringify(data, null, 2), { mode: 0o600 });
+  async setPermissionMode(profileName: string, permissionMode: Profile['permissionMode']): Promise<Profile> {
+    const profile = this.profiles.get(profileName);
+    if (!profile) {
+      throw new Error(`Profile ${profileName} not found`);
+    }
+
+    await this.updateProfile(profileName, (stored) => {
+      stored.permissionMode = permissionMode;
+    });
+    return this.profiles.get(profileName) ?? { ...profile, permissionMode };
   }
 
   /**
-   * Load profiles from profiles.json
+   * Apply `change` to the in-memory profiles and persist it. Before writing,
+   * profiles.json is read again under the profiles lock and every unsaved change
+   * applied to it, so changes made by other processes (the CLI, the desktop app)
+   * are kept; `change` must therefore look profiles up by name.
    */
-  async load(): Promise<void> {
-    const profilesPath = path.join(this.configDir, 'profiles.json');
-    try {
-      const content = await fs.readFile(profilesPath, 'utf-8');
-      const data = JSON.parse(content);
+  private commit(change: () => void): Promise<void> {
+    change();
+    this.unsaved.push(change);
+    return this.save();
+  }
 
-      // Clear existing profiles
-      this.profiles.clear();
+  /**
+   * Write pending profile changes to profiles.json
+   */
+  async save(): Promise<void> {
+    const run = this.saving.then(() => this.writeUnsaved());
+    this.saving = run.catch(() => {});
+    return run;
+  }
 
-      // Load profiles
-      for (const profile of data.profiles) {
-        this.profiles.set(profile.name, profile);
-      }
+  private async writeUnsaved(): Promise<void> {
+    if (this.unsaved.length === 0) {
+      return;
+    }
 
-      // Restore current profile
-      if (data.current) {
-        const currentProfile = this.profiles.get(data.current);
-        if (currentProfile) {
-          this.current = currentProfile;
-        }
+    await withProfilesLock(this.configDir, async () => {
+      const content = await this.readStore();
+      // Synchronous up to the write: changes made while writing stay unsaved for the next one
+      this.apply(content);
+      const written = this.unsaved.length;
+      const data = {
+        current: this.current?.name ?? null,
+        profiles: this.list(),
+      };
+
+      // Replace the file in one step; the desktop app watches and reads it concurrently
+      const profilesPath = path.join(this.configDir, 'profiles.json');
+      const tempPath = `${profilesPath}.${process.pid}.tmp`;
+      try {
+        await fs.writeFile(tempPath, JSON.stringify(data, null, 2), { mode: 0o600 });
+        await fs.rename(tempPath, profilesPath);
+      } catch (error) {
+        await fs.rm(tempPath, { force: true });
+        throw error;
       }
+      this.unsaved.splice(0, written);
+    });
+  }
+
+  /**
+   * Load profiles from profiles.json, keeping changes not written yet
+   */
+  async load(): Promise<void> {
+    this.apply(await this.readStore());
+  }
+
+  /**
+   * Contents of profiles.json, or null if it doesn't exist yet (first run)
+   */
+  private async readStore(): Promise<string | null> {
+    try {
+      return await fs.readFile(path.join(this.configDir, 'profiles.json'), 'utf-8');
     } catch (error: any) {
-      if (error.code !== 'ENOENT') {
-        // Only throw if it's not a "file not found" error
-        throw error;
+      if (error.code === 'ENOENT') {
+        return null;
       }
-      // File doesn't exist yet, which is fine for first run
+      throw error;
+    }
+  }
+
+  /**
+   * Replace the in-memory profiles with `content` and apply the unsaved changes on top
+   */
+  private apply(content: string | null): void {
+    const data = content ? JSON.parse(content) : { current: null, profiles: [] };
+
+    this.profiles.clear();
+    for (const profile of data.profiles ?? []) {
+      this.profiles.set(profile.name, profile);
+    }
+
+    // Restore current profile; reloading after another process cleared it clears it here too
+    this.current = (data.current && this.profiles.get(data.current)) || null;
+
+    for (const change of this.unsaved) {
+      change();
     }
   }
 
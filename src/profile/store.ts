@@ -8,6 +8,7 @@
 import fs from 'node:fs';
 import path from 'node:path';
 import os from 'node:os';
+import { withProfilesLockSync } from './lock.js';
 import type { ProviderAuthInfo, ProfileData, ProfileStoreData } from './types.js';
 
 export class ProfileStore {
@@ -238,7 +239,7 @@ export class ProfileStore {
   }
 
   /**
-   * Save profiles to disk
+   * Save profiles to disk; only called from mutate(), under the profiles lock
    */
   private save(): void {
     try {
@@ -246,12 +247,33 @@ export class ProfileStore {
       if (!fs.existsSync(dir)) {
         fs.mkdirSync(dir, { recursive: true });
       }
-      fs.writeFileSync(this.storePath, JSON.stringify(this.serializeData(), null, 2), 'utf-8');
+      // Replace the file in one step; the desktop app watches and reads it concurrently
+      const tempPath = `${this.storePath}.${process.pid}.tmp`;
+      try {
+        fs.writeFileSync(tempPath, JSON.stringify(this.serializeData(), null, 2), 'utf-8');
+        fs.renameSync(tempPath, this.storePath);
+      } catch (error) {
+        fs.rmSync(tempPath, { force: true });
+        throw error;
+      }
     } catch (error) {
       throw new Error(`Failed to save profiles to ${this.storePath}: ${error}`);
     }
   }
 
+  /**
+   * Re-read profiles.json and apply `change` to it while holding the profiles lock,
+   * so changes other processes made since this store was loaded are kept
+   */
+  private mutate<T>(change: (data: ProfileStoreData) => T): T {
+    return withProfilesLockSync(path.dirname(this.storePath), () => {
+      this.data = this.load();
+      const result = change(this.data);
+      this.save();
+      return result;
+    });
+  }
+
   /**
    * Get current profile
    */
@@ -273,11 +295,12 @@ export class ProfileStore {
    * Set current profile
    */
   setCurrent(name: string | null): void {
-    if (name !== null && !this.data.profiles[name]) {
-      throw new Error(`Profile '${name}' does not exist`);
-    }
-    this.data.currentProfile = name;
-    this.save();
+    this.mutate((data) => {
+      if (name !== null && !data.profiles[name]) {
+        throw new Error(`Profile '${name}' does not exist`);
+      }
+      data.currentProfile = name;
+    });
   }
 
   /**
@@ -298,45 +321,46 @@ export class ProfileStore {
    * Create a new profile
    */
   create(name: string): ProfileData {
-    if (this.data.profiles[name]) {
-      throw new Error(`Profile '${name}' already exists`);
-    }
+    return this.mutate((data) => {
+      if (data.profiles[name]) {
+        throw new Error(`Profile '${name}' already exists`);
+      }
 
-    const now = Date.now();
-    const profile: ProfileData = {
-      name,
-      providers: {},
-      createdAt: now,
-      updatedAt: now,
-    };
+      const now = Date.now();
+      const profile: ProfileData = {
+        name,
+        providers: {},
+        createdAt: now,
+        updatedAt: now,
+      };
 
-    this.data.profiles[name] = profile;
+      data.profiles[name] = profile;
 
-    // Set as current if it's the first profile
-    if (Object.keys(this.data.profiles).length === 1) {
-      this.data.currentProfile = name;
-    }
+      // Set as current if it's the first profile
+      if (Object.keys(data.profiles).length === 1) {
+        data.currentProfile = name;
+      }
 
-    this.save();
-    return profile;
+      return profile;
+    });
   }
 
   /**
    * Update a profile
    */
   update(name: string, updater: (profile: ProfileData) => ProfileData): ProfileData {
-    const existing = this.data.profiles[name];
-    if (!existing) {
-      throw new Error(`Profile '${name}' does not exist`);
-    }
-
-    const updated = updater(existing);
-    updated.updatedAt = Date.now();
+    return this.mutate((data) => {
+      const existing = data.profiles[name];
+      if (!existing) {
+        throw new Error(`Profile '${name}' does not exist`);
+      }
 
-    this.data.profiles[name] = updated;
-    this.save();
+      const updated = updater(existing);
+      updated.updatedAt = Date.now();
 
-    return updated;
+      data.profiles[name] = updated;
+      return updated;
+    });
   }
 
   /**
@@ -368,20 +392,20 @@ export class ProfileStore {
       console.warn(`⚠️  Could not delete credential files: ${error instanceof Error ? error.message : String(error)}`);
     }
 
-    delete this.data.profiles[name];
+    this.mutate((data) => {
+      delete data.profiles[name];
 
-    // If current profile was deleted, set to null
-    if (this.data.currentProfile === name) {
-      this.data.currentProfile = null;
+      // If current profile was deleted, set to null
+      if (data.currentProfile === name) {
+        data.currentProfile = null;
 
-      // Auto-select another profile if available
-      const remaining = Object.keys(this.data.profiles);
-      if (remaining.length > 0) {
-        this.data.currentProfile = remaining[0];
+        // Auto-select another profile if available
+        const remaining = Object.keys(data.profiles);
+        if (remaining.length > 0) {
+          data.currentProfile = remaining[0];
+        }
       }
-    }
-
-    this.save();
+    });
   }
 
   /**